@@ -0,0 +1,84 @@
+//! Tests for attenuverter, rectifier, inverter, sum/difference and constant modules
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{
+        GraphAttenuverter, GraphConstant, GraphInverter, GraphRectifier, GraphSumDiff,
+    };
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    #[test]
+    fn test_attenuverter_cv_inverts_and_offsets() {
+        let mut atten = GraphAttenuverter::default();
+        atten.set_param("offset", 0.5).unwrap();
+        let outputs = process_module(
+            &mut atten,
+            &[("in", vec![1.0, 1.0, 1.0, 1.0]), ("cv", vec![1.0, 0.5, 0.0, -1.0])],
+            4,
+        );
+
+        assert_eq!(outputs.get("out").unwrap(), &vec![1.5, 1.0, 0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_attenuverter_gain_is_clamped() {
+        let mut atten = GraphAttenuverter::new(0.0, 1.0);
+        let outputs =
+            process_module(&mut atten, &[("in", vec![2.0, 2.0]), ("cv", vec![-1.0, 3.0])], 2);
+
+        assert_eq!(outputs.get("out").unwrap(), &vec![0.0, 2.0]);
+    }
+
+    #[test]
+    fn test_rectifier_inverter_and_sumdiff() {
+        let signal = vec![-0.5, 0.25];
+
+        let outputs = process_module(&mut GraphRectifier::new(), &[("in", signal.clone())], 2);
+        assert_eq!(outputs.get("half").unwrap(), &vec![0.0, 0.25]);
+        assert_eq!(outputs.get("full").unwrap(), &vec![0.5, 0.25]);
+
+        let outputs = process_module(&mut GraphInverter::new(), &[("in", signal.clone())], 2);
+        assert_eq!(outputs.get("out").unwrap(), &vec![0.5, -0.25]);
+
+        let outputs =
+            process_module(&mut GraphSumDiff::new(), &[("a", signal), ("b", vec![1.0, 1.0])], 2);
+        assert_eq!(outputs.get("sum").unwrap(), &vec![0.5, 1.25]);
+        assert_eq!(outputs.get("diff").unwrap(), &vec![-1.5, -0.75]);
+    }
+
+    #[test]
+    fn test_constant_with_cv() {
+        let outputs = process_module(&mut GraphConstant::new(2.0), &[("cv", vec![0.0, 1.0])], 2);
+        assert_eq!(outputs.get("out").unwrap(), &vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_modulated_attenuverter_patch() {
+        let mut runner = TestRunner::new();
+
+        // Slow LFO sweeps the gain of a faster LFO through zero
+        let patch = r"
+            lfo: lfo 5.0
+            depth: lfo 0.5
+            atten: attenuverter
+            atten.in <- lfo.sine
+            atten.cv <- depth.sine
+            level: constant 0.25
+            out <- atten.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Attenuverter patch should load and run");
+
+        assert!(result.collector.signal_varied("atten", "out"), "Output should vary");
+        result
+            .assert_signal_range("atten", "out", -1.01, 1.01)
+            .expect("Gain should stay within -1..1");
+        result
+            .assert_signal_range("level", "out", 0.25, 0.25)
+            .expect("Constant should hold its value");
+    }
+}
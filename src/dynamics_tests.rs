@@ -0,0 +1,109 @@
+//! Tests for envelope follower and dynamics modules
+
+#[cfg(test)]
+mod tests {
+    use crate::graph_modules::{GraphDuck, GraphFollower};
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    const SAMPLE_RATE: usize = 44100;
+
+    /// A 1 kHz-ish square burst followed by silence
+    fn burst(on: usize, off: usize) -> Vec<f32> {
+        (0..on + off)
+            .map(|i| {
+                if i >= on {
+                    0.0
+                } else if (i / 22) % 2 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_follower_tracks_and_gates() {
+        let mut follower = GraphFollower::new(0.005, 0.05);
+        let signal = burst(SAMPLE_RATE / 10, SAMPLE_RATE / 2);
+        let len = signal.len();
+        let outputs = process_module(&mut follower, &[("in", signal)], len);
+        let env = outputs.get("env").unwrap();
+        let gate = outputs.get("gate").unwrap();
+
+        // Rectified square settles near 1 well within the burst
+        assert!(env[SAMPLE_RATE / 10 - 1] > 0.99);
+        assert_eq!(gate[SAMPLE_RATE / 20], 1.0);
+
+        // One release time after the burst the envelope has fallen to about 1/e
+        let after_release = env[SAMPLE_RATE / 10 + SAMPLE_RATE / 20];
+        assert!((after_release - (-1.0f32).exp()).abs() < 0.02, "{after_release}");
+        assert_eq!(*gate.last().unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_follower_attack_cv_overrides_param() {
+        let slow = |attack_cv: f32| {
+            let mut follower = GraphFollower::new(0.5, 0.5);
+            let outputs = process_module(
+                &mut follower,
+                &[("in", vec![1.0; 441]), ("attack", vec![attack_cv; 441])],
+                441,
+            );
+            outputs.get("env").unwrap()[440]
+        };
+
+        // 10ms into a 500ms attack vs a 1ms attack from CV
+        assert!(slow(0.0) < 0.05);
+        assert!(slow(0.001) > 0.99);
+    }
+
+    #[test]
+    fn test_duck_reduces_gain_from_sidechain() {
+        let mut duck = GraphDuck::new(0.75, 0.5);
+        let len = SAMPLE_RATE / 10;
+        let outputs = process_module(
+            &mut duck,
+            &[("in", vec![0.5; len]), ("sidechain", vec![0.0; len])],
+            len,
+        );
+        assert!(outputs.get("out").unwrap().iter().all(|&v| v == 0.5));
+
+        let outputs =
+            process_module(&mut duck, &[("in", vec![0.5; len]), ("sidechain", burst(len, 0))], len);
+        let gain = outputs.get("gain").unwrap()[len - 1];
+        assert!((gain - 0.25).abs() < 1e-3, "{gain}");
+        assert!((outputs.get("out").unwrap()[len - 1] - 0.125).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_kick_ducks_pad_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clk: clock 120
+            kick_osc: osc sine 50
+            kick_env: envelope 0.001 0.15
+            kick_env.gate <- clk.clock
+            kick: vca
+            kick.audio <- kick_osc.sine
+            kick.cv <- kick_env.out
+            pad: osc saw 220
+            duck: duck 0.9 0.3
+            duck.in <- pad.saw
+            duck.sidechain <- kick.out
+            fol: follower 0.005 0.1
+            fol.in <- kick.out
+            out <- duck.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Ducking patch should load and run");
+
+        result.assert_signal_varied("duck", "gain").expect("Kick should pump the pad");
+        result.assert_signal_range("duck", "gain", 0.0, 1.0).expect("Gain stays in 0-1");
+        assert_eq!(result.gate_fire_count("fol", "gate"), 4);
+    }
+}
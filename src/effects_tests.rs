@@ -0,0 +1,384 @@
+//! Tests for ring modulation, frequency shifting, modulation effects and the vocoder
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{
+        GraphFreqShift, GraphModulation, GraphRingMod, GraphVocoder, ModulationKind,
+    };
+    use crate::test_framework::{process_module, TestRunner};
+    use std::f32::consts::TAU;
+    use std::time::Duration;
+
+    const SAMPLE_RATE: f32 = 44100.0;
+
+    fn sine(freq: f32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| (TAU * freq * i as f32 / SAMPLE_RATE).sin()).collect()
+    }
+
+    /// Amplitude of one frequency in a signal (Goertzel)
+    fn magnitude(signal: &[f32], freq: f32) -> f32 {
+        let coeff = 2.0 * (TAU * freq / SAMPLE_RATE).cos();
+        let (mut s1, mut s2) = (0.0f32, 0.0f32);
+        for &x in signal {
+            let s = x + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s;
+        }
+        let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+        2.0 * power.max(0.0).sqrt() / signal.len() as f32
+    }
+
+    #[test]
+    fn test_ringmod_is_four_quadrant() {
+        let mut ring = GraphRingMod::new(1.0);
+        let outputs = process_module(
+            &mut ring,
+            &[
+                ("carrier", vec![0.5, -0.5, 0.5, -0.5]),
+                ("modulator", vec![1.0, 1.0, -1.0, -1.0]),
+            ],
+            4,
+        );
+        assert_eq!(outputs.get("out").unwrap(), &vec![0.5, -0.5, -0.5, 0.5]);
+
+        // Half mix blends the dry carrier back in
+        let outputs = process_module(
+            &mut ring,
+            &[("carrier", vec![1.0]), ("modulator", vec![0.0]), ("mix", vec![-0.5])],
+            1,
+        );
+        assert_eq!(outputs.get("out").unwrap(), &vec![0.5]);
+    }
+
+    #[test]
+    fn test_ringmod_sidebands() {
+        let len = 44100;
+        let mut ring = GraphRingMod::default();
+        let outputs = process_module(
+            &mut ring,
+            &[("carrier", sine(1000.0, len)), ("modulator", sine(300.0, len))],
+            len,
+        );
+        let out = outputs.get("out").unwrap();
+
+        // Sum and difference tones, no carrier
+        assert!((magnitude(out, 1300.0) - 0.5).abs() < 0.01);
+        assert!((magnitude(out, 700.0) - 0.5).abs() < 0.01);
+        assert!(magnitude(out, 1000.0) < 0.01);
+    }
+
+    #[test]
+    fn test_freqshift_moves_partials() {
+        let len = 44100;
+        let mut shifter = GraphFreqShift::new(200.0);
+        let outputs = process_module(&mut shifter, &[("in", sine(1000.0, len))], len);
+
+        // Skip the allpass settling time
+        let up = &outputs.get("up").unwrap()[4410..];
+        let down = &outputs.get("down").unwrap()[4410..];
+
+        assert!(magnitude(up, 1200.0) > 0.9, "up {}", magnitude(up, 1200.0));
+        assert!(magnitude(up, 800.0) < 0.05, "up image {}", magnitude(up, 800.0));
+        assert!(magnitude(down, 800.0) > 0.9, "down {}", magnitude(down, 800.0));
+        assert!(magnitude(down, 1200.0) < 0.05, "down image {}", magnitude(down, 1200.0));
+    }
+
+    #[test]
+    fn test_freqshift_cv_adds_to_shift() {
+        let len = 44100;
+        let mut shifter = GraphFreqShift::new(100.0);
+        let outputs = process_module(
+            &mut shifter,
+            &[("in", sine(500.0, len)), ("shift", vec![-300.0; len])],
+            len,
+        );
+
+        // Net -200 Hz shift: "up" now moves the tone down
+        let up = &outputs.get("up").unwrap()[4410..];
+        assert!(magnitude(up, 300.0) > 0.9);
+    }
+
+    #[test]
+    fn test_ring_and_shift_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            vco: osc saw 220
+            lfo1: lfo 3
+            ring: ringmod 0.7
+            ring.carrier <- vco.saw
+            ring.modulator <- lfo1.sine
+            fs: freqshift 5
+            fs.in <- ring.out
+            drift: lfo 0.1
+            fs.shift <- drift.sine * 20
+            out <- fs.up
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Ring mod and shifter patch should load and run");
+
+        result.assert_signal_varied("ring", "out").expect("Ring mod should pass audio");
+        result.assert_signal_varied("fs", "up").expect("Shifter should pass audio");
+    }
+
+    fn impulse(len: usize) -> Vec<f32> {
+        let mut signal = vec![0.0; len];
+        signal[0] = 1.0;
+        signal
+    }
+
+    fn rms(signal: &[f32]) -> f32 {
+        (signal.iter().map(|x| x * x).sum::<f32>() / signal.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_modulation_dry_at_zero_mix() {
+        for kind in [ModulationKind::Chorus, ModulationKind::Flanger, ModulationKind::Phaser] {
+            let mut fx = GraphModulation::new(kind);
+            fx.set_param("mix", 0.0).unwrap();
+            let input = sine(440.0, 1000);
+            let outputs = process_module(&mut fx, &[("in", input.clone())], 1000);
+            assert_eq!(outputs.get("left").unwrap(), &input);
+            assert_eq!(outputs.get("right").unwrap(), &input);
+        }
+    }
+
+    #[test]
+    fn test_chorus_delays_wet_signal() {
+        let mut chorus = GraphModulation::new(ModulationKind::Chorus);
+        chorus.set_param("depth", 0.0).unwrap();
+        chorus.set_param("mix", 1.0).unwrap();
+        let outputs = process_module(&mut chorus, &[("in", impulse(1000))], 1000);
+        let left = outputs.get("left").unwrap();
+
+        // 15ms is 661.5 samples, split across the two neighbours
+        assert!((left[661] - 0.5).abs() < 1e-4);
+        assert!((left[662] - 0.5).abs() < 1e-4);
+        assert!(left[..661].iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_flanger_feedback_repeats() {
+        let mut flanger = GraphModulation::new(ModulationKind::Flanger);
+        flanger.set_param("depth", 0.0).unwrap();
+        flanger.set_param("mix", 1.0).unwrap();
+        flanger.set_param("feedback", 0.8).unwrap();
+        let outputs = process_module(&mut flanger, &[("in", impulse(1000))], 1000);
+        let left = outputs.get("left").unwrap();
+
+        // 3ms (132.3 samples) per pass; each pass is scaled by the feedback
+        let first: f32 = left[120..145].iter().sum();
+        let second: f32 = left[250..280].iter().sum();
+        assert!((first - 1.0).abs() < 0.05, "{first}");
+        assert!(second > 0.3 && second < first, "{second}");
+    }
+
+    #[test]
+    fn test_phaser_allpass_keeps_level_and_spreads_stereo() {
+        let len = 44100;
+        let mut phaser = GraphModulation::new(ModulationKind::Phaser);
+        phaser.set_param("mix", 1.0).unwrap();
+        phaser.set_param("feedback", 0.0).unwrap();
+        let input = sine(600.0, len);
+        let outputs = process_module(&mut phaser, &[("in", input.clone())], len);
+        let left = outputs.get("left").unwrap();
+        let right = outputs.get("right").unwrap();
+
+        // All-pass only moves phase, so the wet signal keeps its level
+        assert!((rms(&left[4410..]) - rms(&input[4410..])).abs() < 0.05);
+        assert_ne!(left, right);
+
+        // Half wet, the moving notches make the level wobble
+        phaser.set_param("mix", 0.5).unwrap();
+        let outputs = process_module(&mut phaser, &[("in", input)], len);
+        let left = outputs.get("left").unwrap();
+        let window_levels: Vec<f32> = left.chunks(2205).map(rms).collect();
+        let min = window_levels.iter().cloned().fold(f32::MAX, f32::min);
+        let max = window_levels.iter().cloned().fold(0.0, f32::max);
+        assert!(max - min > 0.1, "{min}..{max}");
+    }
+
+    #[test]
+    fn test_modulation_effects_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            vco: osc saw 110
+            vcf: filter 2000 0.3
+            vcf.audio <- vco.saw
+            ch: chorus
+            ch.in <- vcf.lp
+            fl: flanger 0.1
+            fl.in <- ch.left
+            ph: phaser
+            ph.in <- fl.left
+            wobble: lfo 0.2
+            ph.depth <- wobble.sine * 0.2
+            out.left <- ph.left
+            out.right <- ph.right
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Modulation effects patch should load and run");
+
+        for module in ["ch", "fl", "ph"] {
+            result.assert_signal_varied(module, "left").expect("Effect should pass audio");
+            result
+                .assert_signal_range(module, "right", -3.0, 3.0)
+                .expect("Effect should stay bounded");
+        }
+    }
+
+    /// Band-limited-enough saw for a carrier with partials every `freq` Hz
+    fn saw(freq: f32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| 2.0 * (freq * i as f32 / SAMPLE_RATE).fract() - 1.0).collect()
+    }
+
+    #[test]
+    fn test_vocoder_imposes_modulator_spectrum() {
+        let len = 44100;
+        let mut vocoder = GraphVocoder::new(16);
+        let outputs = process_module(
+            &mut vocoder,
+            &[("carrier", saw(100.0, len)), ("modulator", sine(1000.0, len))],
+            len,
+        );
+        let out = &outputs.get("out").unwrap()[4410..];
+
+        // Only the carrier partials near the modulator come through
+        let near = magnitude(out, 1000.0);
+        assert!(near > 0.05, "near {near}");
+        assert!(magnitude(out, 200.0) < near * 0.1);
+        assert!(magnitude(out, 5000.0) < near * 0.1);
+
+        // A silent modulator closes every band
+        let outputs =
+            process_module(&mut GraphVocoder::new(16), &[("carrier", saw(100.0, len))], len);
+        assert!(rms(outputs.get("out").unwrap()) < 0.001);
+    }
+
+    #[test]
+    fn test_vocoder_shift_moves_formants() {
+        let len = 44100;
+        let mut vocoder = GraphVocoder::new(16);
+        vocoder.set_param("shift", 3.0).unwrap();
+        let outputs = process_module(
+            &mut vocoder,
+            &[("carrier", saw(100.0, len)), ("modulator", sine(1000.0, len))],
+            len,
+        );
+        let out = &outputs.get("out").unwrap()[4410..];
+
+        // Three bands up from 1kHz at 16 bands is roughly 2.4kHz
+        assert!(magnitude(out, 2400.0) > magnitude(out, 1000.0) * 4.0);
+
+        // Shift CV adds to the parameter and can undo it
+        let outputs = process_module(
+            &mut GraphVocoder::new(16),
+            &[("carrier", saw(100.0, len)), ("modulator", sine(1000.0, len))],
+            len,
+        );
+        let mut vocoder = GraphVocoder::new(16);
+        vocoder.set_param("shift", 3.0).unwrap();
+        let shifted_back = process_module(
+            &mut vocoder,
+            &[
+                ("carrier", saw(100.0, len)),
+                ("modulator", sine(1000.0, len)),
+                ("shift", vec![-3.0; len]),
+            ],
+            len,
+        );
+        assert_eq!(outputs.get("out"), shifted_back.get("out"));
+    }
+
+    #[test]
+    fn test_vocoder_band_count_is_clamped() {
+        let mut vocoder = GraphVocoder::new(4);
+        assert_eq!(vocoder.get_param("bands"), Some(8.0));
+        vocoder.set_param("bands", 64.0).unwrap();
+        assert_eq!(vocoder.get_param("bands"), Some(32.0));
+
+        // More bands give a tighter response around the modulator
+        let len = 44100;
+        let spread = |bands: f32| {
+            let mut vocoder = GraphVocoder::new(8);
+            let outputs = process_module(
+                &mut vocoder,
+                &[
+                    ("carrier", saw(100.0, len)),
+                    ("modulator", sine(1000.0, len)),
+                    ("bands", vec![bands; len]),
+                ],
+                len,
+            );
+            let out = &outputs.get("out").unwrap()[4410..];
+            magnitude(out, 1500.0) / magnitude(out, 1000.0)
+        };
+        assert!(spread(24.0) < spread(0.0));
+
+        // Bands moved to new centres start from silence instead of ringing
+        let mut vocoder = GraphVocoder::new(16);
+        process_module(
+            &mut vocoder,
+            &[("carrier", saw(100.0, 4410)), ("modulator", sine(1000.0, 4410))],
+            4410,
+        );
+        vocoder.set_param("bands", 8.0).unwrap();
+        let outputs = process_module(&mut vocoder, &[], 64);
+        assert!(outputs.get("out").unwrap().iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_vocoder_unvoiced_noise_follows_sibilance() {
+        let len = 22050;
+        let hiss = sine(7000.0, len);
+
+        let mut vocoder = GraphVocoder::new(16);
+        let outputs = process_module(&mut vocoder, &[("modulator", hiss.clone())], len);
+        assert!(rms(outputs.get("out").unwrap()) < 0.001);
+
+        let mut vocoder = GraphVocoder::new(16);
+        let outputs =
+            process_module(&mut vocoder, &[("modulator", hiss), ("noise", vec![1.0; len])], len);
+        assert!(rms(outputs.get("out").unwrap()) > 0.05);
+
+        // Low voiced sounds don't open the noise
+        let mut vocoder = GraphVocoder::new(16);
+        vocoder.set_param("noise", 1.0).unwrap();
+        let outputs = process_module(&mut vocoder, &[("modulator", sine(200.0, len))], len);
+        assert!(rms(&outputs.get("out").unwrap()[4410..]) < 0.01);
+    }
+
+    #[test]
+    fn test_vocoder_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            vco: osc saw 110
+            hats: noise
+            beat: lfo 4
+            vca1: vca
+            vca1.audio <- hats.white
+            vca1.cv <- beat.square
+            voc: vocoder 20
+            voc.carrier <- vco.saw
+            voc.modulator <- vca1.out
+            voc.noise <- 0.3
+            out <- voc.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Vocoder patch should load and run");
+
+        result.assert_signal_varied("voc", "out").expect("Vocoder should pass audio");
+        result
+            .assert_signal_range("voc", "out", -3.0, 3.0)
+            .expect("Vocoder should stay bounded");
+    }
+}
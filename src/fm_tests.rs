@@ -0,0 +1,65 @@
+//! Tests for the FM operator module
+
+#[cfg(test)]
+mod tests {
+    use crate::graph_modules::GraphFmOperator;
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    #[test]
+    fn test_fm_operator_stack() {
+        let mut runner = TestRunner::new();
+
+        // Two-operator DX-style stack: modulator phase-modulates the carrier
+        let patch = r"
+            modulator: fm 220 2.0
+            carrier: fm 220 1.0 3.0
+            carrier.pm <- modulator.out
+            out <- carrier.out * 0.5
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_millis(500))
+            .expect("FM stack patch should load and run");
+
+        assert!(result.collector.signal_varied("carrier", "out"), "Carrier should oscillate");
+        result
+            .assert_signal_range("carrier", "out", -1.01, 1.01)
+            .expect("Operator output should stay within -1..1");
+    }
+
+    #[test]
+    fn test_through_zero_fm_runs_backwards() {
+        let mut op = GraphFmOperator::new(100.0, 1.0, 1.0);
+
+        // fm = -2 with index 1 gives an instantaneous frequency of -100 Hz
+        let outputs = process_module(&mut op, &[("fm", vec![-2.0; 64])], 64);
+
+        let out = outputs.get("out").unwrap();
+        assert!(out[1] < 0.0, "Negative frequency should run the phase backwards");
+        assert!(out[32] < out[1], "Phase should keep moving backwards (got {})", out[32]);
+    }
+
+    #[test]
+    fn test_index_envelope_follows_gate() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clock: lfo 4.0
+            op: fm 440 1.0
+            op.gate <- clock.gate
+            op.decay <- 0.1
+            op.env_depth <- 5.0
+            out <- op.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Enveloped operator patch should run");
+
+        assert!(result.collector.signal_varied("op", "env"), "Index envelope should move");
+        result
+            .assert_signal_range("op", "env", 0.0, 1.0)
+            .expect("Index envelope should stay within 0..1");
+    }
+}
@@ -14,7 +14,8 @@
 
 use crate::observability::{ObserverManager, SignalObserver};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// Describes a module input or output port
 #[derive(Debug, Clone)]
@@ -25,17 +26,79 @@ pub struct PortDescriptor {
 }
 
 /// Buffer of audio samples for a single port
+///
+/// Polyphonic ports hold one block per channel back to back, so the first
+/// `sample_count` samples are always channel 0.
 pub type PortBuffer = Vec<f32>;
 
+/// Most channels a polyphonic cable can carry
+pub const MAX_CHANNELS: usize = 16;
+
+/// Builds another instance of a module, used for extra polyphonic voices
+pub type ModuleFactory = Arc<dyn Fn() -> Result<Box<dyn GraphModule>> + Send + Sync>;
+
 /// Collection of buffers for all ports of a module
 pub struct PortBuffers {
     buffers: HashMap<String, PortBuffer>,
+    channels: HashMap<String, usize>,
 }
 
 impl PortBuffers {
     #[must_use]
     pub fn new() -> Self {
-        Self { buffers: HashMap::new() }
+        Self {
+            buffers: HashMap::new(),
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Number of channels on a port, 1 unless it carries a poly cable
+    #[must_use]
+    pub fn channels(&self, port: &str) -> usize {
+        self.channels.get(port).copied().unwrap_or(1)
+    }
+
+    /// Widest channel count across all ports
+    #[must_use]
+    pub fn max_channels(&self) -> usize {
+        self.channels.values().copied().max().unwrap_or(1).max(1)
+    }
+
+    /// Samples for one channel of a port
+    #[must_use]
+    pub fn channel(&self, port: &str, channel: usize) -> Option<&[f32]> {
+        let buffer = self.buffers.get(port)?;
+        let len = buffer.len() / self.channels(port);
+        buffer.get(channel * len..(channel + 1) * len)
+    }
+
+    pub fn channel_mut(&mut self, port: &str, channel: usize) -> Option<&mut [f32]> {
+        let channels = self.channels(port);
+        let buffer = self.buffers.get_mut(port)?;
+        let len = buffer.len() / channels;
+        buffer.get_mut(channel * len..(channel + 1) * len)
+    }
+
+    /// Resize a port to carry `channels` blocks, keeping existing samples
+    pub fn set_channels(&mut self, port: &str, channels: usize, sample_count: usize) {
+        let channels = channels.clamp(1, MAX_CHANNELS);
+        self.buffers
+            .entry(port.to_string())
+            .or_default()
+            .resize(channels * sample_count, 0.0);
+        if channels == 1 {
+            self.channels.remove(port);
+        } else {
+            self.channels.insert(port.to_string(), channels);
+        }
+    }
+
+    /// Put every port back to one channel, in place
+    pub fn reset_channels(&mut self, sample_count: usize) {
+        for buffer in self.buffers.values_mut() {
+            buffer.resize(sample_count, 0.0);
+        }
+        self.channels.clear();
     }
 
     #[must_use]
@@ -100,6 +163,22 @@ pub trait GraphModule: Send {
 
     /// Allow downcasting to concrete types
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+
+    /// Whether the module reads and writes channel counts itself
+    ///
+    /// All other modules are run once per channel when a poly cable reaches
+    /// one of their inputs.
+    fn is_polyphonic(&self) -> bool {
+        false
+    }
+
+    /// Most channels a polyphonic module puts out on any port
+    ///
+    /// Used to build the voices of the mono modules it feeds before the
+    /// audio needs them.
+    fn output_channels(&self) -> usize {
+        1
+    }
 }
 
 /// Represents a connection expression with potential scaling/offset
@@ -117,40 +196,81 @@ pub enum ConnectionExpr {
 
 impl ConnectionExpr {
     /// Evaluate this expression given output buffers from all modules
-    pub fn evaluate(&self, outputs: &HashMap<String, PortBuffers>, buffer: &mut PortBuffer) {
+    ///
+    /// Returns how many channels the result carries; the buffer is resized to
+    /// match. Summed terms with a single channel are spread across all of them.
+    pub fn evaluate(
+        &self,
+        outputs: &HashMap<String, PortBuffers>,
+        buffer: &mut PortBuffer,
+        sample_count: usize,
+    ) -> usize {
         match self {
             Self::Direct { module, port } => {
                 if let Some(module_outputs) = outputs.get(module) {
                     if let Some(source) = module_outputs.get(port) {
-                        buffer.copy_from_slice(source);
+                        buffer.clear();
+                        buffer.extend_from_slice(source);
+                        return module_outputs.channels(port);
                     }
                 }
+                (buffer.len() / sample_count.max(1)).max(1)
             }
             Self::Scaled { expr, factor } => {
-                expr.evaluate(outputs, buffer);
+                let channels = expr.evaluate(outputs, buffer, sample_count);
                 for sample in buffer.iter_mut() {
                     *sample *= factor;
                 }
+                channels
             }
             Self::Offset { expr, offset } => {
-                expr.evaluate(outputs, buffer);
+                let channels = expr.evaluate(outputs, buffer, sample_count);
                 for sample in buffer.iter_mut() {
                     *sample += offset;
                 }
+                channels
             }
             Self::Sum { exprs } => {
-                // Clear buffer first
-                for sample in buffer.iter_mut() {
-                    *sample = 0.0;
+                let mut terms = Vec::with_capacity(exprs.len());
+                for expr in exprs {
+                    let mut term = vec![0.0; sample_count];
+                    let channels = expr.evaluate(outputs, &mut term, sample_count);
+                    terms.push((term, channels));
                 }
 
-                let mut temp_buffer = vec![0.0; buffer.len()];
-                for expr in exprs {
-                    expr.evaluate(outputs, &mut temp_buffer);
-                    for (i, sample) in temp_buffer.iter().enumerate() {
-                        buffer[i] += sample;
+                let channels = terms.iter().map(|(_, channels)| *channels).max().unwrap_or(1);
+                buffer.clear();
+                buffer.resize(channels * sample_count, 0.0);
+                for (term, term_channels) in &terms {
+                    for channel in 0..channels {
+                        let source = if *term_channels == 1 {
+                            0
+                        } else if channel < *term_channels {
+                            channel
+                        } else {
+                            continue;
+                        };
+                        let from = &term[source * sample_count..(source + 1) * sample_count];
+                        let to = &mut buffer[channel * sample_count..(channel + 1) * sample_count];
+                        for (sum, sample) in to.iter_mut().zip(from) {
+                            *sum += sample;
+                        }
                     }
                 }
+                channels
+            }
+        }
+    }
+
+    /// Collect the names of all modules this expression reads from
+    pub fn collect_sources<'a>(&'a self, sources: &mut Vec<&'a str>) {
+        match self {
+            Self::Direct { module, .. } => sources.push(module),
+            Self::Scaled { expr, .. } | Self::Offset { expr, .. } => expr.collect_sources(sources),
+            Self::Sum { exprs } => {
+                for expr in exprs {
+                    expr.collect_sources(sources);
+                }
             }
         }
     }
@@ -164,6 +284,15 @@ pub struct Connection {
     pub expression: ConnectionExpr,
 }
 
+/// Extra instances of a mono module running on a poly cable
+#[derive(Default)]
+struct PolyVoices {
+    /// Instances for channels 1 and up; channel 0 is the module itself
+    extra: Vec<Box<dyn GraphModule>>,
+    /// Per-channel input and output buffers
+    scratch: Vec<(PortBuffers, PortBuffers)>,
+}
+
 /// The main graph executor
 pub struct GraphExecutor {
     modules: HashMap<String, Box<dyn GraphModule>>,
@@ -175,6 +304,16 @@ pub struct GraphExecutor {
     current_cycle: usize,
     // Gate state tracking for edge detection
     gate_states: HashMap<String, f32>, // module.port -> previous value
+    // First module output that produced a NaN or infinity
+    first_nonfinite: Option<String>,
+    // Polyphony: how to build more voices, their state, and the parameters
+    // to give each new one
+    factories: HashMap<String, ModuleFactory>,
+    poly_voices: HashMap<String, PolyVoices>,
+    params: HashMap<String, Vec<(String, f32)>>,
+    // What each set parameter read before it was first set, so a reload
+    // that drops the setting can put it back
+    defaults: HashMap<String, Vec<(String, f32)>>,
 }
 
 impl GraphExecutor {
@@ -188,6 +327,11 @@ impl GraphExecutor {
             observers: ObserverManager::new(),
             current_cycle: 0,
             gate_states: HashMap::new(),
+            first_nonfinite: None,
+            factories: HashMap::new(),
+            poly_voices: HashMap::new(),
+            params: HashMap::new(),
+            defaults: HashMap::new(),
         }
     }
 
@@ -196,8 +340,181 @@ impl GraphExecutor {
         self.update_execution_order();
     }
 
+    /// Let a module run one instance per channel when fed a poly cable
+    ///
+    /// The extra instances are made by [`Self::build_voices`], or by
+    /// [`Self::missing_voices`] and [`Self::add_voices`] to build them away
+    /// from the graph; processing never builds them itself.
+    pub fn set_module_factory(&mut self, name: &str, factory: ModuleFactory) {
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    /// Most channels that can reach each module, following poly cables
+    /// from their sources through the mono modules that run per channel
+    fn channel_widths(&self) -> HashMap<&str, usize> {
+        let mut widths: HashMap<&str, usize> = self
+            .modules
+            .iter()
+            .map(|(name, module)| {
+                let width = if module.is_polyphonic() { module.output_channels() } else { 1 };
+                (name.as_str(), width.clamp(1, MAX_CHANNELS))
+            })
+            .collect();
+
+        loop {
+            let mut changed = false;
+            for conn in &self.connections {
+                let to = conn.to_module.as_str();
+                let per_channel =
+                    self.modules.get(to).is_some_and(|module| !module.is_polyphonic());
+                if !per_channel || !self.factories.contains_key(to) {
+                    continue;
+                }
+                let mut sources = Vec::new();
+                conn.expression.collect_sources(&mut sources);
+                let incoming =
+                    sources.iter().filter_map(|source| widths.get(source)).max().copied();
+                if let (Some(incoming), Some(width)) = (incoming, widths.get_mut(to)) {
+                    if incoming > *width {
+                        *width = incoming;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return widths;
+            }
+        }
+    }
+
+    /// Voices each module still needs for the poly cables that can reach it,
+    /// with the factory to build them
+    pub fn missing_voices(&self) -> Vec<(String, usize, ModuleFactory)> {
+        let mut missing = Vec::new();
+        for (name, width) in self.channel_widths() {
+            let Some(factory) = self.factories.get(name) else {
+                continue;
+            };
+            let built = self.poly_voices.get(name).map_or(0, |voices| voices.extra.len());
+            if width > built + 1 {
+                missing.push((name.to_string(), width - 1 - built, Arc::clone(factory)));
+            }
+        }
+        missing.sort_by(|a, b| a.0.cmp(&b.0));
+        missing
+    }
+
+    /// Add voices built from a module's factory, giving them its parameters
+    pub fn add_voices(&mut self, module_name: &str, new_voices: Vec<Box<dyn GraphModule>>) {
+        if !self.modules.contains_key(module_name) {
+            return;
+        }
+        let voices = self.poly_voices.entry(module_name.to_string()).or_default();
+        for mut voice in new_voices {
+            for (param, value) in self.params.get(module_name).into_iter().flatten() {
+                let _ = voice.set_param(param, *value);
+            }
+            voices.extra.push(voice);
+        }
+        voices.scratch.resize_with(voices.extra.len() + 1, Default::default);
+    }
+
+    /// Build every voice the graph's poly cables call for
+    ///
+    /// Building can read files, so a graph being played should build them
+    /// outside its lock with `missing_voices` and `add_voices` instead.
+    pub fn build_voices(&mut self) {
+        for (name, count, factory) in self.missing_voices() {
+            let voices: Vec<_> = (0..count).map_while(|_| factory().ok()).collect();
+            self.add_voices(&name, voices);
+        }
+    }
+
+    /// Replace a module with the same-named one from another graph, keeping
+    /// its running state: poly voices, last outputs and gate edge tracking
+    ///
+    /// The adopted module is set as a fresh build would be: the `retuned`
+    /// values first (creation values that changed), then the defaults of
+    /// parameters only the other graph set, then the parameters set on this
+    /// graph's module, which may only be a stand-in. Returns false, leaving
+    /// both graphs as they were, if either graph lacks the module.
+    pub fn adopt_module(&mut self, name: &str, from: &mut Self, retuned: &[(&str, f32)]) -> bool {
+        if !self.modules.contains_key(name) {
+            return false;
+        }
+        let Some(mut module) = from.modules.remove(name) else {
+            return false;
+        };
+
+        let recorded = self.params.get(name).cloned().unwrap_or_default();
+        let is_recorded = |param: &str| recorded.iter().any(|(set, _)| set == param);
+        let mut defaults = from.defaults.remove(name).unwrap_or_default();
+        let mut settings: Vec<(String, f32)> = Vec::new();
+        for (param, value) in retuned {
+            defaults.retain(|(set, _)| set != param);
+            settings.push(((*param).to_string(), *value));
+        }
+        for (param, _) in from.params.get(name).into_iter().flatten() {
+            if is_recorded(param) || settings.iter().any(|(set, _)| set == param) {
+                continue;
+            }
+            if let Some((_, value)) = defaults.iter().find(|(set, _)| set == param) {
+                settings.push((param.clone(), *value));
+            }
+        }
+        Self::apply_settings(&mut module, &settings);
+
+        // The new settings replace values the module has had since it was built
+        for (param, _) in &recorded {
+            if defaults.iter().all(|(set, _)| set != param) {
+                if let Some(value) = module.get_param(param) {
+                    defaults.push((param.clone(), value));
+                }
+            }
+        }
+        Self::apply_settings(&mut module, &recorded);
+        settings.extend(recorded);
+        self.defaults.insert(name.to_string(), defaults);
+
+        if let Some(mut voices) = from.poly_voices.remove(name) {
+            for voice in &mut voices.extra {
+                Self::apply_settings(voice, &settings);
+            }
+            self.poly_voices.insert(name.to_string(), voices);
+        }
+        if let Some(outputs) = from.output_buffers.remove(name) {
+            self.output_buffers.insert(name.to_string(), outputs);
+        }
+        let prefix = format!("{name}.");
+        let gates: Vec<String> = from
+            .gate_states
+            .keys()
+            .filter(|key| key.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for key in gates {
+            if let Some(state) = from.gate_states.remove(&key) {
+                self.gate_states.insert(key, state);
+            }
+        }
+
+        self.modules.insert(name.to_string(), module);
+        true
+    }
+
+    /// Set parameters that differ from what the module reads back, so an
+    /// unchanged seed doesn't reseed it
+    fn apply_settings(module: &mut Box<dyn GraphModule>, settings: &[(String, f32)]) {
+        for (param, value) in settings {
+            if module.get_param(param) != Some(*value) {
+                let _ = module.set_param(param, *value);
+            }
+        }
+    }
+
     pub fn add_connection(&mut self, connection: Connection) {
         self.connections.push(connection);
+        self.update_execution_order();
     }
 
     /// Add an observer to monitor the graph execution
@@ -205,6 +522,11 @@ impl GraphExecutor {
         self.observers.add_observer(observer);
     }
 
+    /// Add an observer declared by the patch, which a reload replaces
+    pub fn add_patch_observer(&mut self, observer: Box<dyn SignalObserver>) {
+        self.observers.add_patch_observer(observer);
+    }
+
     /// Get mutable access to the observer manager
     pub fn observer_manager_mut(&mut self) -> &mut ObserverManager {
         &mut self.observers
@@ -217,6 +539,10 @@ impl GraphExecutor {
         // Initialize buffers
         self.prepare_buffers(sample_count);
 
+        // A fault is reported for the block it happens in, so a module that
+        // has since recovered is not blamed for a later one
+        self.first_nonfinite = None;
+
         // Process each module in order
         for module_name in &self.execution_order {
             if let Some(module) = self.modules.get_mut(module_name) {
@@ -227,13 +553,53 @@ impl GraphExecutor {
                 for conn in &self.connections {
                     if conn.to_module == *module_name {
                         let buffer = module_inputs.get_or_default(&conn.to_port, sample_count, 0.0);
-                        conn.expression.evaluate(&self.output_buffers, buffer);
+                        let channels =
+                            conn.expression.evaluate(&self.output_buffers, buffer, sample_count);
+                        module_inputs.set_channels(&conn.to_port, channels, sample_count);
                     }
                 }
 
-                // Process the module
+                // Process the module, once per channel if a poly cable arrives
+                // at a module that only handles one
                 let module_outputs = self.output_buffers.get_mut(module_name).unwrap();
-                module.process(module_inputs, module_outputs, sample_count);
+                let wanted = if module.is_polyphonic() { 1 } else { module_inputs.max_channels() };
+                // Only voices built beforehand are used; channels beyond them are dropped
+                let channels = if wanted > 1 {
+                    self.poly_voices
+                        .get(module_name)
+                        .map_or(1, |voices| wanted.min(voices.extra.len() + 1))
+                } else {
+                    1
+                };
+
+                if channels > 1 {
+                    let voices = self.poly_voices.get_mut(module_name).unwrap();
+                    process_voices(
+                        module.as_mut(),
+                        voices,
+                        module_inputs,
+                        module_outputs,
+                        channels,
+                        sample_count,
+                    );
+                } else {
+                    if !module.is_polyphonic() {
+                        module_outputs.reset_channels(sample_count);
+                    }
+                    module.process(module_inputs, module_outputs, sample_count);
+                }
+
+                // Remember where the first NaN/infinity came from; downstream
+                // modules will all go non-finite too, so only the first matters
+                if self.first_nonfinite.is_none() {
+                    for (port_name, buffer) in &module_outputs.buffers {
+                        if buffer.iter().any(|v| !v.is_finite()) {
+                            self.first_nonfinite =
+                                Some(format!("module '{module_name}' port '{port_name}'"));
+                            break;
+                        }
+                    }
+                }
 
                 // Observe output signals (sample some values, not all for performance)
                 // We need to collect the observation data first to avoid borrowing issues
@@ -242,6 +608,9 @@ impl GraphExecutor {
                 let sample_step = if sample_count > 128 { 64 } else { 1 };
 
                 for (port_name, buffer) in &module_outputs.buffers {
+                    // Poly cables are observed on their first channel
+                    let buffer = &buffer[..sample_count.min(buffer.len())];
+
                     // Check if this is a gate output (commonly named eor, eoc, gate, trigger, etc.)
                     let is_gate_output = port_name.contains("eor")
                         || port_name.contains("eoc")
@@ -266,9 +635,13 @@ impl GraphExecutor {
                             let current_high = value > 0.5;
                             let prev_high = prev_value > 0.5;
 
-                            // Only trigger on LOW→HIGH transitions (rising edge)
-                            if current_high && !prev_high {
-                                gate_observations.push((port_name.clone(), sample_index, true));
+                            // Rising edges trigger; falling edges are reported untriggered
+                            if current_high != prev_high {
+                                gate_observations.push((
+                                    port_name.clone(),
+                                    sample_index,
+                                    current_high,
+                                ));
                             }
 
                             // Update gate state for next sample
@@ -283,14 +656,15 @@ impl GraphExecutor {
                     }
                 }
 
-                // Now observe the collected data
-                for (port_name, sample_index, value) in signal_observations {
-                    self.observers.observe_signal(module_name, &port_name, sample_index, value);
-                }
-
+                // Now observe the collected data, edges first so observers
+                // know a port is a gate before they see its samples
                 for (port_name, sample_index, triggered) in gate_observations {
                     self.observers.observe_gate(module_name, &port_name, sample_index, triggered);
                 }
+
+                for (port_name, sample_index, value) in signal_observations {
+                    self.observers.observe_signal(module_name, &port_name, sample_index, value);
+                }
             }
         }
 
@@ -315,10 +689,106 @@ impl GraphExecutor {
     }
 
     fn update_execution_order(&mut self) {
-        // Simple topological sort
-        // For now, just process in the order modules were added
-        // TODO: Implement proper topological sort
-        self.execution_order = self.modules.keys().cloned().collect();
+        // Topological sort so sources run before the modules they feed within
+        // the same buffer. A feedback loop cannot be ordered inside itself, so
+        // its modules run as one group, alphabetically, once everything
+        // feeding the loop has run; each reads the previous buffer of the
+        // loop members after it. Modules fed by a loop still run after it.
+        let mut names: Vec<&String> = self.modules.keys().collect();
+        names.sort();
+
+        let mut dependencies: HashMap<&str, Vec<&str>> = HashMap::new();
+        for conn in &self.connections {
+            let mut sources = Vec::new();
+            conn.expression.collect_sources(&mut sources);
+            let deps = dependencies.entry(conn.to_module.as_str()).or_default();
+            for source in sources {
+                if source != conn.to_module && self.modules.contains_key(source) {
+                    deps.push(source);
+                }
+            }
+        }
+
+        // Everything each module depends on, directly or through others
+        let upstream: HashMap<&str, HashSet<&str>> = names
+            .iter()
+            .map(|name| {
+                let mut seen = HashSet::new();
+                let mut stack = vec![name.as_str()];
+                while let Some(current) = stack.pop() {
+                    for dep in dependencies.get(current).into_iter().flatten() {
+                        if seen.insert(*dep) {
+                            stack.push(dep);
+                        }
+                    }
+                }
+                (name.as_str(), seen)
+            })
+            .collect();
+
+        // Group modules that feed each other; a module outside any loop is
+        // a group of its own
+        let mut group_of: HashMap<&str, usize> = HashMap::new();
+        let mut groups: Vec<Vec<&str>> = Vec::new();
+        for name in &names {
+            if group_of.contains_key(name.as_str()) {
+                continue;
+            }
+            let members: Vec<&str> = names
+                .iter()
+                .map(|other| other.as_str())
+                .filter(|other| {
+                    *other == name.as_str()
+                        || (upstream[name.as_str()].contains(other)
+                            && upstream[other].contains(name.as_str()))
+                })
+                .collect();
+            for member in &members {
+                group_of.insert(member, groups.len());
+            }
+            groups.push(members);
+        }
+
+        let group_deps: Vec<HashSet<usize>> = groups
+            .iter()
+            .enumerate()
+            .map(|(index, members)| {
+                members
+                    .iter()
+                    .flat_map(|member| dependencies.get(member).into_iter().flatten())
+                    .map(|dep| group_of[dep])
+                    .filter(|dep| *dep != index)
+                    .collect()
+            })
+            .collect();
+
+        let mut order: Vec<String> = Vec::with_capacity(names.len());
+        let mut placed = vec![false; groups.len()];
+        loop {
+            let ready: Vec<usize> = (0..groups.len())
+                .filter(|index| !placed[*index])
+                .filter(|index| group_deps[*index].iter().all(|dep| placed[*dep]))
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            for index in ready {
+                placed[index] = true;
+                order.extend(groups[index].iter().map(|name| (*name).to_string()));
+            }
+        }
+
+        self.execution_order = order;
+    }
+
+    /// The module output that first produced a non-finite value in the last block, if any
+    pub fn nonfinite_source(&self) -> Option<&str> {
+        self.first_nonfinite.as_deref()
+    }
+
+    /// Forget the recorded non-finite source so the next one is reported
+    pub fn clear_nonfinite(&mut self) {
+        self.first_nonfinite = None;
     }
 
     pub fn get_output(&self, module: &str, port: &str) -> Option<&PortBuffer> {
@@ -335,19 +805,57 @@ impl GraphExecutor {
         param_name: &str,
         value: f32,
     ) -> Result<()> {
+        let mut before = None;
         let result = self.modules.get_mut(module_name).map_or_else(
             || Err(anyhow!("Module '{module_name}' not found")),
-            |module| module.set_param(param_name, value),
+            |module| {
+                before = module.get_param(param_name);
+                module.set_param(param_name, value)
+            },
         );
 
-        // Observe parameter changes
         if result.is_ok() {
+            if let Some(before) = before {
+                let defaults = self.defaults.entry(module_name.to_string()).or_default();
+                if defaults.iter().all(|(name, _)| name != param_name) {
+                    defaults.push((param_name.to_string(), before));
+                }
+            }
+
+            // Keep polyphonic voices in step, including ones not built yet
+            if let Some(voices) = self.poly_voices.get_mut(module_name) {
+                for voice in &mut voices.extra {
+                    let _ = voice.set_param(param_name, value);
+                }
+            }
+            let params = self.params.entry(module_name.to_string()).or_default();
+            params.retain(|(name, _)| name != param_name);
+            params.push((param_name.to_string(), value));
+
+            // Observe parameter changes
             self.observers.observe_parameter(module_name, param_name, value);
         }
 
         result
     }
 
+    /// Parameters set on a module since it was created, in the order last set
+    ///
+    /// Values are read back from the module, so they are what it is using now.
+    /// Parameters that can't be read back, such as a clock's `tap` and
+    /// `reset`, are actions rather than settings and are left out.
+    pub fn module_params(&self, module_name: &str) -> Vec<(String, f32)> {
+        let Some(module) = self.modules.get(module_name) else {
+            return Vec::new();
+        };
+        self.params
+            .get(module_name)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, _)| Some((name.clone(), module.get_param(name)?)))
+            .collect()
+    }
+
     /// Get information about a module's ports
     pub fn inspect_module(&self, name: &str) -> Option<ModuleInfo> {
         let module = self.modules.get(name)?;
@@ -468,6 +976,51 @@ impl Default for GraphExecutor {
     }
 }
 
+/// Run a mono module once per channel, each channel on its own instance
+///
+/// Inputs carrying fewer channels than the module is running are spread if
+/// they are mono and silent on the missing channels otherwise.
+fn process_voices(
+    module: &mut dyn GraphModule,
+    voices: &mut PolyVoices,
+    inputs: &PortBuffers,
+    outputs: &mut PortBuffers,
+    channels: usize,
+    sample_count: usize,
+) {
+    voices.scratch.resize_with(channels, Default::default);
+    let output_ports: Vec<String> = outputs.buffers.keys().cloned().collect();
+
+    for channel in 0..channels {
+        let (voice_inputs, voice_outputs) = &mut voices.scratch[channel];
+        for port in inputs.buffers.keys() {
+            let port_channels = inputs.channels(port);
+            let buffer = voice_inputs.get_or_default(port, sample_count, 0.0);
+            buffer.resize(sample_count, 0.0);
+            if port_channels == 1 || channel < port_channels {
+                let source = inputs.channel(port, channel.min(port_channels - 1)).unwrap();
+                buffer.copy_from_slice(&source[..sample_count]);
+            } else {
+                buffer.fill(0.0);
+            }
+        }
+        for port in &output_ports {
+            voice_outputs.get_or_default(port, sample_count, 0.0).resize(sample_count, 0.0);
+        }
+
+        let voice = if channel == 0 { &mut *module } else { voices.extra[channel - 1].as_mut() };
+        voice.process(voice_inputs, voice_outputs, sample_count);
+    }
+
+    for port in &output_ports {
+        outputs.set_channels(port, channels, sample_count);
+        for (channel, (_, voice_outputs)) in voices.scratch.iter().enumerate().take(channels) {
+            let source = voice_outputs.get(port).unwrap();
+            outputs.channel_mut(port, channel).unwrap().copy_from_slice(source);
+        }
+    }
+}
+
 /// Information about a module for introspection
 #[derive(Debug, Clone)]
 pub struct ModuleInfo {
@@ -1,25 +1,130 @@
 //! Graph-based audio engine for the REPL
 
-use crate::graph::{Connection, ConnectionExpr, GraphExecutor, ModuleInfo};
+use crate::graph::{Connection, ConnectionExpr, GraphExecutor, ModuleFactory, ModuleInfo};
 use crate::graph_modules::{
-    GraphClockDiv, GraphEnvelope, GraphFilter, GraphLfo, GraphManualGate, GraphMonoMixer,
-    GraphMult, GraphNoiseGen, GraphOscillator, GraphSampleHold, GraphSeq8, GraphSlewGen,
-    GraphStereoMixer, GraphStereoOutput, GraphSwitch, GraphVca, GraphVisual,
+    BernoulliMode, ChaosKind, GraphAttenuverter, GraphBernoulli, GraphChaos, GraphClock,
+    GraphClockDiv, GraphComparator, GraphConstant, GraphCrossfade, GraphDuck, GraphEdge,
+    GraphEnvelope, GraphEuclid, GraphFilter, GraphFmOperator, GraphFollower, GraphFreqShift,
+    GraphInverter, GraphLfo, GraphLogic, GraphManualGate, GraphMatrix, GraphMidiFile, GraphMidiIn,
+    GraphModulation, GraphMonoMixer, GraphMult, GraphNoiseGen, GraphOscIn, GraphOscillator,
+    GraphPan, GraphPoly, GraphRandom, GraphRectifier, GraphResonator, GraphRingMod,
+    GraphSampleHold, GraphSeq8, GraphSlewGen, GraphStereoMixer, GraphStereoOutput, GraphString,
+    GraphSum, GraphSumDiff, GraphSwitch, GraphTuring, GraphVca, GraphVisual, GraphVocoder,
+    GraphWavetable, ModulationKind, NotePriority, PanLaw, ResonatorShape, SlewCurve, VoicePolicy,
 };
+use crate::midi_file::MidiFile;
 use crate::modules::ModuleType;
-use crate::observability::SignalObserver;
+use crate::observability::{OscObserver, OscWatch, SignalObserver};
+use crate::osc::{OscMessage, OscSender, OscServer, OscValues};
+use crate::output_stage::OutputStage;
 use crate::parser::{parse_line, Command};
 use crate::user_modules::UserModuleRegistry;
+use crate::wavetable::{Wavetable, BUILTIN_TABLES};
 use anyhow::{anyhow, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// Length of the crossfade when a reload has to rebuild the whole graph
+const CROSSFADE_SECONDS: f32 = 0.05;
+
+/// How a module was created, used to tell whether a reload can keep it and
+/// to write its declaration back out
+#[derive(Debug, Clone, PartialEq)]
+struct ModuleSpec {
+    module_type: ModuleType,
+    params: Vec<f32>,
+    args: Vec<String>,
+}
+
+/// The old graph of a full rebuild, faded out under the new one
+///
+/// The audio callback only plays it; once finished it stays in place until
+/// the control thread drops it, so freeing its modules never happens on
+/// the audio thread.
+struct Crossfade {
+    graph: GraphExecutor,
+    output_module: Option<String>,
+    position: usize,
+    length: usize,
+}
+
+impl Crossfade {
+    fn is_finished(&self) -> bool {
+        self.position >= self.length
+    }
+}
+
+/// Stands in for a module a reload keeps until the running one is adopted,
+/// taking the new patch's parameter lines for it
+struct KeptModule;
+
+impl crate::graph::GraphModule for KeptModule {
+    fn inputs(&self) -> Vec<crate::graph::PortDescriptor> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<crate::graph::PortDescriptor> {
+        Vec::new()
+    }
+
+    fn process(
+        &mut self,
+        _inputs: &crate::graph::PortBuffers,
+        _outputs: &mut crate::graph::PortBuffers,
+        _sample_count: usize,
+    ) {
+    }
+
+    fn set_param(&mut self, _name: &str, _value: f32) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_param(&self, _name: &str) -> Option<f32> {
+        None
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+/// What a reload did to the running graph
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReloadSummary {
+    /// Modules that kept running with their state
+    pub kept: Vec<String>,
+    /// New modules, including ones rebuilt because their type or arguments changed
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Nothing could be kept, so the old graph was crossfaded out
+    pub crossfaded: bool,
+}
+
+impl std::fmt::Display for ReloadSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "kept {}, added {}, removed {}",
+            self.kept.len(),
+            self.added.len(),
+            self.removed.len()
+        )?;
+        if self.crossfaded {
+            write!(f, " (full rebuild, crossfaded)")?;
+        }
+        Ok(())
+    }
+}
+
 /// Audio engine using the new graph executor
 pub struct GraphEngine {
     graph: Arc<Mutex<GraphExecutor>>,
     stream: Option<cpal::Stream>,
     is_running: bool,
-    #[allow(dead_code)]
     sample_rate: f32,
     // Store output module and port for audio routing
     output_module: Option<String>,
@@ -28,6 +133,22 @@ pub struct GraphEngine {
     has_stereo_output: bool,
     // User module registry
     user_modules: UserModuleRegistry,
+    // Directory of the loaded patch file, used to resolve relative file arguments
+    patch_dir: Option<PathBuf>,
+    // DC blocker, limiter and NaN guard in front of the audio device
+    output_stage: Arc<Mutex<OutputStage>>,
+    // Open Sound Control listener, when enabled
+    osc_server: Option<OscServer>,
+    // Latest value of every OSC address, read by osc_in modules
+    osc_values: OscValues,
+    // How each module was created, so a reload can tell which ones to keep
+    module_specs: HashMap<String, ModuleSpec>,
+    // Specs of the running patch while a reload builds its replacement
+    reload_from: HashMap<String, ModuleSpec>,
+    // osc_out lines in the order they were given, so a saved patch keeps them
+    osc_outputs: Vec<(String, ModuleSpec)>,
+    // Old graph being faded out after a reload rebuilt everything
+    crossfade: Arc<Mutex<Option<Crossfade>>>,
 }
 
 impl Default for GraphEngine {
@@ -58,6 +179,14 @@ impl GraphEngine {
             output_port: None,
             has_stereo_output: false,
             user_modules,
+            patch_dir: patch_file.and_then(|path| Path::new(path).parent()).map(Path::to_path_buf),
+            output_stage: Arc::new(Mutex::new(OutputStage::default())),
+            osc_server: None,
+            osc_values: OscValues::new(),
+            module_specs: HashMap::new(),
+            reload_from: HashMap::new(),
+            osc_outputs: Vec::new(),
+            crossfade: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -112,7 +241,253 @@ impl GraphEngine {
     /// Returns an error if any line in the patch fails to parse or process
     pub fn load_patch(&mut self, patch_content: &str) -> Result<()> {
         self.clear_patch();
+        self.process_patch(patch_content)
+    }
+
+    /// Replace the running patch, keeping modules the new patch still has
+    ///
+    /// The new patch is built alongside the old one, which keeps playing,
+    /// constructing only the modules it can't keep, so a `midi` port stays
+    /// open and files aren't read again. Modules with the same name and
+    /// type carry over with their state (oscillator phase, sequencer step,
+    /// envelope level) unless a text argument or a creation value that
+    /// isn't a plain setting (mixer inputs, poly voices) changed. Kept
+    /// modules are set as the new patch would build them: changed creation
+    /// values, the new patch's parameter lines, and defaults for parameters
+    /// set only on the old graph. Everything else is added, removed or
+    /// rewired, and observers added through [`Self::add_observer`] move
+    /// across. The switch happens between two blocks. If nothing can be
+    /// kept, the old graph is crossfaded out instead.
+    ///
+    /// # Errors
+    /// Returns an error, leaving the old patch playing, if the new one fails to load
+    ///
+    /// # Panics
+    /// Panics if the graph mutex is poisoned
+    pub fn reload_patch(&mut self, patch_content: &str) -> Result<ReloadSummary> {
+        // Build the new patch on a staging graph while the old one plays
+        // Modules it can keep are only stood in for, not built again
+        let live = std::mem::replace(&mut self.graph, Arc::new(Mutex::new(GraphExecutor::new())));
+        self.reload_from = std::mem::take(&mut self.module_specs);
+        let old_osc_outputs = std::mem::take(&mut self.osc_outputs);
+        let old_stereo = std::mem::replace(&mut self.has_stereo_output, false);
+        let old_output = self.output_module.take();
+        let result = self.process_patch(patch_content);
+        let staged = std::mem::replace(&mut self.graph, live);
+        let old_specs = std::mem::take(&mut self.reload_from);
+
+        if let Err(e) = result {
+            self.module_specs = old_specs;
+            self.osc_outputs = old_osc_outputs;
+            self.has_stereo_output = old_stereo;
+            self.output_module = old_output;
+            return Err(e);
+        }
+
+        let mut staged = staged.lock().unwrap();
+        let mut names: Vec<&String> = self.module_specs.keys().collect();
+        names.sort();
+        // Changed creation values that are plain settings are set in place
+        let retuned: HashMap<&String, Vec<(&'static str, f32)>> = names
+            .iter()
+            .filter_map(|name| {
+                let params =
+                    Self::retuned_params(old_specs.get(*name)?, &self.module_specs[*name])?;
+                Some((*name, params))
+            })
+            .collect();
+        let (keep, added): (Vec<&String>, Vec<&String>) =
+            names.into_iter().partition(|name| retuned.contains_key(name));
+
+        // Implicit modules such as `_output` aren't reported
+        let visible = |name: &&String| !name.starts_with('_');
+        let mut summary = ReloadSummary {
+            kept: keep.iter().copied().filter(visible).cloned().collect(),
+            added: added.into_iter().filter(visible).cloned().collect(),
+            removed: old_specs
+                .keys()
+                .filter(|name| !self.module_specs.contains_key(*name))
+                .filter(visible)
+                .cloned()
+                .collect(),
+            crossfaded: false,
+        };
+        summary.removed.sort();
+        summary.crossfaded = keep.is_empty() && old_specs.keys().any(|name| visible(&name));
+
+        {
+            let mut live = self.graph.lock().unwrap();
+            // A full rebuild leaves the old graph whole so it can fade out
+            if !summary.crossfaded {
+                for name in keep {
+                    staged.adopt_module(name, &mut live, &retuned[name]);
+                }
+            }
+            for observer in live.observer_manager_mut().take_observers() {
+                staged.add_observer(observer);
+            }
+            std::mem::swap(&mut *live, &mut *staged);
+        }
+        // Kept modules bring their old voices, which may be too few now
+        Self::prepare_poly_voices(&self.graph);
+
+        if summary.crossfaded {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let length = (self.sample_rate * CROSSFADE_SECONDS) as usize;
+            let old_graph = std::mem::take(&mut *staged);
+            // Any earlier fade is replaced here rather than on the audio thread
+            let earlier = self.crossfade.lock().unwrap().replace(Crossfade {
+                graph: old_graph,
+                output_module: old_output,
+                position: 0,
+                length,
+            });
+            drop(earlier);
+        }
+        drop(staged);
+        Ok(summary)
+    }
+
+    /// Drop the old graph of a reload crossfade once it has faded out
+    ///
+    /// The audio callback leaves a finished fade in place, so call this
+    /// from time to time on the control thread, as the watch loop does.
+    /// Returns whether there was a graph to drop.
+    ///
+    /// # Panics
+    /// Panics if the crossfade mutex is poisoned
+    pub fn release_crossfade(&self) -> bool {
+        let spent = {
+            let mut crossfade = self.crossfade.lock().unwrap();
+            if crossfade.as_ref().is_some_and(Crossfade::is_finished) {
+                crossfade.take()
+            } else {
+                None
+            }
+        };
+        spent.is_some()
+    }
+
+    /// The live graph written out as patch text
+    ///
+    /// Modules are declared in name order with the arguments they were
+    /// created with, each followed by the parameters set on it since, at
+    /// their current values. Then come the connections in the order they
+    /// were made (a later one to the same input replaces an earlier one)
+    /// and any `osc_out` lines. User modules are written expanded. Loading
+    /// the text gives back the same graph with the same settings, though
+    /// not its running state (phases, sequencer steps). After a reload the
+    /// parameters set only on the old patch are gone, so the text is what
+    /// loading the new patch would give.
+    ///
+    /// # Errors
+    /// Returns an error if a connection has no patch syntax, such as a sum
+    /// built through the graph API
+    ///
+    /// # Panics
+    /// Panics if the graph mutex is poisoned
+    pub fn patch_text(&self) -> Result<String> {
+        let graph = self.graph.lock().unwrap();
+        let mut lines = Vec::new();
+
+        // The stereo output is implied by the `out` connections
+        let mut names: Vec<&String> =
+            self.module_specs.keys().filter(|name| *name != "_output").collect();
+        names.sort();
+        for name in names {
+            lines.push(Self::format_declaration(name, &self.module_specs[name]));
+            for (param, value) in graph.module_params(name) {
+                lines.push(format!("{name}.{param} <- {value}"));
+            }
+        }
+
+        if !graph.list_connections().is_empty() {
+            lines.push(String::new());
+        }
+        for conn in graph.list_connections() {
+            let dest = match (conn.to_module.as_str(), conn.to_port.as_str()) {
+                ("_output", "mono") => "out".to_string(),
+                ("_output", port) => format!("out.{port}"),
+                (module, port) => format!("{module}.{port}"),
+            };
+            lines.push(format!("{dest} <- {}", Self::format_connection_expr(&conn.expression)?));
+        }
+
+        if !self.osc_outputs.is_empty() {
+            lines.push(String::new());
+        }
+        for (name, spec) in &self.osc_outputs {
+            lines.push(Self::format_declaration(name, spec));
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        Ok(text)
+    }
+
+    /// Write the live graph to a patch file
+    ///
+    /// # Errors
+    /// Returns an error if the graph cannot be written as patch text or the
+    /// file cannot be written
+    pub fn save_patch(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let text = self.patch_text()?;
+        std::fs::write(path, text).map_err(|e| anyhow!("Failed to write {}: {e}", path.display()))
+    }
+
+    /// A module declaration line, `name: type numbers names`
+    fn format_declaration(name: &str, spec: &ModuleSpec) -> String {
+        let mut line = format!("{name}: {}", spec.module_type);
+        let mut params = spec.params.as_slice();
+
+        // The parser stores an oscillator's waveform as a negative first param
+        if spec.module_type == ModuleType::Oscillator {
+            let waveform = match params.first() {
+                Some(code) if *code == -1.0 => Some("sine"),
+                Some(code) if *code == -2.0 => Some("saw"),
+                Some(code) if *code == -3.0 => Some("square"),
+                Some(code) if *code == -4.0 => Some("tri"),
+                _ => None,
+            };
+            if let Some(waveform) = waveform {
+                line.push(' ');
+                line.push_str(waveform);
+                params = &params[1..];
+            }
+        }
+
+        for param in params {
+            line.push_str(&format!(" {param}"));
+        }
+        for arg in &spec.args {
+            line.push(' ');
+            line.push_str(arg);
+        }
+        line
+    }
 
+    /// Write a connection expression the way [`Self::parse_connection_expr`] reads it
+    ///
+    /// The parser splits at the rightmost operator, so writing each operation
+    /// after its operand gives back the same nesting.
+    fn format_connection_expr(expr: &ConnectionExpr) -> Result<String> {
+        Ok(match expr {
+            ConnectionExpr::Direct { module, port } => format!("{module}.{port}"),
+            ConnectionExpr::Scaled { expr, factor } => {
+                format!("{} * {factor}", Self::format_connection_expr(expr)?)
+            }
+            ConnectionExpr::Offset { expr, offset } => {
+                format!("{} + {offset}", Self::format_connection_expr(expr)?)
+            }
+            ConnectionExpr::Sum { .. } => {
+                return Err(anyhow!("Summed connections have no patch syntax"))
+            }
+        })
+    }
+
+    /// Run each line of a patch against the current graph
+    fn process_patch(&mut self, patch_content: &str) -> Result<()> {
         // Phase 1: Preprocess to expand user modules
         let expanded_patch = self.preprocess_patch(patch_content);
 
@@ -142,19 +517,58 @@ impl GraphEngine {
         }
 
         // First try parsing with the existing parser
-        match parse_line(line) {
+        let result = match parse_line(line) {
             Ok(command) => self.handle_parsed_command(command),
             Err(_) => {
                 // Try parsing new syntax (like module.port connections)
                 self.handle_extended_syntax(line)
             }
+        };
+        if result.is_ok() {
+            Self::prepare_poly_voices(&self.graph);
+        }
+        result
+    }
+
+    /// Build the voices that poly cables will need, away from the audio thread
+    ///
+    /// Factories can read files, so they run without the graph locked.
+    fn prepare_poly_voices(graph: &Mutex<GraphExecutor>) {
+        let missing = graph.lock().unwrap().missing_voices();
+        for (name, count, factory) in missing {
+            let voices: Vec<_> = (0..count).map_while(|_| factory().ok()).collect();
+            graph.lock().unwrap().add_voices(&name, voices);
         }
     }
 
     fn handle_parsed_command(&mut self, command: Command) -> Result<String> {
         match command {
-            Command::CreateModule { name, module_type, params } => {
-                self.create_module(name.clone(), module_type, &params)?;
+            Command::CreateModule {
+                name,
+                module_type: ModuleType::OscOut,
+                params,
+                args,
+            } => {
+                // Not an audio module: it watches the graph through an observer
+                let (target, watches) = args
+                    .split_first()
+                    .ok_or_else(|| anyhow!("osc_out needs a host:port target"))?;
+                let rate = params.first().copied().unwrap_or(30.0);
+                // Part of the patch, so a reload replaces it
+                let (observer, addr) = self.osc_observer(target, watches, rate)?;
+                self.graph.lock().unwrap().add_patch_observer(Box::new(observer));
+                self.osc_outputs.push((
+                    name.clone(),
+                    ModuleSpec {
+                        module_type: ModuleType::OscOut,
+                        params,
+                        args,
+                    },
+                ));
+                Ok(format!("Created OSC output {name}: sending to {addr}"))
+            }
+            Command::CreateModule { name, module_type, params, args } => {
+                self.create_module(name.clone(), module_type, &params, &args)?;
                 Ok(format!("Created module: {name}"))
             }
             Command::Connect { from, to } => {
@@ -162,7 +576,12 @@ impl GraphEngine {
                 if to == "out" {
                     // Create implicit stereo output module if needed
                     if !self.has_stereo_output {
-                        self.create_module("_output".to_string(), ModuleType::StereoOutput, &[])?;
+                        self.create_module(
+                            "_output".to_string(),
+                            ModuleType::StereoOutput,
+                            &[],
+                            &[],
+                        )?;
                         self.has_stereo_output = true;
                     }
 
@@ -171,7 +590,12 @@ impl GraphEngine {
                 } else if to.starts_with("out.") {
                     // Direct stereo output routing (out.left, out.right)
                     if !self.has_stereo_output {
-                        self.create_module("_output".to_string(), ModuleType::StereoOutput, &[])?;
+                        self.create_module(
+                            "_output".to_string(),
+                            ModuleType::StereoOutput,
+                            &[],
+                            &[],
+                        )?;
                         self.has_stereo_output = true;
                     }
 
@@ -377,7 +801,51 @@ impl GraphEngine {
         Err(anyhow!("Invalid connection expression: {}", expr))
     }
 
-    fn create_module(&self, name: String, module_type: ModuleType, params: &[f32]) -> Result<()> {
+    fn create_module(
+        &mut self,
+        name: String,
+        module_type: ModuleType,
+        params: &[f32],
+        args: &[String],
+    ) -> Result<()> {
+        let spec = ModuleSpec {
+            module_type,
+            params: params.to_vec(),
+            args: args.to_vec(),
+        };
+        let kept = self.reload_from.get(&name).and_then(|old| Self::retuned_params(old, &spec));
+        let module: Box<dyn crate::graph::GraphModule> = if kept.is_some() {
+            Box::new(KeptModule)
+        } else {
+            let mut module =
+                Self::build_module(module_type, params, args, self.patch_dir.as_deref())?;
+            if let Some(osc_in) = module.as_any_mut().downcast_mut::<GraphOscIn>() {
+                osc_in.set_values(self.osc_values.clone());
+            }
+            module
+        };
+        self.module_specs.insert(name.clone(), spec);
+
+        // Poly cables reaching this module run extra copies built the same way
+        let params = params.to_vec();
+        let args = args.to_vec();
+        let patch_dir = self.patch_dir.clone();
+        let factory: ModuleFactory =
+            Arc::new(move || Self::build_module(module_type, &params, &args, patch_dir.as_deref()));
+
+        let mut graph = self.graph.lock().unwrap();
+        graph.set_module_factory(&name, factory);
+        graph.add_module(name, module);
+        Ok(())
+    }
+
+    /// Build a module from its type and creation arguments
+    fn build_module(
+        module_type: ModuleType,
+        params: &[f32],
+        args: &[String],
+        patch_dir: Option<&Path>,
+    ) -> Result<Box<dyn crate::graph::GraphModule>> {
         let module: Box<dyn crate::graph::GraphModule> = match module_type {
             ModuleType::Oscillator => {
                 // Handle waveform encoding (negative number means waveform type)
@@ -421,13 +889,24 @@ impl GraphEngine {
                 // Default to 4-channel stereo mixer, or use parameter if provided
                 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
                 let channel_count = params.first().copied().unwrap_or(4.0) as usize;
-                Box::new(GraphStereoMixer::new(channel_count))
+                let law = Self::pan_law_arg(args)?;
+                Box::new(GraphStereoMixer::new(channel_count).with_law(law))
             }
             ModuleType::Slew => {
                 // Default rise/fall times, or use parameters if provided
                 let rise_time = params.first().copied().unwrap_or(0.1);
                 let fall_time = params.get(1).copied().unwrap_or(rise_time);
-                Box::new(GraphSlewGen::new(rise_time, fall_time))
+                let mut slew = GraphSlewGen::new(rise_time, fall_time);
+                for arg in args {
+                    if arg == "cycle" {
+                        slew = slew.with_cycle(true);
+                    } else {
+                        let curve = SlewCurve::from_name(arg)
+                            .ok_or_else(|| anyhow!("Unknown slew option: {arg}"))?;
+                        slew = slew.with_curve(curve);
+                    }
+                }
+                Box::new(slew)
             }
             ModuleType::Seq8 => Box::new(GraphSeq8::new()),
             ModuleType::Visual => Box::new(GraphVisual::new()),
@@ -445,13 +924,308 @@ impl GraphEngine {
                 Box::new(GraphClockDiv::new(division))
             }
             ModuleType::SampleHold => Box::new(GraphSampleHold::new()),
+            ModuleType::Wavetable => {
+                // First argument is a built-in table name or a WAV file path
+                let source = args.first().map_or("basic", String::as_str);
+                let table = if BUILTIN_TABLES.contains(&source) {
+                    Wavetable::builtin(source)?
+                } else {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let frame_size = params.get(1).map(|size| *size as usize);
+                    Wavetable::from_wav_file(
+                        Self::resolve_patch_path(patch_dir, source),
+                        frame_size,
+                    )?
+                };
+                let freq = params.first().copied().unwrap_or(440.0);
+                Box::new(GraphWavetable::new(table, freq))
+            }
+            ModuleType::FmOperator => {
+                let freq = params.first().copied().unwrap_or(440.0);
+                let ratio = params.get(1).copied().unwrap_or(1.0);
+                let index = params.get(2).copied().unwrap_or(0.0);
+                Box::new(GraphFmOperator::new(freq, ratio, index))
+            }
+            ModuleType::Comparator => {
+                let threshold = params.first().copied().unwrap_or(0.5);
+                let hysteresis = params.get(1).copied().unwrap_or(0.0);
+                Box::new(GraphComparator::new(threshold, hysteresis))
+            }
+            ModuleType::Logic => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let input_count = params.first().copied().unwrap_or(2.0) as usize;
+                Box::new(GraphLogic::new(input_count))
+            }
+            ModuleType::Edge => {
+                let pulse_length = params.first().copied().unwrap_or(0.001);
+                Box::new(GraphEdge::new(pulse_length))
+            }
+            ModuleType::Attenuverter => {
+                let min_gain = params.first().copied().unwrap_or(-1.0);
+                let max_gain = params.get(1).copied().unwrap_or(1.0);
+                Box::new(GraphAttenuverter::new(min_gain, max_gain))
+            }
+            ModuleType::Rectifier => Box::new(GraphRectifier::new()),
+            ModuleType::Inverter => Box::new(GraphInverter::new()),
+            ModuleType::SumDiff => Box::new(GraphSumDiff::new()),
+            ModuleType::Constant => {
+                let value = params.first().copied().unwrap_or(0.0);
+                Box::new(GraphConstant::new(value))
+            }
+            ModuleType::Clock => {
+                let bpm = params.first().copied().unwrap_or(120.0);
+                Box::new(GraphClock::new(bpm))
+            }
+            ModuleType::Euclid => {
+                let steps = params.first().copied().unwrap_or(16.0);
+                let fills = params.get(1).copied().unwrap_or(4.0);
+                let rotate = params.get(2).copied().unwrap_or(0.0);
+                Box::new(GraphEuclid::new(steps, fills, rotate))
+            }
+            ModuleType::Turing => {
+                let length = params.first().copied().unwrap_or(8.0);
+                let seed = params.get(1).copied().unwrap_or(12345.0);
+                Box::new(GraphTuring::new(length as usize, seed as u32))
+            }
+            ModuleType::Bernoulli => {
+                let probability = params.first().copied().unwrap_or(0.5);
+                let mode = match args.first() {
+                    Some(name) => BernoulliMode::from_name(name)
+                        .ok_or_else(|| anyhow!("Unknown bernoulli mode: {name}"))?,
+                    None => BernoulliMode::Trigger,
+                };
+                Box::new(GraphBernoulli::new(probability, mode))
+            }
+            ModuleType::Matrix => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let inputs = params.first().map_or(4, |n| *n as usize);
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let outputs = params.get(1).map_or(inputs, |n| *n as usize);
+                Box::new(GraphMatrix::new(inputs, outputs))
+            }
+            ModuleType::Crossfade => {
+                let position = params.first().copied().unwrap_or(0.5);
+                let curve = Self::pan_law_arg(args)?;
+                Box::new(GraphCrossfade::new(position, curve))
+            }
+            ModuleType::Pan => {
+                let pan = params.first().copied().unwrap_or(0.0);
+                let law = Self::pan_law_arg(args)?;
+                Box::new(GraphPan::new(pan, law))
+            }
+            ModuleType::Follower => {
+                let attack = params.first().copied().unwrap_or(0.01);
+                let release = params.get(1).copied().unwrap_or(0.1);
+                Box::new(GraphFollower::new(attack, release))
+            }
+            ModuleType::Duck => {
+                let depth = params.first().copied().unwrap_or(0.8);
+                let range = params.get(1).copied().unwrap_or(0.5);
+                Box::new(GraphDuck::new(depth, range))
+            }
+            ModuleType::RingMod => {
+                let mix = params.first().copied().unwrap_or(1.0);
+                Box::new(GraphRingMod::new(mix))
+            }
+            ModuleType::FreqShift => {
+                let shift = params.first().copied().unwrap_or(0.0);
+                Box::new(GraphFreqShift::new(shift))
+            }
+            ModuleType::Chorus | ModuleType::Flanger | ModuleType::Phaser => {
+                let kind = match module_type {
+                    ModuleType::Chorus => ModulationKind::Chorus,
+                    ModuleType::Flanger => ModulationKind::Flanger,
+                    _ => ModulationKind::Phaser,
+                };
+                let mut module = GraphModulation::new(kind);
+                if let Some(rate) = params.first() {
+                    module = module.with_rate(*rate);
+                }
+                Box::new(module)
+            }
+            ModuleType::Vocoder => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let bands = params.first().map_or(16, |n| *n as usize);
+                Box::new(GraphVocoder::new(bands))
+            }
+            ModuleType::String => {
+                let freq = params.first().copied().unwrap_or(220.0);
+                Box::new(GraphString::new(freq))
+            }
+            ModuleType::Resonator => {
+                let freq = params.first().copied().unwrap_or(220.0);
+                let shape = match args.first() {
+                    Some(name) => ResonatorShape::from_name(name)
+                        .ok_or_else(|| anyhow!("Unknown resonator shape: {name}"))?,
+                    None => ResonatorShape::Harmonic,
+                };
+                Box::new(GraphResonator::new(freq, shape))
+            }
+            ModuleType::Random => {
+                let rate = params.first().copied().unwrap_or(1.0);
+                let seed = params.get(1).copied().unwrap_or(12345.0);
+                Box::new(GraphRandom::new(rate, seed as u32))
+            }
+            ModuleType::Chaos => {
+                let rate = params.first().copied().unwrap_or(1.0);
+                let seed = params.get(1).copied().unwrap_or(12345.0);
+                let kind = match args.first() {
+                    Some(name) => ChaosKind::from_name(name)
+                        .ok_or_else(|| anyhow!("Unknown attractor: {name}"))?,
+                    None => ChaosKind::Lorenz,
+                };
+                Box::new(GraphChaos::new(kind, rate, seed as u32))
+            }
+            ModuleType::Poly => {
+                let voices = params.first().copied().unwrap_or(4.0).max(1.0) as usize;
+                let policy = match args.first() {
+                    Some(name) => VoicePolicy::from_name(name)
+                        .ok_or_else(|| anyhow!("Unknown voice policy: {name}"))?,
+                    None => VoicePolicy::RoundRobin,
+                };
+                Box::new(GraphPoly::new(voices, policy))
+            }
+            ModuleType::Sum => Box::new(GraphSum::new(params.first().copied().unwrap_or(1.0))),
+            ModuleType::MidiFile => {
+                // First argument is the file, the rest are playback options
+                let path = args.first().ok_or_else(|| anyhow!("midifile needs a file path"))?;
+                let song = MidiFile::from_file(Self::resolve_patch_path(patch_dir, path))?;
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let voices = params.first().copied().unwrap_or(1.0).max(1.0) as usize;
+                let mut player = GraphMidiFile::new(song, voices);
+                for option in &args[1..] {
+                    player = match option.as_str() {
+                        "loop" => player.with_looping(true),
+                        "once" => player.with_looping(false),
+                        "clock" | "sync" => player.with_sync(true),
+                        _ => return Err(anyhow!("Unknown midifile option: {option}")),
+                    };
+                }
+                Box::new(player)
+            }
+            ModuleType::MidiIn => {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let voices = params.first().copied().unwrap_or(1.0).max(1.0) as usize;
+                let mut priority = NotePriority::Last;
+                let mut legato = false;
+                for option in args {
+                    match option.as_str() {
+                        "legato" => legato = true,
+                        name => {
+                            priority = NotePriority::from_name(name)
+                                .ok_or_else(|| anyhow!("Unknown note priority: {name}"))?;
+                        }
+                    }
+                }
+                Box::new(GraphMidiIn::open("input", voices, priority)?.with_legato(legato))
+            }
+            ModuleType::OscIn => {
+                if args.is_empty() {
+                    return Err(anyhow!("osc_in needs at least one OSC address"));
+                }
+                if let Some(bad) = args.iter().find(|address| !address.starts_with('/')) {
+                    return Err(anyhow!("OSC addresses start with '/': {bad}"));
+                }
+                let smooth = params.first().copied().unwrap_or(0.02);
+                Box::new(GraphOscIn::new(args.to_vec(), smooth))
+            }
+            ModuleType::OscOut => {
+                return Err(anyhow!("osc_out is an observer, not an audio module"))
+            }
             ModuleType::Output => {
                 return Err(anyhow!("Module type {:?} not yet implemented", module_type))
             }
         };
 
-        self.graph.lock().unwrap().add_module(name, module);
-        Ok(())
+        Ok(module)
+    }
+
+    /// The parameter settings that turn a module built from `old` into one
+    /// built from `new`, or None if it has to be rebuilt
+    ///
+    /// Creation values that are plain settings (a filter's cutoff, a
+    /// clock's tempo) map to the parameter of the same meaning; counts that
+    /// shape the module, oscillator waveform codes and text arguments don't.
+    /// The stereo output is always rebuilt, as the new patch's connections
+    /// say which of its sides are in use.
+    fn retuned_params(old: &ModuleSpec, new: &ModuleSpec) -> Option<Vec<(&'static str, f32)>> {
+        if old.module_type != new.module_type
+            || new.module_type == ModuleType::StereoOutput
+            || old.args != new.args
+            || old.params.len() != new.params.len()
+        {
+            return None;
+        }
+        let settable: &[&str] = match new.module_type {
+            ModuleType::Oscillator if new.params.first().is_some_and(|code| *code < 0.0) => {
+                &["", "freq"]
+            }
+            ModuleType::Oscillator
+            | ModuleType::Lfo
+            | ModuleType::Wavetable
+            | ModuleType::String
+            | ModuleType::Resonator => &["freq"],
+            ModuleType::Filter => &["cutoff", "res"],
+            ModuleType::Envelope => &["attack", "decay"],
+            ModuleType::Vca => &["gain"],
+            ModuleType::Slew => &["rise", "fall"],
+            ModuleType::ClockDiv => &["division"],
+            ModuleType::FmOperator => &["freq", "ratio", "index"],
+            ModuleType::Comparator => &["threshold", "hysteresis"],
+            ModuleType::Edge => &["length"],
+            ModuleType::Constant => &["value"],
+            ModuleType::Clock => &["bpm"],
+            ModuleType::Euclid => &["steps", "fills", "rotate"],
+            ModuleType::Turing => &["length", "seed"],
+            ModuleType::Bernoulli => &["prob"],
+            ModuleType::Crossfade => &["position"],
+            ModuleType::Pan => &["pan"],
+            ModuleType::Follower => &["attack", "release"],
+            ModuleType::Duck => &["depth", "range"],
+            ModuleType::RingMod => &["mix"],
+            ModuleType::FreqShift => &["shift"],
+            ModuleType::Chorus | ModuleType::Flanger | ModuleType::Phaser => &["rate"],
+            ModuleType::Random | ModuleType::Chaos => &["rate", "seed"],
+            ModuleType::OscIn => &["smooth"],
+            _ => &[],
+        };
+
+        let mut params = Vec::new();
+        for (index, (was, now)) in old.params.iter().zip(&new.params).enumerate() {
+            if was == now {
+                continue;
+            }
+            match settable.get(index) {
+                Some(param) if !param.is_empty() => params.push((*param, *now)),
+                _ => return None,
+            }
+        }
+        // A slew's fall time follows its rise time unless given
+        if new.module_type == ModuleType::Slew && new.params.len() == 1 && !params.is_empty() {
+            params.push(("fall", new.params[0]));
+        }
+        Some(params)
+    }
+
+    /// Resolve a file argument relative to the patch file's directory
+    fn resolve_patch_path(patch_dir: Option<&Path>, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_relative() {
+            if let Some(dir) = patch_dir {
+                let candidate = dir.join(path);
+                if candidate.exists() {
+                    return candidate;
+                }
+            }
+        }
+        path.to_path_buf()
+    }
+
+    /// Pan law named by the first argument, equal power if none is given
+    fn pan_law_arg(args: &[String]) -> Result<PanLaw> {
+        args.first().map_or(Ok(PanLaw::EqualPower), |name| {
+            PanLaw::from_name(name).ok_or_else(|| anyhow!("Unknown pan law: {name}"))
+        })
     }
 
     /// Start audio processing
@@ -479,16 +1253,15 @@ impl GraphEngine {
         #[allow(clippy::cast_precision_loss)]
         let sample_rate = config.sample_rate().0 as f32;
         self.sample_rate = sample_rate;
+        self.output_stage.lock().unwrap().set_sample_rate(sample_rate);
 
-        // Clone the graph reference for the audio thread
+        // Clone the graph reference for the audio thread; a fade left from
+        // a reload while stopped would only replay a stale patch
         let graph_clone = Arc::clone(&self.graph);
+        *self.crossfade.lock().unwrap() = None;
 
-        // For stereo output, we'll use the _output module
-        let output_module = if self.has_stereo_output {
-            Some("_output".to_string())
-        } else {
-            self.output_module.clone()
-        };
+        // A patch that uses `out` is found by the callback itself
+        let output_module = self.output_module.clone();
 
         // Build the output stream
         let stream = match config.sample_format() {
@@ -496,22 +1269,25 @@ impl GraphEngine {
                 &device,
                 &config.into(),
                 graph_clone,
+                Arc::clone(&self.crossfade),
+                Arc::clone(&self.output_stage),
                 output_module,
-                self.has_stereo_output,
             )?,
             cpal::SampleFormat::I16 => Self::build_stream::<i16>(
                 &device,
                 &config.into(),
                 graph_clone,
+                Arc::clone(&self.crossfade),
+                Arc::clone(&self.output_stage),
                 output_module,
-                self.has_stereo_output,
             )?,
             cpal::SampleFormat::U16 => Self::build_stream::<u16>(
                 &device,
                 &config.into(),
                 graph_clone,
+                Arc::clone(&self.crossfade),
+                Arc::clone(&self.output_stage),
                 output_module,
-                self.has_stereo_output,
             )?,
             _ => {
                 return Err(anyhow!("Unsupported sample format"));
@@ -545,6 +1321,9 @@ impl GraphEngine {
     pub fn clear_patch(&mut self) {
         self.stop();
         *self.graph.lock().unwrap() = GraphExecutor::new();
+        *self.crossfade.lock().unwrap() = None;
+        self.module_specs.clear();
+        self.osc_outputs.clear();
         self.output_module = None;
         self.output_port = None;
         self.has_stereo_output = false;
@@ -597,6 +1376,40 @@ impl GraphEngine {
             "switch" => ModuleType::Switch,
             "clockdiv" | "clock_div" => ModuleType::ClockDiv,
             "samplehold" | "sample_hold" | "sh" => ModuleType::SampleHold,
+            "wavetable" | "wt" => ModuleType::Wavetable,
+            "fm" | "fm_op" | "operator" => ModuleType::FmOperator,
+            "comparator" | "compare" | "comp" => ModuleType::Comparator,
+            "logic" | "gate_logic" => ModuleType::Logic,
+            "edge" | "edge_detect" => ModuleType::Edge,
+            "attenuverter" | "attenuvert" | "atten" => ModuleType::Attenuverter,
+            "rectifier" | "rect" => ModuleType::Rectifier,
+            "inverter" | "invert" | "inv" => ModuleType::Inverter,
+            "sumdiff" | "sum_diff" | "difference" => ModuleType::SumDiff,
+            "constant" | "const" | "dc" => ModuleType::Constant,
+            "clock" | "master_clock" => ModuleType::Clock,
+            "euclid" | "euclidean" => ModuleType::Euclid,
+            "turing" | "turing_machine" | "shift_register" => ModuleType::Turing,
+            "bernoulli" | "bernoulli_gate" | "coin" => ModuleType::Bernoulli,
+            "matrix" | "matrix_mixer" => ModuleType::Matrix,
+            "xfade" | "crossfade" | "crossfader" => ModuleType::Crossfade,
+            "pan" | "panner" => ModuleType::Pan,
+            "follower" | "envfollow" | "env_follower" => ModuleType::Follower,
+            "duck" | "ducker" | "sidechain" => ModuleType::Duck,
+            "ringmod" | "ring_mod" | "ring" => ModuleType::RingMod,
+            "freqshift" | "freq_shift" | "shifter" => ModuleType::FreqShift,
+            "chorus" | "ensemble" => ModuleType::Chorus,
+            "flanger" | "flange" => ModuleType::Flanger,
+            "phaser" | "phase_shifter" => ModuleType::Phaser,
+            "vocoder" | "vocode" => ModuleType::Vocoder,
+            "string" | "pluck" | "karplus" => ModuleType::String,
+            "resonator" | "modal" => ModuleType::Resonator,
+            "random" | "rand" | "rnd" => ModuleType::Random,
+            "chaos" | "attractor" => ModuleType::Chaos,
+            "poly" | "voices" => ModuleType::Poly,
+            "sum" | "polysum" => ModuleType::Sum,
+            "midifile" | "midi_file" | "smf" => ModuleType::MidiFile,
+            "midi" | "midi_in" | "midiin" => ModuleType::MidiIn,
+            "osc_in" | "oscin" | "osc_receive" => ModuleType::OscIn,
             _ => return None,
         };
 
@@ -619,6 +1432,54 @@ impl GraphEngine {
             ModuleType::Switch => Box::new(crate::graph_modules::GraphSwitch::new(4)),
             ModuleType::ClockDiv => Box::new(crate::graph_modules::GraphClockDiv::new(4)),
             ModuleType::SampleHold => Box::new(crate::graph_modules::GraphSampleHold::new()),
+            ModuleType::Wavetable => Box::new(crate::graph_modules::GraphWavetable::new(
+                Wavetable::builtin("basic").ok()?,
+                440.0,
+            )),
+            ModuleType::FmOperator => {
+                Box::new(crate::graph_modules::GraphFmOperator::new(440.0, 1.0, 0.0))
+            }
+            ModuleType::Comparator => Box::new(crate::graph_modules::GraphComparator::default()),
+            ModuleType::Logic => Box::new(crate::graph_modules::GraphLogic::default()),
+            ModuleType::Edge => Box::new(crate::graph_modules::GraphEdge::default()),
+            ModuleType::Attenuverter => {
+                Box::new(crate::graph_modules::GraphAttenuverter::default())
+            }
+            ModuleType::Rectifier => Box::new(crate::graph_modules::GraphRectifier::new()),
+            ModuleType::Inverter => Box::new(crate::graph_modules::GraphInverter::new()),
+            ModuleType::SumDiff => Box::new(crate::graph_modules::GraphSumDiff::new()),
+            ModuleType::Constant => Box::new(crate::graph_modules::GraphConstant::default()),
+            ModuleType::Clock => Box::new(crate::graph_modules::GraphClock::default()),
+            ModuleType::Euclid => Box::new(crate::graph_modules::GraphEuclid::default()),
+            ModuleType::Turing => Box::new(crate::graph_modules::GraphTuring::default()),
+            ModuleType::Bernoulli => Box::new(crate::graph_modules::GraphBernoulli::default()),
+            ModuleType::Matrix => Box::new(crate::graph_modules::GraphMatrix::default()),
+            ModuleType::Crossfade => Box::new(crate::graph_modules::GraphCrossfade::default()),
+            ModuleType::Pan => Box::new(crate::graph_modules::GraphPan::default()),
+            ModuleType::Follower => Box::new(crate::graph_modules::GraphFollower::default()),
+            ModuleType::Duck => Box::new(crate::graph_modules::GraphDuck::default()),
+            ModuleType::RingMod => Box::new(crate::graph_modules::GraphRingMod::default()),
+            ModuleType::FreqShift => Box::new(crate::graph_modules::GraphFreqShift::default()),
+            ModuleType::Chorus => Box::new(crate::graph_modules::GraphModulation::new(
+                crate::graph_modules::ModulationKind::Chorus,
+            )),
+            ModuleType::Flanger => Box::new(crate::graph_modules::GraphModulation::new(
+                crate::graph_modules::ModulationKind::Flanger,
+            )),
+            ModuleType::Phaser => Box::new(crate::graph_modules::GraphModulation::new(
+                crate::graph_modules::ModulationKind::Phaser,
+            )),
+            ModuleType::Vocoder => Box::new(crate::graph_modules::GraphVocoder::default()),
+            ModuleType::String => Box::new(crate::graph_modules::GraphString::default()),
+            ModuleType::Resonator => Box::new(crate::graph_modules::GraphResonator::default()),
+            ModuleType::Random => Box::new(crate::graph_modules::GraphRandom::default()),
+            ModuleType::Chaos => Box::new(crate::graph_modules::GraphChaos::default()),
+            ModuleType::Poly => Box::new(GraphPoly::default()),
+            ModuleType::Sum => Box::new(GraphSum::default()),
+            ModuleType::MidiFile => Box::new(GraphMidiFile::default()),
+            ModuleType::MidiIn => Box::new(GraphMidiIn::default()),
+            ModuleType::OscIn => Box::new(GraphOscIn::default()),
+            ModuleType::OscOut => return None, // Observer, no ports
             ModuleType::Output => return None, // Not implemented
         };
 
@@ -900,6 +1761,124 @@ impl GraphEngine {
         self.graph.lock().unwrap().release_manual_gates()
     }
 
+    /// Listen for Open Sound Control messages on a UDP port (0 picks a free
+    /// one), from programs on this machine only
+    ///
+    /// `/zim/<module>/<param> value` sets a parameter, `/zim/gate 1` and
+    /// `/zim/gate 0` press and release the manual gates, and every address
+    /// is made available to `osc_in` modules. Replaces any running listener.
+    ///
+    /// # Errors
+    /// Returns an error if the port cannot be opened
+    pub fn start_osc(&mut self, port: u16) -> Result<SocketAddr> {
+        self.start_osc_on(("127.0.0.1", port))
+    }
+
+    /// Like [`Self::start_osc`], but on a given local address, such as
+    /// `0.0.0.0:9000` for remote control; anyone who can reach it can then
+    /// set parameters and press the gates
+    ///
+    /// # Errors
+    /// Returns an error if the address cannot be resolved or opened
+    pub fn start_osc_on<A: ToSocketAddrs>(&mut self, addr: A) -> Result<SocketAddr> {
+        self.osc_server = None;
+        let graph = Arc::clone(&self.graph);
+        let values = self.osc_values.clone();
+        let server = OscServer::start(addr, move |message| {
+            if let Err(e) = Self::handle_osc_message(&graph, &values, &message) {
+                eprintln!("OSC {}: {e}", message.address);
+            }
+        })?;
+        let addr = server.local_addr();
+        self.osc_server = Some(server);
+        Ok(addr)
+    }
+
+    /// Stop listening for OSC messages
+    pub fn stop_osc(&mut self) {
+        self.osc_server = None;
+    }
+
+    /// The address the OSC listener is bound to, if it is running
+    #[must_use]
+    pub fn osc_addr(&self) -> Option<SocketAddr> {
+        self.osc_server.as_ref().map(OscServer::local_addr)
+    }
+
+    /// Send watched signals and gate edges to an OSC target until the patch is cleared
+    ///
+    /// Each watch is `module`, `module.port` or `module.port=/address`;
+    /// signals are sent at most `rate` times a second.
+    ///
+    /// # Errors
+    /// Returns an error if a watch is malformed or the target cannot be resolved
+    ///
+    /// # Panics
+    /// Panics if the graph mutex is poisoned
+    #[allow(dead_code)] // Library API; patches declare `osc_out` instead
+    pub fn add_osc_output(
+        &self,
+        target: &str,
+        watches: &[String],
+        rate: f32,
+    ) -> Result<SocketAddr> {
+        let (observer, addr) = self.osc_observer(target, watches, rate)?;
+        self.graph.lock().unwrap().add_observer(Box::new(observer));
+        Ok(addr)
+    }
+
+    /// Build the observer behind an OSC output, with the address it sends to
+    fn osc_observer(
+        &self,
+        target: &str,
+        watches: &[String],
+        rate: f32,
+    ) -> Result<(OscObserver, SocketAddr)> {
+        if watches.is_empty() {
+            return Err(anyhow!("osc_out needs at least one module.port to send"));
+        }
+        let watches =
+            watches.iter().map(|spec| OscWatch::parse(spec)).collect::<Result<Vec<_>>>()?;
+        let sender = OscSender::connect(target)?;
+        let addr = sender.target();
+        Ok((OscObserver::new(sender, watches, rate, self.sample_rate), addr))
+    }
+
+    /// Apply one incoming OSC message to the graph
+    fn handle_osc_message(
+        graph: &Mutex<GraphExecutor>,
+        values: &OscValues,
+        message: &OscMessage,
+    ) -> Result<()> {
+        // A message without arguments is a bang, which reads as 1
+        let value = message.value();
+        values.set(&message.address, value.unwrap_or(1.0));
+
+        let Some(path) = message.address.strip_prefix("/zim/") else {
+            return Ok(());
+        };
+        let parts: Vec<&str> = path.split('/').collect();
+        match parts.as_slice() {
+            ["gate"] => {
+                let mut graph = graph.lock().unwrap();
+                if value.unwrap_or(1.0) > 0.5 {
+                    graph.activate_manual_gates();
+                } else {
+                    graph.release_manual_gates();
+                }
+                Ok(())
+            }
+            [module, param] => {
+                let value = value.ok_or_else(|| anyhow!("Expected a numeric argument"))?;
+                graph.lock().unwrap().set_module_param(module, param, value)?;
+                // A poly module may now have more voices to feed
+                Self::prepare_poly_voices(graph);
+                Ok(())
+            }
+            _ => Err(anyhow!("Use /zim/<module>/<param> or /zim/gate")),
+        }
+    }
+
     /// Add an observer to the graph for monitoring
     ///
     /// # Panics
@@ -918,6 +1897,22 @@ impl GraphEngine {
         self.graph.lock().unwrap().process(sample_count);
     }
 
+    /// Process one block and return the stereo output the audio device would
+    /// get, including any reload crossfade
+    ///
+    /// # Panics
+    /// Panics if the graph mutex is poisoned
+    #[allow(dead_code)] // Used by tests
+    pub fn render_for_test(&self, frames: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut graph = self.graph.lock().unwrap();
+        graph.process(frames);
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        Self::read_output(&graph, self.output_module.as_deref(), frames, &mut left, &mut right);
+        let mut old = (Vec::new(), Vec::new());
+        Self::apply_crossfade(&self.crossfade, frames, &mut left, &mut right, &mut old);
+        (left, right)
+    }
+
     /// Get access to the observer manager for test inspection
     ///
     /// # Panics
@@ -927,6 +1922,58 @@ impl GraphEngine {
         self.graph.lock().unwrap()
     }
 
+    /// Silence the output immediately, keeping the patch loaded
+    ///
+    /// # Panics
+    /// Panics if the output stage mutex is poisoned
+    pub fn panic(&self) {
+        self.output_stage.lock().unwrap().panic();
+    }
+
+    /// Resume output after `panic` or a non-finite fault
+    ///
+    /// # Panics
+    /// Panics if a mutex is poisoned
+    pub fn unmute(&self) {
+        self.graph.lock().unwrap().clear_nonfinite();
+        self.output_stage.lock().unwrap().unmute();
+    }
+
+    /// Why the output was muted, if a module produced a NaN or infinity
+    ///
+    /// # Panics
+    /// Panics if the output stage mutex is poisoned
+    #[must_use]
+    pub fn output_fault(&self) -> Option<String> {
+        self.output_stage.lock().unwrap().fault().map(str::to_string)
+    }
+
+    /// Whether the output is currently silenced by `panic` or a fault
+    ///
+    /// # Panics
+    /// Panics if the output stage mutex is poisoned
+    #[must_use]
+    pub fn is_output_muted(&self) -> bool {
+        self.output_stage.lock().unwrap().is_muted()
+    }
+
+    /// Whether the master brickwall limiter is active
+    ///
+    /// # Panics
+    /// Panics if the output stage mutex is poisoned
+    #[must_use]
+    pub fn limiter_enabled(&self) -> bool {
+        self.output_stage.lock().unwrap().limiter_enabled()
+    }
+
+    /// Enable or bypass the master brickwall limiter
+    ///
+    /// # Panics
+    /// Panics if the output stage mutex is poisoned
+    pub fn set_limiter_enabled(&self, enabled: bool) {
+        self.output_stage.lock().unwrap().set_limiter_enabled(enabled);
+    }
+
     /// Build an audio stream for the given sample format
     ///
     /// # Errors
@@ -935,8 +1982,9 @@ impl GraphEngine {
         device: &cpal::Device,
         config: &cpal::StreamConfig,
         graph: Arc<Mutex<GraphExecutor>>,
+        crossfade: Arc<Mutex<Option<Crossfade>>>,
+        output_stage: Arc<Mutex<OutputStage>>,
         output_module: Option<String>,
-        is_stereo: bool,
     ) -> Result<cpal::Stream>
     where
         T: cpal::Sample + cpal::SizedSample + cpal::FromSample<f32>,
@@ -945,6 +1993,10 @@ impl GraphEngine {
 
         let err_fn = |err| eprintln!("Audio stream error: {err}");
 
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        let mut fade_buffers = (Vec::new(), Vec::new());
+
         let stream = device.build_output_stream(
             config,
             move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
@@ -961,44 +2013,39 @@ impl GraphEngine {
                     graph.process(samples_per_channel);
 
                     // Get output from the designated module
-                    if let Some(ref output_module) = output_module {
-                        if is_stereo {
-                            // Get stereo output
-                            let left_buffer = graph.get_output(output_module, "left");
-                            let right_buffer = graph.get_output(output_module, "right");
-
-                            if let (Some(left), Some(right)) = (left_buffer, right_buffer) {
-                                // Interleave stereo samples
-                                for (i, frame) in data.chunks_mut(channels).enumerate() {
-                                    if i < left.len() && i < right.len() {
-                                        let left_sample = cpal::Sample::from_sample(left[i]);
-                                        let right_sample = cpal::Sample::from_sample(right[i]);
-
-                                        if channels >= 2 {
-                                            frame[0] = left_sample;
-                                            frame[1] = right_sample;
-                                        } else {
-                                            // Mono output - mix left and right
-                                            let mixed = (left[i] + right[i]) * 0.5;
-                                            frame[0] = cpal::Sample::from_sample(mixed);
-                                        }
-                                    }
-                                }
-                            }
+                    Self::read_output(
+                        &graph,
+                        output_module.as_deref(),
+                        samples_per_channel,
+                        &mut left,
+                        &mut right,
+                    );
+                    Self::apply_crossfade(
+                        &crossfade,
+                        samples_per_channel,
+                        &mut left,
+                        &mut right,
+                        &mut fade_buffers,
+                    );
+                    let frames = left.len();
+
+                    // Safety stage: DC blocker, limiter and NaN guard
+                    if let Ok(mut stage) = output_stage.lock() {
+                        let source = graph.nonfinite_source();
+                        if let Some(fault) = stage.process(&mut left, &mut right, source) {
+                            eprintln!("Output muted: {fault}. Type 'unmute' to resume.");
+                        }
+                    }
+
+                    // Interleave into the device buffer
+                    for (i, frame) in data.chunks_mut(channels).take(frames).enumerate() {
+                        if channels >= 2 {
+                            frame[0] = cpal::Sample::from_sample(left[i]);
+                            frame[1] = cpal::Sample::from_sample(right[i]);
                         } else {
-                            // Legacy mono output
-                            if let Some(buffer) = graph.get_output(output_module, "output") {
-                                // Copy the output buffer to the audio stream
-                                for (i, frame) in data.chunks_mut(channels).enumerate() {
-                                    if i < buffer.len() {
-                                        let value = buffer[i];
-                                        let sample = cpal::Sample::from_sample(value);
-                                        for channel_sample in frame.iter_mut() {
-                                            *channel_sample = sample;
-                                        }
-                                    }
-                                }
-                            }
+                            // Mono device - mix left and right
+                            let mixed = (left[i] + right[i]) * 0.5;
+                            frame[0] = cpal::Sample::from_sample(mixed);
                         }
                     }
                 }
@@ -1009,4 +2056,74 @@ impl GraphEngine {
 
         Ok(stream)
     }
+
+    /// Copy a graph's output for one block into `left` and `right`
+    ///
+    /// A graph with the stereo output module plays through it, so the
+    /// routing follows whichever graph is playing, across reloads too.
+    fn read_output(
+        graph: &GraphExecutor,
+        output_module: Option<&str>,
+        frames: usize,
+        left: &mut Vec<f32>,
+        right: &mut Vec<f32>,
+    ) {
+        left.clear();
+        right.clear();
+
+        let stereo = (graph.get_output("_output", "left"), graph.get_output("_output", "right"));
+        if let (Some(l), Some(r)) = stereo {
+            left.extend_from_slice(l);
+            right.extend_from_slice(r);
+        } else if let Some(buffer) =
+            output_module.and_then(|module| graph.get_output(module, "output"))
+        {
+            // Legacy mono output goes to both channels
+            left.extend_from_slice(buffer);
+            right.extend_from_slice(buffer);
+        }
+
+        let frames = left.len().min(right.len()).min(frames);
+        left.truncate(frames);
+        right.truncate(frames);
+    }
+
+    /// Mix in the old graph of a full rebuild while it fades out
+    fn apply_crossfade(
+        crossfade: &Mutex<Option<Crossfade>>,
+        frames: usize,
+        left: &mut Vec<f32>,
+        right: &mut Vec<f32>,
+        old: &mut (Vec<f32>, Vec<f32>),
+    ) {
+        let Ok(mut crossfade) = crossfade.lock() else {
+            return;
+        };
+        // A finished fade waits for the control thread to drop its graph
+        let Some(fade) = crossfade.as_mut().filter(|fade| !fade.is_finished()) else {
+            return;
+        };
+
+        fade.graph.process(frames);
+        Self::read_output(
+            &fade.graph,
+            fade.output_module.as_deref(),
+            frames,
+            &mut old.0,
+            &mut old.1,
+        );
+        // A new patch with no output yet fades in from silence
+        left.resize(frames, 0.0);
+        right.resize(frames, 0.0);
+
+        // Equal-power curves, as the two patches are unrelated
+        for i in 0..frames {
+            #[allow(clippy::cast_precision_loss)]
+            let t = ((fade.position + i) as f32 / fade.length.max(1) as f32).min(1.0);
+            let (fade_in, fade_out) = (t * FRAC_PI_2).sin_cos();
+            left[i] = left[i] * fade_in + old.0.get(i).copied().unwrap_or(0.0) * fade_out;
+            right[i] = right[i] * fade_in + old.1.get(i).copied().unwrap_or(0.0) * fade_out;
+        }
+        fade.position += frames;
+    }
 }
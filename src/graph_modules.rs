@@ -3,8 +3,14 @@
 #![allow(clippy::pedantic)]
 #![allow(clippy::nursery)]
 
-use crate::graph::{GraphModule, PortBuffers, PortDescriptor};
+use crate::graph::{GraphModule, PortBuffers, PortDescriptor, MAX_CHANNELS};
+use crate::midi_file::{EventKind, MidiFile};
+use crate::midi_input::{MidiInputPort, MidiMessage};
+use crate::osc::OscValues;
+use crate::wavetable::Wavetable;
 use anyhow::{anyhow, Result};
+use std::f32::consts::TAU;
+use std::sync::mpsc::{self, Receiver};
 
 /// Oscillator module with multiple waveform outputs
 pub struct GraphOscillator {
@@ -110,11 +116,8 @@ impl GraphModule for GraphOscillator {
             triangle_out[i] =
                 if self.phase < 0.5 { self.phase * 4.0 - 1.0 } else { 3.0 - self.phase * 4.0 };
 
-            // Advance phase
-            self.phase += instant_freq / self.sample_rate;
-            if self.phase >= 1.0 {
-                self.phase -= 1.0;
-            }
+            // Advance phase (wraps both ways so negative FM excursions run backwards)
+            self.phase = (self.phase + instant_freq / self.sample_rate).rem_euclid(1.0);
         }
     }
 
@@ -506,6 +509,8 @@ impl GraphModule for GraphManualGate {
 pub struct GraphStereoOutput {
     left_connected: bool,
     right_connected: bool,
+    // Where poly left, right and mono inputs are summed
+    mix: [Vec<f32>; 3],
 }
 
 impl GraphStereoOutput {
@@ -513,6 +518,7 @@ impl GraphStereoOutput {
         Self {
             left_connected: false,
             right_connected: false,
+            mix: Default::default(),
         }
     }
 
@@ -571,10 +577,16 @@ impl GraphModule for GraphStereoOutput {
         ]
     }
 
+    fn is_polyphonic(&self) -> bool {
+        true
+    }
+
     fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
-        let left_in = inputs.get("left").map(|b| b.as_slice()).unwrap_or(&[]);
-        let right_in = inputs.get("right").map(|b| b.as_slice()).unwrap_or(&[]);
-        let mono_in = inputs.get("mono").map(|b| b.as_slice()).unwrap_or(&[]);
+        // Poly cables are summed so every voice reaches the speakers
+        let [left_mix, right_mix, mono_mix] = &mut self.mix;
+        let left_in = mixdown(inputs, "left", sample_count, left_mix);
+        let right_in = mixdown(inputs, "right", sample_count, right_mix);
+        let mono_in = mixdown(inputs, "mono", sample_count, mono_mix);
 
         let [left_out, right_out] = outputs.get_many_mut(["left", "right"]);
         let left_out = left_out.unwrap();
@@ -615,10 +627,37 @@ impl GraphModule for GraphStereoOutput {
     }
 }
 
+/// Linear congruential generator shared by the random modules
+#[derive(Clone, Copy)]
+struct Lcg {
+    state: u32,
+}
+
+impl Lcg {
+    fn new(seed: u32) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_mul(1664525).wrapping_add(1013904223);
+        self.state
+    }
+
+    /// Random value in -1 to 1
+    fn next_bipolar(&mut self) -> f32 {
+        (self.next_u32() as i32 as f32) / (i32::MAX as f32)
+    }
+
+    /// Random value in 0 to 1
+    fn next_unipolar(&mut self) -> f32 {
+        self.next_u32() as f32 / u32::MAX as f32
+    }
+}
+
 /// Noise generator with multiple noise colors
 pub struct GraphNoiseGen {
     // Random number generator state
-    rng_state: u32,
+    rng: Lcg,
     // Pink noise state (Paul Kellet's method)
     pink_state: [f32; 7],
     // Brown noise state
@@ -628,7 +667,7 @@ pub struct GraphNoiseGen {
 impl GraphNoiseGen {
     pub fn new() -> Self {
         Self {
-            rng_state: 12345, // Seed
+            rng: Lcg::new(12345), // Seed
             pink_state: [0.0; 7],
             brown_state: 0.0,
         }
@@ -636,9 +675,7 @@ impl GraphNoiseGen {
 
     // Linear congruential generator for white noise
     fn next_random(&mut self) -> f32 {
-        self.rng_state = self.rng_state.wrapping_mul(1664525).wrapping_add(1013904223);
-        // Convert to float in range -1 to 1
-        (self.rng_state as i32 as f32) / (i32::MAX as f32)
+        self.rng.next_bipolar()
     }
 
     // Generate pink noise using Paul Kellet's method
@@ -742,7 +779,7 @@ impl GraphModule for GraphNoiseGen {
     fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
         match name {
             "seed" => {
-                self.rng_state = value as u32;
+                self.rng = Lcg::new(value as u32);
                 Ok(())
             }
             _ => Err(anyhow!("Unknown parameter: {name}")),
@@ -751,7 +788,7 @@ impl GraphModule for GraphNoiseGen {
 
     fn get_param(&self, name: &str) -> Option<f32> {
         match name {
-            "seed" => Some(self.rng_state as f32),
+            "seed" => Some(self.rng.state as f32),
             _ => None,
         }
     }
@@ -903,19 +940,33 @@ impl GraphModule for GraphMonoMixer {
     }
 }
 
+/// Peak level of a triggered or cycling rise
+const FUNCTION_PEAK: f32 = 1.0;
+
 /// Slew generator module - smooths stepped CV signals
+///
+/// Also works as a function generator in the style of the Serge DUSG: a
+/// trigger fires a full rise to the peak and a fall back to the input level,
+/// and cycle mode retriggers at the end of every fall so it runs as an LFO or
+/// VCO.
 pub struct GraphSlewGen {
     rise_time: f32, // Time to rise from 0 to 1
     fall_time: f32, // Time to fall from 1 to 0
     current_value: f32,
     target_value: f32,
     sample_rate: f32,
-    curve_type: SlewCurve,
+    curve: f32, // -1 = logarithmic, 0 = linear, 1 = exponential
     // Gate state tracking
     previous_value: f32,
     is_rising: bool,
     is_falling: bool,
     reached_target: bool,
+    // Function generator state
+    cycle: bool,
+    stage: FunctionStage,
+    stage_from: f32,
+    stage_progress: f32,
+    last_trig: f32,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -925,6 +976,35 @@ pub enum SlewCurve {
     Logarithmic,
 }
 
+impl SlewCurve {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lin" | "linear" => Some(Self::Linear),
+            "exp" | "exponential" => Some(Self::Exponential),
+            "log" | "logarithmic" => Some(Self::Logarithmic),
+            _ => None,
+        }
+    }
+
+    /// Position on the continuous curve control
+    pub fn amount(self) -> f32 {
+        match self {
+            Self::Linear => 0.0,
+            Self::Exponential => 1.0,
+            Self::Logarithmic => -1.0,
+        }
+    }
+}
+
+/// Which segment of a triggered or cycling function is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FunctionStage {
+    /// Following the input like a plain slew limiter
+    Idle,
+    Rising,
+    Falling,
+}
+
 impl GraphSlewGen {
     pub fn new(rise_time: f32, fall_time: f32) -> Self {
         Self {
@@ -933,40 +1013,62 @@ impl GraphSlewGen {
             current_value: 0.0,
             target_value: 0.0,
             sample_rate: 44100.0,
-            curve_type: SlewCurve::Linear,
+            curve: 0.0,
             // Initialize gate state
             previous_value: 0.0,
             is_rising: false,
             is_falling: false,
             reached_target: false, // Start as not at target to allow first transition to fire gates
+            cycle: false,
+            stage: FunctionStage::Idle,
+            stage_from: 0.0,
+            stage_progress: 0.0,
+            last_trig: 0.0,
         }
     }
 
-    fn apply_curve(&self, progress: f32) -> f32 {
-        match self.curve_type {
-            SlewCurve::Linear => progress,
-            SlewCurve::Exponential => {
-                // Exponential curve: fast at start, slow at end
-                1.0 - (-4.0 * progress).exp()
-            }
-            SlewCurve::Logarithmic => {
-                // Logarithmic curve: slow at start, fast at end
-                if progress <= 0.0 {
-                    0.0
-                } else {
-                    (1.0 + 4.0 * progress).ln() / (1.0_f32 + 4.0).ln()
-                }
-            }
-        }
+    pub fn with_curve(mut self, curve: SlewCurve) -> Self {
+        self.curve = curve.amount();
+        self
     }
 
-    fn set_curve_from_param(&mut self, value: f32) {
-        self.curve_type = match value as i32 {
-            0 => SlewCurve::Linear,
-            1 => SlewCurve::Exponential,
-            2 => SlewCurve::Logarithmic,
-            _ => SlewCurve::Linear,
+    pub fn with_cycle(mut self, cycle: bool) -> Self {
+        self.cycle = cycle;
+        self
+    }
+
+    fn apply_curve(&self, progress: f32, curve: f32) -> f32 {
+        // Exponential curve: fast at start, slow at end
+        let exponential = 1.0 - (-4.0 * progress).exp();
+        // Logarithmic curve: slow at start, fast at end
+        let logarithmic = if progress <= 0.0 {
+            0.0
+        } else {
+            (1.0 + 4.0 * progress).ln() / (1.0_f32 + 4.0).ln()
         };
+        if curve >= 0.0 {
+            progress + (exponential - progress) * curve
+        } else {
+            progress + (logarithmic - progress) * -curve
+        }
+    }
+
+    /// Shape a function generator segment, mapping 0-1 progress to 0-1 level
+    fn shape_segment(progress: f32, curve: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        let exponential = |p: f32| (1.0 - (-4.0 * p).exp()) / (1.0 - (-4.0f32).exp());
+        if curve >= 0.0 {
+            progress + (exponential(progress) - progress) * curve
+        } else {
+            let logarithmic = 1.0 - exponential(1.0 - progress);
+            progress + (logarithmic - progress) * -curve
+        }
+    }
+
+    fn start_rise(&mut self) {
+        self.stage = FunctionStage::Rising;
+        self.stage_from = self.current_value;
+        self.stage_progress = 0.0;
     }
 }
 
@@ -998,6 +1100,21 @@ impl GraphModule for GraphSlewGen {
                 default_value: 0.0,
                 description: "Fall time CV (0V = use parameter, >0V = override)".to_string(),
             },
+            PortDescriptor {
+                name: "trig".to_string(),
+                default_value: 0.0,
+                description: "Fire a full rise and fall on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "cycle".to_string(),
+                default_value: 0.0,
+                description: "Self-oscillate while high (or with the cycle parameter)".to_string(),
+            },
+            PortDescriptor {
+                name: "curve".to_string(),
+                default_value: 0.0,
+                description: "CV added to the curve (-1 = log, 0 = linear, 1 = exp)".to_string(),
+            },
         ]
     }
 
@@ -1027,6 +1144,9 @@ impl GraphModule for GraphSlewGen {
         let input_signal = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
         let rise_cv = inputs.get("rise").map(|b| b.as_slice()).unwrap_or(&[]);
         let fall_cv = inputs.get("fall").map(|b| b.as_slice()).unwrap_or(&[]);
+        let trig_input = inputs.get("trig").map(|b| b.as_slice()).unwrap_or(&[]);
+        let cycle_input = inputs.get("cycle").map(|b| b.as_slice()).unwrap_or(&[]);
+        let curve_cv = inputs.get("curve").map(|b| b.as_slice()).unwrap_or(&[]);
         let [out, eor_out, eoc_out] = outputs.get_many_mut(["out", "eor", "eoc"]);
         let out = out.unwrap();
         let eor_out = eor_out.unwrap();
@@ -1055,6 +1175,59 @@ impl GraphModule for GraphSlewGen {
                 self.fall_time.max(0.001) // Parameter default
             };
 
+            let trig_value = if i < trig_input.len() { trig_input[i] } else { 0.0 };
+            let cycle_value = if i < cycle_input.len() { cycle_input[i] } else { 0.0 };
+            let curve_value = if i < curve_cv.len() { curve_cv[i] } else { 0.0 };
+            let curve = (self.curve + curve_value).clamp(-1.0, 1.0);
+            let cycling = self.cycle || cycle_value > 0.5;
+
+            // A trigger restarts the rise unless one is already under way
+            let triggered = trig_value > 0.5 && self.last_trig <= 0.5;
+            self.last_trig = trig_value;
+            if (triggered && self.stage != FunctionStage::Rising)
+                || (cycling && self.stage == FunctionStage::Idle)
+            {
+                self.start_rise();
+            }
+
+            if self.stage != FunctionStage::Idle {
+                // Segment times scale with the distance left to travel, so a
+                // retrigger partway down keeps the same slope
+                let (to, time) = if self.stage == FunctionStage::Rising {
+                    (FUNCTION_PEAK, effective_rise_time)
+                } else {
+                    (input_value, effective_fall_time)
+                };
+                let span = (to - self.stage_from).abs().clamp(0.001, 1.0);
+                self.stage_progress += 1.0 / (time * span * self.sample_rate);
+
+                let shaped = Self::shape_segment(self.stage_progress, curve);
+                self.current_value = self.stage_from + (to - self.stage_from) * shaped;
+
+                let rising = self.stage == FunctionStage::Rising;
+                eor_out[i] = if rising { 0.0 } else { 1.0 };
+                eoc_out[i] = if rising { 1.0 } else { 0.0 };
+
+                if self.stage_progress >= 1.0 {
+                    self.current_value = to;
+                    self.stage_from = to;
+                    self.stage_progress = 0.0;
+                    if rising {
+                        eor_out[i] = 1.0;
+                        self.stage = FunctionStage::Falling;
+                    } else {
+                        eoc_out[i] = 1.0;
+                        self.stage = FunctionStage::Idle;
+                        self.reached_target = true;
+                        self.is_rising = false;
+                        self.is_falling = false;
+                    }
+                }
+
+                out[i] = self.current_value;
+                continue;
+            }
+
             // Track previous state for gate detection
             let was_at_target = self.reached_target;
             let previous_value = self.current_value;
@@ -1091,7 +1264,7 @@ impl GraphModule for GraphSlewGen {
                 let raw_progress = step_size;
 
                 // Apply curve shaping
-                let shaped_progress = self.apply_curve(raw_progress);
+                let shaped_progress = self.apply_curve(raw_progress, curve);
                 let step = diff * shaped_progress.min(1.0);
 
                 self.current_value += step;
@@ -1152,7 +1325,16 @@ impl GraphModule for GraphSlewGen {
                 Ok(())
             }
             "curve" => {
-                self.set_curve_from_param(value);
+                // 2 is the old code for logarithmic
+                self.curve = if value == 2.0 {
+                    SlewCurve::Logarithmic.amount()
+                } else {
+                    value.clamp(-1.0, 1.0)
+                };
+                Ok(())
+            }
+            "cycle" => {
+                self.cycle = value > 0.5;
                 Ok(())
             }
             _ => Err(anyhow!("Unknown parameter: {name}")),
@@ -1163,11 +1345,8 @@ impl GraphModule for GraphSlewGen {
         match name {
             "rise" => Some(self.rise_time),
             "fall" => Some(self.fall_time),
-            "curve" => Some(match self.curve_type {
-                SlewCurve::Linear => 0.0,
-                SlewCurve::Exponential => 1.0,
-                SlewCurve::Logarithmic => 2.0,
-            }),
+            "curve" => Some(self.curve),
+            "cycle" => Some(if self.cycle { 1.0 } else { 0.0 }),
             _ => None,
         }
     }
@@ -2064,19 +2243,77 @@ impl GraphModule for GraphSeq8 {
     }
 }
 
+/// Gain curve shared by panners, crossfaders and the stereo mixer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanLaw {
+    /// Gains sum to one (-6dB in the middle)
+    Linear,
+    /// Sine/cosine gains keep power constant (-3dB in the middle)
+    EqualPower,
+    /// Halfway between linear and equal power (-4.5dB in the middle)
+    Compromise,
+}
+
+impl PanLaw {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" | "lin" => Some(Self::Linear),
+            "equal" | "equal_power" | "power" => Some(Self::EqualPower),
+            "compromise" | "-4.5db" => Some(Self::Compromise),
+            _ => None,
+        }
+    }
+
+    pub fn from_param(value: f32) -> Self {
+        match value as i32 {
+            0 => Self::Linear,
+            2 => Self::Compromise,
+            _ => Self::EqualPower,
+        }
+    }
+
+    pub fn as_param(self) -> f32 {
+        match self {
+            Self::Linear => 0.0,
+            Self::EqualPower => 1.0,
+            Self::Compromise => 2.0,
+        }
+    }
+
+    /// Left/right gains for a pan position (-1 = left, 0 = center, +1 = right)
+    pub fn gains(self, pan: f32) -> (f32, f32) {
+        let position = (pan.clamp(-1.0, 1.0) + 1.0) * 0.5; // 0 to 1
+        let linear = (1.0 - position, position);
+        let angle = position * std::f32::consts::FRAC_PI_2;
+        let power = (angle.cos(), angle.sin());
+        match self {
+            Self::Linear => linear,
+            Self::EqualPower => power,
+            Self::Compromise => ((linear.0 * power.0).sqrt(), (linear.1 * power.1).sqrt()),
+        }
+    }
+}
+
 /// Stereo mixer with voltage-controlled panning
 /// Supports multiple stereo inputs with individual pan controls
-/// Uses constant-power panning laws for smooth stereo imaging
+/// Uses a selectable pan law, constant-power by default
 pub struct GraphStereoMixer {
     channels: usize,
+    law: PanLaw,
 }
 
 impl GraphStereoMixer {
     pub fn new(channels: usize) -> Self {
         Self {
             channels: channels.clamp(2, 8), // 2-8 channels
+            law: PanLaw::EqualPower,
         }
     }
+
+    pub fn with_law(mut self, law: PanLaw) -> Self {
+        self.law = law;
+        self
+    }
 }
 
 impl Default for GraphStereoMixer {
@@ -2168,16 +2405,8 @@ impl GraphModule for GraphStereoMixer {
                 let pan_cv = if i < pan_in.len() { pan_in[i] } else { 0.0 };
                 let level = if i < level_in.len() { level_in[i] } else { 1.0 };
 
-                // Clamp pan CV to valid range (-1 to +1)
-                let pan = pan_cv.clamp(-1.0, 1.0);
-
-                // Constant-power panning using sine/cosine laws
                 // Pan: -1 = full left, 0 = center, +1 = full right
-                // Convert to radians: -1 -> 0, 0 -> π/4, +1 -> π/2
-                let pan_angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4; // 0 to π/2
-
-                let left_gain = pan_angle.cos(); // 1.0 at left, 0.707 at center, 0.0 at right
-                let right_gain = pan_angle.sin(); // 0.0 at left, 0.707 at center, 1.0 at right
+                let (left_gain, right_gain) = self.law.gains(pan_cv);
 
                 // Apply panning and level to both input channels
                 // If input is mono (only left connected), pan it across stereo field
@@ -2205,14 +2434,19 @@ impl GraphModule for GraphStereoMixer {
         }
     }
 
-    fn set_param(&mut self, _name: &str, _value: f32) -> Result<()> {
-        // No static parameters - all control via CV
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        // Levels and pans are controlled via CV; only the pan law is static
+        if name == "law" {
+            self.law = PanLaw::from_param(value);
+        }
         Ok(())
     }
 
-    fn get_param(&self, _name: &str) -> Option<f32> {
-        // No static parameters
-        None
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "law" => Some(self.law.as_param()),
+            _ => None,
+        }
     }
 }
 
@@ -2316,3 +2550,5399 @@ impl GraphModule for GraphSampleHold {
         }
     }
 }
+
+/// Wavetable oscillator that morphs between the frames of a table
+/// Uses band-limited mipmaps so high notes don't alias
+pub struct GraphWavetable {
+    table: Wavetable,
+    frequency: f32,
+    position: f32,
+    phase: f32,
+    last_sync: f32,
+    sample_rate: f32,
+}
+
+impl GraphWavetable {
+    pub fn new(table: Wavetable, frequency: f32) -> Self {
+        Self {
+            table,
+            frequency,
+            position: 0.0,
+            phase: 0.0,
+            last_sync: 0.0,
+            sample_rate: 44100.0,
+        }
+    }
+}
+
+impl GraphModule for GraphWavetable {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "freq".to_string(),
+                default_value: 0.0,
+                description: "Frequency control input (Hz, 0 = use base freq)".to_string(),
+            },
+            PortDescriptor {
+                name: "voct".to_string(),
+                default_value: 0.0,
+                description: "Pitch offset in volts per octave".to_string(),
+            },
+            PortDescriptor {
+                name: "position".to_string(),
+                default_value: 0.0,
+                description: "Frame position CV (0-1, added to position parameter)".to_string(),
+            },
+            PortDescriptor {
+                name: "fm".to_string(),
+                default_value: 0.0,
+                description: "Frequency modulation input".to_string(),
+            },
+            PortDescriptor {
+                name: "sync".to_string(),
+                default_value: 0.0,
+                description: "Sync/reset input".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Wavetable output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let freq_input = inputs.get("freq").map(|b| b.as_slice()).unwrap_or(&[]);
+        let voct_input = inputs.get("voct").map(|b| b.as_slice()).unwrap_or(&[]);
+        let position_input = inputs.get("position").map(|b| b.as_slice()).unwrap_or(&[]);
+        let fm_input = inputs.get("fm").map(|b| b.as_slice()).unwrap_or(&[]);
+        let sync_input = inputs.get("sync").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let out = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            let freq_cv = if i < freq_input.len() { freq_input[i] } else { 0.0 };
+            let voct = if i < voct_input.len() { voct_input[i] } else { 0.0 };
+            let position_cv = if i < position_input.len() { position_input[i] } else { 0.0 };
+            let fm_amount = if i < fm_input.len() { fm_input[i] } else { 0.0 };
+            let sync = if i < sync_input.len() { sync_input[i] } else { 0.0 };
+
+            // Handle sync on rising edge
+            if sync > 0.0 && self.last_sync <= 0.0 {
+                self.phase = 0.0;
+            }
+            self.last_sync = sync;
+
+            let base_freq = if freq_cv > 0.0 { freq_cv } else { self.frequency };
+            let instant_freq = base_freq * voct.exp2() * (1.0 + fm_amount);
+
+            let level = Wavetable::mip_level(instant_freq, self.sample_rate);
+            let position = (self.position + position_cv).clamp(0.0, 1.0);
+            out[i] = self.table.sample(position, self.phase, level);
+
+            // Advance phase
+            self.phase = (self.phase + instant_freq / self.sample_rate).rem_euclid(1.0);
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "frequency" | "freq" => {
+                self.frequency = value;
+                Ok(())
+            }
+            "position" | "pos" => {
+                self.position = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "frequency" | "freq" => Some(self.frequency),
+            "position" | "pos" => Some(self.position),
+            "frames" => Some(self.table.frame_count() as f32),
+            _ => None,
+        }
+    }
+}
+
+/// FM operator with linear through-zero FM, phase modulation and feedback
+/// A built-in AD envelope modulates the index, so operators can be stacked DX-style
+pub struct GraphFmOperator {
+    frequency: f32,
+    ratio: f32,
+    index: f32,
+    feedback: f32,
+    attack: f32,
+    decay: f32,
+    env_depth: f32,
+    phase: f32,
+    last_out: f32,
+    env_phase: EnvelopePhase,
+    env_value: f32,
+    env_time: f32,
+    last_gate: f32,
+    sample_rate: f32,
+}
+
+impl GraphFmOperator {
+    pub fn new(frequency: f32, ratio: f32, index: f32) -> Self {
+        Self {
+            frequency,
+            ratio,
+            index,
+            feedback: 0.0,
+            attack: 0.01,
+            decay: 0.5,
+            env_depth: 0.0,
+            phase: 0.0,
+            last_out: 0.0,
+            env_phase: EnvelopePhase::Idle,
+            env_value: 0.0,
+            env_time: 0.0,
+            last_gate: 0.0,
+            sample_rate: 44100.0,
+        }
+    }
+
+    /// Advance the index envelope by one sample
+    fn next_env(&mut self, gate: f32) -> f32 {
+        if gate > 0.5 && self.last_gate <= 0.5 {
+            self.env_phase = EnvelopePhase::Attack;
+            self.env_time = 0.0;
+        }
+        self.last_gate = gate;
+
+        match self.env_phase {
+            EnvelopePhase::Idle => self.env_value = 0.0,
+            EnvelopePhase::Attack => {
+                if self.attack > 0.0 && self.env_time < self.attack {
+                    self.env_value = self.env_time / self.attack;
+                } else {
+                    self.env_value = 1.0;
+                    self.env_phase = EnvelopePhase::Decay;
+                    self.env_time = 0.0;
+                }
+            }
+            EnvelopePhase::Decay => {
+                if self.decay > 0.0 && self.env_time < self.decay {
+                    self.env_value = 1.0 - self.env_time / self.decay;
+                } else {
+                    self.env_value = 0.0;
+                    self.env_phase = EnvelopePhase::Idle;
+                    self.env_time = 0.0;
+                }
+            }
+        }
+
+        self.env_time += 1.0 / self.sample_rate;
+        self.env_value
+    }
+}
+
+impl GraphModule for GraphFmOperator {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "freq".to_string(),
+                default_value: 0.0,
+                description: "Frequency control input (Hz, 0 = use base freq)".to_string(),
+            },
+            PortDescriptor {
+                name: "fm".to_string(),
+                default_value: 0.0,
+                description: "Linear through-zero FM input (scaled by index)".to_string(),
+            },
+            PortDescriptor {
+                name: "pm".to_string(),
+                default_value: 0.0,
+                description: "Phase modulation input (scaled by index)".to_string(),
+            },
+            PortDescriptor {
+                name: "index".to_string(),
+                default_value: 0.0,
+                description: "Modulation index CV (added to index parameter)".to_string(),
+            },
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "Gate input triggering the index envelope".to_string(),
+            },
+            PortDescriptor {
+                name: "sync".to_string(),
+                default_value: 0.0,
+                description: "Sync/reset input".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "out".to_string(),
+                default_value: 0.0,
+                description: "Operator output (sine, -1 to 1)".to_string(),
+            },
+            PortDescriptor {
+                name: "env".to_string(),
+                default_value: 0.0,
+                description: "Index envelope output (0 to 1)".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let freq_input = inputs.get("freq").map(|b| b.as_slice()).unwrap_or(&[]);
+        let fm_input = inputs.get("fm").map(|b| b.as_slice()).unwrap_or(&[]);
+        let pm_input = inputs.get("pm").map(|b| b.as_slice()).unwrap_or(&[]);
+        let index_input = inputs.get("index").map(|b| b.as_slice()).unwrap_or(&[]);
+        let gate_input = inputs.get("gate").map(|b| b.as_slice()).unwrap_or(&[]);
+        let sync_input = inputs.get("sync").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [out, env_out] = outputs.get_many_mut(["out", "env"]);
+        let out = out.unwrap();
+        let env_out = env_out.unwrap();
+
+        for i in 0..sample_count {
+            let freq_cv = if i < freq_input.len() { freq_input[i] } else { 0.0 };
+            let fm = if i < fm_input.len() { fm_input[i] } else { 0.0 };
+            let pm = if i < pm_input.len() { pm_input[i] } else { 0.0 };
+            let index_cv = if i < index_input.len() { index_input[i] } else { 0.0 };
+            let gate = if i < gate_input.len() { gate_input[i] } else { 0.0 };
+
+            // Handle sync
+            if i < sync_input.len() && sync_input[i] > 0.0 && (i == 0 || sync_input[i - 1] <= 0.0) {
+                self.phase = 0.0;
+            }
+
+            let env = self.next_env(gate);
+            let index = self.index + index_cv + env * self.env_depth;
+
+            let base_freq = if freq_cv > 0.0 { freq_cv } else { self.frequency };
+            let carrier_freq = base_freq * self.ratio;
+
+            // Linear FM: deviation proportional to carrier frequency, can go negative
+            let instant_freq = carrier_freq * (1.0 + fm * index);
+
+            // Phase modulation and self-feedback are applied to the read phase
+            let read_phase = self.phase + (pm * index + self.last_out * self.feedback) / TAU;
+            let sample = (read_phase * TAU).sin();
+
+            out[i] = sample;
+            env_out[i] = env;
+            self.last_out = sample;
+
+            // Advance phase in either direction (through-zero)
+            self.phase = (self.phase + instant_freq / self.sample_rate).rem_euclid(1.0);
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "frequency" | "freq" => {
+                self.frequency = value;
+                Ok(())
+            }
+            "ratio" => {
+                self.ratio = value;
+                Ok(())
+            }
+            "index" => {
+                self.index = value;
+                Ok(())
+            }
+            "feedback" | "fb" => {
+                self.feedback = value;
+                Ok(())
+            }
+            "attack" => {
+                self.attack = value.max(0.0);
+                Ok(())
+            }
+            "decay" => {
+                self.decay = value.max(0.0);
+                Ok(())
+            }
+            "env_depth" | "depth" => {
+                self.env_depth = value;
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "frequency" | "freq" => Some(self.frequency),
+            "ratio" => Some(self.ratio),
+            "index" => Some(self.index),
+            "feedback" | "fb" => Some(self.feedback),
+            "attack" => Some(self.attack),
+            "decay" => Some(self.decay),
+            "env_depth" | "depth" => Some(self.env_depth),
+            _ => None,
+        }
+    }
+}
+
+/// Comparator with hysteresis - turns any signal into gates
+/// Goes high above threshold + hysteresis/2 and low below threshold - hysteresis/2
+pub struct GraphComparator {
+    threshold: f32,
+    hysteresis: f32,
+    state: bool,
+}
+
+impl GraphComparator {
+    pub fn new(threshold: f32, hysteresis: f32) -> Self {
+        Self {
+            threshold,
+            hysteresis: hysteresis.max(0.0),
+            state: false,
+        }
+    }
+}
+
+impl Default for GraphComparator {
+    fn default() -> Self {
+        Self::new(0.5, 0.0)
+    }
+}
+
+impl GraphModule for GraphComparator {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Signal to compare".to_string(),
+            },
+            PortDescriptor {
+                name: "threshold".to_string(),
+                default_value: 0.0,
+                description: "Threshold CV (added to threshold parameter)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "High (1) while input is above threshold".to_string(),
+            },
+            PortDescriptor {
+                name: "inv".to_string(),
+                default_value: 0.0,
+                description: "Inverted gate output".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let threshold_cv = inputs.get("threshold").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [gate_out, inv_out] = outputs.get_many_mut(["gate", "inv"]);
+        let gate_out = gate_out.unwrap();
+        let inv_out = inv_out.unwrap();
+
+        let half_band = self.hysteresis * 0.5;
+
+        for i in 0..sample_count {
+            let value = if i < input.len() { input[i] } else { 0.0 };
+            let threshold_mod = if i < threshold_cv.len() { threshold_cv[i] } else { 0.0 };
+            let threshold = self.threshold + threshold_mod;
+
+            if self.state && value < threshold - half_band {
+                self.state = false;
+            } else if !self.state && value > threshold + half_band {
+                self.state = true;
+            }
+
+            gate_out[i] = if self.state { 1.0 } else { 0.0 };
+            inv_out[i] = if self.state { 0.0 } else { 1.0 };
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "threshold" => {
+                self.threshold = value;
+                Ok(())
+            }
+            "hysteresis" | "hyst" => {
+                self.hysteresis = value.max(0.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "threshold" => Some(self.threshold),
+            "hysteresis" | "hyst" => Some(self.hysteresis),
+            _ => None,
+        }
+    }
+}
+
+/// Gate logic module - Boolean functions over 2-4 gate inputs
+/// Inputs are high above 0.5; XOR is true when an odd number of inputs are high
+pub struct GraphLogic {
+    input_count: usize,
+}
+
+impl GraphLogic {
+    pub fn new(input_count: usize) -> Self {
+        Self { input_count: input_count.clamp(2, 4) }
+    }
+}
+
+impl Default for GraphLogic {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl GraphModule for GraphLogic {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        (1..=self.input_count)
+            .map(|i| PortDescriptor {
+                name: format!("in{i}"),
+                default_value: 0.0,
+                description: format!("Gate input {i} (>0.5 = high)"),
+            })
+            .collect()
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "and".to_string(),
+                default_value: 0.0,
+                description: "High when all inputs are high".to_string(),
+            },
+            PortDescriptor {
+                name: "or".to_string(),
+                default_value: 0.0,
+                description: "High when any input is high".to_string(),
+            },
+            PortDescriptor {
+                name: "xor".to_string(),
+                default_value: 0.0,
+                description: "High when an odd number of inputs are high".to_string(),
+            },
+            PortDescriptor {
+                name: "nand".to_string(),
+                default_value: 0.0,
+                description: "Inverted AND output".to_string(),
+            },
+            PortDescriptor {
+                name: "not".to_string(),
+                default_value: 0.0,
+                description: "Inverted input 1".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let mut input_signals = Vec::new();
+        for i in 1..=self.input_count {
+            let signal = inputs.get(&format!("in{i}")).map(|b| b.as_slice()).unwrap_or(&[]);
+            input_signals.push(signal);
+        }
+
+        let [and_out, or_out, xor_out, nand_out, not_out] =
+            outputs.get_many_mut(["and", "or", "xor", "nand", "not"]);
+        let and_out = and_out.unwrap();
+        let or_out = or_out.unwrap();
+        let xor_out = xor_out.unwrap();
+        let nand_out = nand_out.unwrap();
+        let not_out = not_out.unwrap();
+
+        for i in 0..sample_count {
+            let high_count = input_signals
+                .iter()
+                .filter(|signal| i < signal.len() && signal[i] > 0.5)
+                .count();
+            let first_high = i < input_signals[0].len() && input_signals[0][i] > 0.5;
+
+            let all_high = high_count == self.input_count;
+            and_out[i] = if all_high { 1.0 } else { 0.0 };
+            or_out[i] = if high_count > 0 { 1.0 } else { 0.0 };
+            xor_out[i] = if high_count % 2 == 1 { 1.0 } else { 0.0 };
+            nand_out[i] = if all_high { 0.0 } else { 1.0 };
+            not_out[i] = if first_high { 0.0 } else { 1.0 };
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "inputs" => {
+                self.input_count = (value as usize).clamp(2, 4);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "inputs" => Some(self.input_count as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Edge detector - converts gate transitions into trigger pulses
+pub struct GraphEdge {
+    pulse_length: f32, // Trigger length in seconds
+    last_high: bool,
+    rise_remaining: usize,
+    fall_remaining: usize,
+    sample_rate: f32,
+}
+
+impl GraphEdge {
+    pub fn new(pulse_length: f32) -> Self {
+        Self {
+            pulse_length: pulse_length.max(0.0),
+            last_high: false,
+            rise_remaining: 0,
+            fall_remaining: 0,
+            sample_rate: 44100.0,
+        }
+    }
+
+    fn pulse_samples(&self) -> usize {
+        ((self.pulse_length * self.sample_rate) as usize).max(1)
+    }
+}
+
+impl Default for GraphEdge {
+    fn default() -> Self {
+        Self::new(0.001) // 1ms triggers
+    }
+}
+
+impl GraphModule for GraphEdge {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "in".to_string(),
+            default_value: 0.0,
+            description: "Gate input (>0.5 = high)".to_string(),
+        }]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "rise".to_string(),
+                default_value: 0.0,
+                description: "Trigger on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "fall".to_string(),
+                default_value: 0.0,
+                description: "Trigger on falling edge".to_string(),
+            },
+            PortDescriptor {
+                name: "both".to_string(),
+                default_value: 0.0,
+                description: "Trigger on either edge".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [rise_out, fall_out, both_out] = outputs.get_many_mut(["rise", "fall", "both"]);
+        let rise_out = rise_out.unwrap();
+        let fall_out = fall_out.unwrap();
+        let both_out = both_out.unwrap();
+
+        let pulse_samples = self.pulse_samples();
+
+        for i in 0..sample_count {
+            let high = i < input.len() && input[i] > 0.5;
+
+            if high && !self.last_high {
+                self.rise_remaining = pulse_samples;
+            } else if !high && self.last_high {
+                self.fall_remaining = pulse_samples;
+            }
+            self.last_high = high;
+
+            let rising = self.rise_remaining > 0;
+            let falling = self.fall_remaining > 0;
+            rise_out[i] = if rising { 1.0 } else { 0.0 };
+            fall_out[i] = if falling { 1.0 } else { 0.0 };
+            both_out[i] = if rising || falling { 1.0 } else { 0.0 };
+
+            self.rise_remaining = self.rise_remaining.saturating_sub(1);
+            self.fall_remaining = self.fall_remaining.saturating_sub(1);
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "length" => {
+                self.pulse_length = value.max(0.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "length" => Some(self.pulse_length),
+            _ => None,
+        }
+    }
+}
+
+/// Attenuverter - CV-controlled gain (attenuate and invert) plus offset
+/// Gain is the gain parameter times the cv input, clamped to the min/max range
+pub struct GraphAttenuverter {
+    gain: f32,
+    offset: f32,
+    min_gain: f32,
+    max_gain: f32,
+}
+
+impl GraphAttenuverter {
+    pub fn new(min_gain: f32, max_gain: f32) -> Self {
+        Self {
+            gain: 1.0,
+            offset: 0.0,
+            min_gain: min_gain.min(max_gain),
+            max_gain: max_gain.max(min_gain),
+        }
+    }
+}
+
+impl Default for GraphAttenuverter {
+    fn default() -> Self {
+        Self::new(-1.0, 1.0)
+    }
+}
+
+impl GraphModule for GraphAttenuverter {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Signal input".to_string(),
+            },
+            PortDescriptor {
+                name: "cv".to_string(),
+                default_value: 1.0,
+                description: "Gain CV (-1 to 1, 0 = no signal, negative inverts)".to_string(),
+            },
+            PortDescriptor {
+                name: "offset".to_string(),
+                default_value: 0.0,
+                description: "Offset CV (added to offset parameter)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Scaled and offset output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let cv = inputs.get("cv").map(|b| b.as_slice()).unwrap_or(&[]);
+        let offset_cv = inputs.get("offset").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let out = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            let value = if i < input.len() { input[i] } else { 0.0 };
+            let cv_value = if i < cv.len() { cv[i] } else { 1.0 };
+            let offset_mod = if i < offset_cv.len() { offset_cv[i] } else { 0.0 };
+
+            let gain = (self.gain * cv_value).clamp(self.min_gain, self.max_gain);
+            out[i] = value * gain + self.offset + offset_mod;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "gain" => {
+                self.gain = value;
+                Ok(())
+            }
+            "offset" => {
+                self.offset = value;
+                Ok(())
+            }
+            "min" => {
+                self.min_gain = value.min(self.max_gain);
+                Ok(())
+            }
+            "max" => {
+                self.max_gain = value.max(self.min_gain);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "gain" => Some(self.gain),
+            "offset" => Some(self.offset),
+            "min" => Some(self.min_gain),
+            "max" => Some(self.max_gain),
+            _ => None,
+        }
+    }
+}
+
+/// Rectifier - half-wave and full-wave rectified outputs
+pub struct GraphRectifier {}
+
+impl GraphRectifier {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for GraphRectifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphModule for GraphRectifier {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "in".to_string(),
+            default_value: 0.0,
+            description: "Signal input".to_string(),
+        }]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "half".to_string(),
+                default_value: 0.0,
+                description: "Half-wave rectified output (negative part removed)".to_string(),
+            },
+            PortDescriptor {
+                name: "full".to_string(),
+                default_value: 0.0,
+                description: "Full-wave rectified output (absolute value)".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [half_out, full_out] = outputs.get_many_mut(["half", "full"]);
+        let half_out = half_out.unwrap();
+        let full_out = full_out.unwrap();
+
+        for i in 0..sample_count {
+            let value = if i < input.len() { input[i] } else { 0.0 };
+            half_out[i] = value.max(0.0);
+            full_out[i] = value.abs();
+        }
+    }
+
+    fn set_param(&mut self, _name: &str, _value: f32) -> Result<()> {
+        Err(anyhow!("Rectifier module has no parameters"))
+    }
+
+    fn get_param(&self, _name: &str) -> Option<f32> {
+        None
+    }
+}
+
+/// Inverter - flips the polarity of a signal
+pub struct GraphInverter {}
+
+impl GraphInverter {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for GraphInverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphModule for GraphInverter {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "in".to_string(),
+            default_value: 0.0,
+            description: "Signal input".to_string(),
+        }]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Inverted output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let out = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            out[i] = if i < input.len() { -input[i] } else { 0.0 };
+        }
+    }
+
+    fn set_param(&mut self, _name: &str, _value: f32) -> Result<()> {
+        Err(anyhow!("Inverter module has no parameters"))
+    }
+
+    fn get_param(&self, _name: &str) -> Option<f32> {
+        None
+    }
+}
+
+/// Sum/difference module - adds and subtracts two signals
+pub struct GraphSumDiff {}
+
+impl GraphSumDiff {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for GraphSumDiff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphModule for GraphSumDiff {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "a".to_string(),
+                default_value: 0.0,
+                description: "First input".to_string(),
+            },
+            PortDescriptor {
+                name: "b".to_string(),
+                default_value: 0.0,
+                description: "Second input".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "sum".to_string(),
+                default_value: 0.0,
+                description: "a + b".to_string(),
+            },
+            PortDescriptor {
+                name: "diff".to_string(),
+                default_value: 0.0,
+                description: "a - b".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let a = inputs.get("a").map(|b| b.as_slice()).unwrap_or(&[]);
+        let b = inputs.get("b").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [sum_out, diff_out] = outputs.get_many_mut(["sum", "diff"]);
+        let sum_out = sum_out.unwrap();
+        let diff_out = diff_out.unwrap();
+
+        for i in 0..sample_count {
+            let a_value = if i < a.len() { a[i] } else { 0.0 };
+            let b_value = if i < b.len() { b[i] } else { 0.0 };
+            sum_out[i] = a_value + b_value;
+            diff_out[i] = a_value - b_value;
+        }
+    }
+
+    fn set_param(&mut self, _name: &str, _value: f32) -> Result<()> {
+        Err(anyhow!("Sum/difference module has no parameters"))
+    }
+
+    fn get_param(&self, _name: &str) -> Option<f32> {
+        None
+    }
+}
+
+/// Constant voltage source with a CV input added to its value
+pub struct GraphConstant {
+    value: f32,
+}
+
+impl GraphConstant {
+    pub fn new(value: f32) -> Self {
+        Self { value }
+    }
+}
+
+impl Default for GraphConstant {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl GraphModule for GraphConstant {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "cv".to_string(),
+            default_value: 0.0,
+            description: "CV added to the constant value".to_string(),
+        }]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Constant output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let cv = inputs.get("cv").map(|b| b.as_slice()).unwrap_or(&[]);
+        let out = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            let cv_value = if i < cv.len() { cv[i] } else { 0.0 };
+            out[i] = self.value + cv_value;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "value" => {
+                self.value = value;
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "value" => Some(self.value),
+            _ => None,
+        }
+    }
+}
+
+/// Clock outputs as (port, period in beats, swung)
+const CLOCK_DIVISIONS: [(&str, f64, bool); 7] = [
+    ("clock", 1.0, false),
+    ("bar", 4.0, false),
+    ("half", 2.0, false),
+    ("eighth", 0.5, true),
+    ("sixteenth", 0.25, true),
+    ("quarter_t", 2.0 / 3.0, false),
+    ("eighth_t", 1.0 / 3.0, false),
+];
+
+/// Longest gap between taps that still counts towards tap tempo, in seconds
+const MAX_TAP_INTERVAL: f64 = 3.0;
+
+/// Master clock with musical divisions, swing and tap tempo
+pub struct GraphClock {
+    bpm: f32,
+    swing: f32,
+    sample_rate: f32,
+    beat_position: f64,
+    last_reset: f32,
+    last_tap: f32,
+    samples_elapsed: u64,
+    last_tap_sample: Option<u64>,
+    tap_intervals: Vec<u64>,
+}
+
+impl GraphClock {
+    pub fn new(bpm: f32) -> Self {
+        Self {
+            bpm: bpm.clamp(1.0, 999.0),
+            swing: 0.0,
+            sample_rate: 44100.0,
+            beat_position: 0.0,
+            last_reset: 0.0,
+            last_tap: 0.0,
+            samples_elapsed: 0,
+            last_tap_sample: None,
+            tap_intervals: Vec::new(),
+        }
+    }
+
+    /// Register a tap at the given sample time, averaging the last few intervals
+    fn tap(&mut self, at_sample: u64) {
+        if let Some(previous) = self.last_tap_sample {
+            let interval = at_sample - previous;
+            if interval as f64 > MAX_TAP_INTERVAL * f64::from(self.sample_rate) {
+                self.tap_intervals.clear();
+            } else if interval > 0 {
+                self.tap_intervals.push(interval);
+                if self.tap_intervals.len() > 4 {
+                    self.tap_intervals.remove(0);
+                }
+                let average =
+                    self.tap_intervals.iter().sum::<u64>() as f64 / self.tap_intervals.len() as f64;
+                self.bpm = (60.0 * f64::from(self.sample_rate) / average).clamp(1.0, 999.0) as f32;
+            }
+        }
+        self.last_tap_sample = Some(at_sample);
+    }
+
+    /// Gate level for a division at the current beat position
+    fn division_gate(&self, period: f64, swung: bool, swing: f64) -> bool {
+        if !swung {
+            return self.beat_position % period < period * 0.5;
+        }
+        // Swing delays every second pulse by up to half a period
+        let position = self.beat_position % (period * 2.0);
+        let offbeat_start = period * (1.0 + swing * 0.5);
+        position < period * 0.5
+            || (position >= offbeat_start && position < offbeat_start + period * 0.5)
+    }
+}
+
+impl Default for GraphClock {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
+
+impl GraphModule for GraphClock {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "bpm".to_string(),
+                default_value: 0.0,
+                description: "Tempo CV in BPM (overrides the bpm parameter when > 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "run".to_string(),
+                default_value: 1.0,
+                description: "Clock runs while high".to_string(),
+            },
+            PortDescriptor {
+                name: "reset".to_string(),
+                default_value: 0.0,
+                description: "Restart from the top of the bar on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "swing".to_string(),
+                default_value: 0.0,
+                description: "Swing CV added to the swing parameter (0-1)".to_string(),
+            },
+            PortDescriptor {
+                name: "tap".to_string(),
+                default_value: 0.0,
+                description: "Tap tempo input".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "clock".to_string(),
+                default_value: 0.0,
+                description: "Quarter note clock".to_string(),
+            },
+            PortDescriptor {
+                name: "bar".to_string(),
+                default_value: 0.0,
+                description: "Whole note (one pulse per 4/4 bar)".to_string(),
+            },
+            PortDescriptor {
+                name: "half".to_string(),
+                default_value: 0.0,
+                description: "Half note clock".to_string(),
+            },
+            PortDescriptor {
+                name: "eighth".to_string(),
+                default_value: 0.0,
+                description: "Eighth note clock (swung)".to_string(),
+            },
+            PortDescriptor {
+                name: "sixteenth".to_string(),
+                default_value: 0.0,
+                description: "Sixteenth note clock (swung)".to_string(),
+            },
+            PortDescriptor {
+                name: "quarter_t".to_string(),
+                default_value: 0.0,
+                description: "Quarter note triplet clock".to_string(),
+            },
+            PortDescriptor {
+                name: "eighth_t".to_string(),
+                default_value: 0.0,
+                description: "Eighth note triplet clock".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let bpm_cv = inputs.get("bpm").map(|b| b.as_slice()).unwrap_or(&[]);
+        let run = inputs.get("run").map(|b| b.as_slice()).unwrap_or(&[]);
+        let reset = inputs.get("reset").map(|b| b.as_slice()).unwrap_or(&[]);
+        let swing_cv = inputs.get("swing").map(|b| b.as_slice()).unwrap_or(&[]);
+        let tap = inputs.get("tap").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let mut outs = outputs.get_many_mut(CLOCK_DIVISIONS.map(|(name, _, _)| name));
+
+        for i in 0..sample_count {
+            let bpm_val = if i < bpm_cv.len() { bpm_cv[i] } else { 0.0 };
+            let run_val = if i < run.len() { run[i] } else { 1.0 };
+            let reset_val = if i < reset.len() { reset[i] } else { 0.0 };
+            let swing_val = if i < swing_cv.len() { swing_cv[i] } else { 0.0 };
+            let tap_val = if i < tap.len() { tap[i] } else { 0.0 };
+
+            if tap_val > 0.5 && self.last_tap <= 0.5 {
+                self.tap(self.samples_elapsed);
+            }
+            self.last_tap = tap_val;
+
+            if reset_val > 0.5 && self.last_reset <= 0.5 {
+                self.beat_position = 0.0;
+            }
+            self.last_reset = reset_val;
+
+            let running = run_val > 0.5;
+            let swing = f64::from((self.swing + swing_val).clamp(0.0, 1.0));
+            for (out, (_, period, swung)) in outs.iter_mut().zip(CLOCK_DIVISIONS) {
+                let high = running && self.division_gate(period, swung, swing);
+                if let Some(out) = out {
+                    out[i] = if high { 1.0 } else { 0.0 };
+                }
+            }
+
+            if running {
+                let bpm = if bpm_val > 0.0 { bpm_val } else { self.bpm };
+                self.beat_position += f64::from(bpm) / 60.0 / f64::from(self.sample_rate);
+                // Wrap at a full bar so every division stays phase aligned
+                if self.beat_position >= 4.0 {
+                    self.beat_position -= 4.0;
+                }
+            }
+            self.samples_elapsed += 1;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "bpm" | "tempo" => {
+                self.bpm = value.clamp(1.0, 999.0);
+                Ok(())
+            }
+            "swing" => {
+                self.swing = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "tap" => {
+                if value > 0.5 {
+                    self.tap(self.samples_elapsed);
+                }
+                Ok(())
+            }
+            "reset" => {
+                if value > 0.5 {
+                    self.beat_position = 0.0;
+                }
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "bpm" | "tempo" => Some(self.bpm),
+            "swing" => Some(self.swing),
+            "beat" => Some(self.beat_position as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Longest pattern the Euclidean generator will produce
+const MAX_EUCLID_STEPS: usize = 64;
+
+/// Euclidean rhythm generator spreading fills evenly across steps
+pub struct GraphEuclid {
+    steps: f32,
+    fills: f32,
+    rotate: f32,
+    position: Option<usize>,
+    hit: bool,
+    accent: bool,
+    last_clock: f32,
+    last_reset: f32,
+}
+
+impl GraphEuclid {
+    pub fn new(steps: f32, fills: f32, rotate: f32) -> Self {
+        Self {
+            steps,
+            fills,
+            rotate,
+            position: None,
+            hit: false,
+            accent: false,
+            last_clock: 0.0,
+            last_reset: 0.0,
+        }
+    }
+
+    /// Whether a step of the rotated pattern is a hit
+    pub fn is_hit(step: usize, steps: usize, fills: usize, rotate: usize) -> bool {
+        let index = (step + steps - rotate % steps) % steps;
+        (index * fills) % steps < fills
+    }
+}
+
+impl Default for GraphEuclid {
+    fn default() -> Self {
+        Self::new(16.0, 4.0, 0.0)
+    }
+}
+
+impl GraphModule for GraphEuclid {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "clock".to_string(),
+                default_value: 0.0,
+                description: "Advance one step on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "reset".to_string(),
+                default_value: 0.0,
+                description: "Next clock plays the first step".to_string(),
+            },
+            PortDescriptor {
+                name: "steps".to_string(),
+                default_value: 0.0,
+                description: "CV added to the pattern length".to_string(),
+            },
+            PortDescriptor {
+                name: "fills".to_string(),
+                default_value: 0.0,
+                description: "CV added to the number of hits".to_string(),
+            },
+            PortDescriptor {
+                name: "rotate".to_string(),
+                default_value: 0.0,
+                description: "CV added to the pattern rotation".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "Clock passed through on hits".to_string(),
+            },
+            PortDescriptor {
+                name: "accent".to_string(),
+                default_value: 0.0,
+                description: "Clock passed through on the first hit of each cycle".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let clock = inputs.get("clock").map(|b| b.as_slice()).unwrap_or(&[]);
+        let reset = inputs.get("reset").map(|b| b.as_slice()).unwrap_or(&[]);
+        let steps_cv = inputs.get("steps").map(|b| b.as_slice()).unwrap_or(&[]);
+        let fills_cv = inputs.get("fills").map(|b| b.as_slice()).unwrap_or(&[]);
+        let rotate_cv = inputs.get("rotate").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [gate_out, accent_out] = outputs.get_many_mut(["gate", "accent"]);
+        let gate_out = gate_out.unwrap();
+        let accent_out = accent_out.unwrap();
+
+        for i in 0..sample_count {
+            let clock_val = if i < clock.len() { clock[i] } else { 0.0 };
+            let reset_val = if i < reset.len() { reset[i] } else { 0.0 };
+
+            if reset_val > 0.5 && self.last_reset <= 0.5 {
+                self.position = None;
+            }
+            self.last_reset = reset_val;
+
+            // Pattern is only re-evaluated on clock edges so CV changes land on the beat
+            if clock_val > 0.5 && self.last_clock <= 0.5 {
+                let steps_val = if i < steps_cv.len() { steps_cv[i] } else { 0.0 };
+                let fills_val = if i < fills_cv.len() { fills_cv[i] } else { 0.0 };
+                let rotate_val = if i < rotate_cv.len() { rotate_cv[i] } else { 0.0 };
+
+                let steps = ((self.steps + steps_val).round() as usize).clamp(1, MAX_EUCLID_STEPS);
+                let fills = ((self.fills + fills_val).round().max(0.0) as usize).min(steps);
+                let rotate = (self.rotate + rotate_val).round().rem_euclid(steps as f32) as usize;
+
+                let step = self.position.map_or(0, |p| (p + 1) % steps);
+                self.position = Some(step);
+                self.hit = Self::is_hit(step, steps, fills, rotate);
+                // First hit at or after the top of the pattern
+                self.accent = self.hit
+                    && (0..step).all(|earlier| !Self::is_hit(earlier, steps, fills, rotate));
+            }
+            self.last_clock = clock_val;
+
+            let clock_high = clock_val > 0.5;
+            gate_out[i] = if clock_high && self.hit { 1.0 } else { 0.0 };
+            accent_out[i] = if clock_high && self.accent { 1.0 } else { 0.0 };
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "steps" => {
+                self.steps = value;
+                Ok(())
+            }
+            "fills" | "hits" => {
+                self.fills = value;
+                Ok(())
+            }
+            "rotate" => {
+                self.rotate = value;
+                Ok(())
+            }
+            "reset" => {
+                if value > 0.5 {
+                    self.position = None;
+                }
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "steps" => Some(self.steps),
+            "fills" | "hits" => Some(self.fills),
+            "rotate" => Some(self.rotate),
+            "position" => self.position.map(|p| p as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Longest shift register the Turing machine supports
+const MAX_TURING_LENGTH: usize = 16;
+/// Number of per-bit pulse outputs
+const TURING_PULSE_OUTPUTS: usize = 8;
+
+/// Looping random shift register in the style of the Turing Machine
+pub struct GraphTuring {
+    length: usize,
+    probability: f32,
+    scale: f32,
+    seed: u32,
+    register: u16,
+    rng: Lcg,
+    last_clock: f32,
+    pulse_names: Vec<String>,
+}
+
+impl GraphTuring {
+    pub fn new(length: usize, seed: u32) -> Self {
+        let mut turing = Self {
+            length: length.clamp(2, MAX_TURING_LENGTH),
+            probability: 0.0,
+            scale: 1.0,
+            seed,
+            register: 0,
+            rng: Lcg::new(seed),
+            last_clock: 0.0,
+            pulse_names: (1..=TURING_PULSE_OUTPUTS).map(|i| format!("pulse{i}")).collect(),
+        };
+        turing.reseed(seed);
+        turing
+    }
+
+    /// Restart the random sequence and fill the register from it
+    fn reseed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.rng = Lcg::new(seed);
+        self.register = (self.rng.next_u32() >> 16) as u16;
+    }
+
+    /// Shift the register one step, flipping the recycled bit with the given probability
+    fn step(&mut self, probability: f32) {
+        let mut bit = (self.register >> (self.length - 1)) & 1;
+        if self.rng.next_unipolar() < probability {
+            bit ^= 1;
+        }
+        let mask = ((1u32 << self.length) - 1) as u16;
+        self.register = ((self.register << 1) | bit) & mask;
+    }
+
+    /// Current register contents, masked to the loop length
+    pub fn register(&self) -> u16 {
+        self.register & ((1u32 << self.length) - 1) as u16
+    }
+}
+
+impl Default for GraphTuring {
+    fn default() -> Self {
+        Self::new(8, 12345)
+    }
+}
+
+impl GraphModule for GraphTuring {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "clock".to_string(),
+                default_value: 0.0,
+                description: "Shift the register on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "prob".to_string(),
+                default_value: 0.0,
+                description: "CV added to the bit flip probability (0 = locked loop)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        let mut outputs = vec![
+            PortDescriptor {
+                name: "cv".to_string(),
+                default_value: 0.0,
+                description: "Lowest 8 bits of the register as 0-1, times scale".to_string(),
+            },
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "Clock passed through while bit 0 is set".to_string(),
+            },
+        ];
+        for (i, name) in self.pulse_names.iter().enumerate() {
+            outputs.push(PortDescriptor {
+                name: name.clone(),
+                default_value: 0.0,
+                description: format!("Clock passed through while bit {i} is set"),
+            });
+        }
+        outputs
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let clock = inputs.get("clock").map(|b| b.as_slice()).unwrap_or(&[]);
+        let prob = inputs.get("prob").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        // Register only changes on clock edges, so render cv/gate first
+        // and the per-bit pulses in a second pass over the clock
+        let mut registers = Vec::with_capacity(sample_count);
+        {
+            let [cv_out, gate_out] = outputs.get_many_mut(["cv", "gate"]);
+            let cv_out = cv_out.unwrap();
+            let gate_out = gate_out.unwrap();
+
+            for i in 0..sample_count {
+                let clock_val = if i < clock.len() { clock[i] } else { 0.0 };
+                let prob_val = if i < prob.len() { prob[i] } else { 0.0 };
+
+                if clock_val > 0.5 && self.last_clock <= 0.5 {
+                    self.step((self.probability + prob_val).clamp(0.0, 1.0));
+                }
+                self.last_clock = clock_val;
+
+                let register = self.register();
+                registers.push(register);
+                cv_out[i] = f32::from(register & 0xFF) / 255.0 * self.scale;
+                gate_out[i] = if clock_val > 0.5 && register & 1 == 1 { 1.0 } else { 0.0 };
+            }
+        }
+
+        for (bit, name) in self.pulse_names.iter().enumerate() {
+            if let Some(out) = outputs.get_mut(name) {
+                for i in 0..sample_count {
+                    let clock_val = if i < clock.len() { clock[i] } else { 0.0 };
+                    let set = (registers[i] >> bit) & 1 == 1;
+                    out[i] = if clock_val > 0.5 && set { 1.0 } else { 0.0 };
+                }
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "length" => {
+                self.length = (value as usize).clamp(2, MAX_TURING_LENGTH);
+                Ok(())
+            }
+            "prob" | "probability" => {
+                self.probability = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "scale" => {
+                self.scale = value;
+                Ok(())
+            }
+            "seed" => {
+                self.reseed(value as u32);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "length" => Some(self.length as f32),
+            "prob" | "probability" => Some(self.probability),
+            "scale" => Some(self.scale),
+            "seed" => Some(self.seed as f32),
+            "register" => Some(f32::from(self.register())),
+            _ => None,
+        }
+    }
+}
+
+/// How a Bernoulli gate presents the side each trigger picks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BernoulliMode {
+    /// The chosen side passes the trigger through; the other stays low
+    Trigger,
+    /// Chosen side stays high until the next trigger
+    Latch,
+    /// Probability is the chance of switching sides rather than of picking B
+    Toggle,
+}
+
+impl BernoulliMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "trigger" | "trig" => Some(Self::Trigger),
+            "latch" => Some(Self::Latch),
+            "toggle" => Some(Self::Toggle),
+            _ => None,
+        }
+    }
+}
+
+/// Bernoulli gate - routes each trigger to A or B at random
+pub struct GraphBernoulli {
+    probability: f32,
+    mode: BernoulliMode,
+    seed: u32,
+    rng: Lcg,
+    side_b: bool,
+    triggered: bool,
+    last_trig: f32,
+}
+
+impl GraphBernoulli {
+    pub fn new(probability: f32, mode: BernoulliMode) -> Self {
+        Self {
+            probability: probability.clamp(0.0, 1.0),
+            mode,
+            seed: 12345,
+            rng: Lcg::new(12345),
+            side_b: false,
+            triggered: false,
+            last_trig: 0.0,
+        }
+    }
+
+    fn set_mode_from_param(&mut self, value: f32) {
+        self.mode = match value as i32 {
+            1 => BernoulliMode::Latch,
+            2 => BernoulliMode::Toggle,
+            _ => BernoulliMode::Trigger,
+        };
+    }
+}
+
+impl Default for GraphBernoulli {
+    fn default() -> Self {
+        Self::new(0.5, BernoulliMode::Trigger)
+    }
+}
+
+impl GraphModule for GraphBernoulli {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "trig".to_string(),
+                default_value: 0.0,
+                description: "Trigger or gate to route".to_string(),
+            },
+            PortDescriptor {
+                name: "prob".to_string(),
+                default_value: 0.0,
+                description: "CV added to the probability of choosing B".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "a".to_string(),
+                default_value: 0.0,
+                description: "Triggers routed to A".to_string(),
+            },
+            PortDescriptor {
+                name: "b".to_string(),
+                default_value: 0.0,
+                description: "Triggers routed to B".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let trig = inputs.get("trig").map(|b| b.as_slice()).unwrap_or(&[]);
+        let prob = inputs.get("prob").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [a_out, b_out] = outputs.get_many_mut(["a", "b"]);
+        let a_out = a_out.unwrap();
+        let b_out = b_out.unwrap();
+
+        for i in 0..sample_count {
+            let trig_val = if i < trig.len() { trig[i] } else { 0.0 };
+            let prob_val = if i < prob.len() { prob[i] } else { 0.0 };
+
+            if trig_val > 0.5 && self.last_trig <= 0.5 {
+                let probability = (self.probability + prob_val).clamp(0.0, 1.0);
+                let coin = self.rng.next_unipolar() < probability;
+                self.side_b = match self.mode {
+                    BernoulliMode::Trigger | BernoulliMode::Latch => coin,
+                    BernoulliMode::Toggle => self.side_b != coin,
+                };
+                self.triggered = true;
+            }
+            self.last_trig = trig_val;
+
+            let active = match self.mode {
+                BernoulliMode::Latch => self.triggered,
+                BernoulliMode::Trigger | BernoulliMode::Toggle => trig_val > 0.5,
+            };
+            a_out[i] = if active && !self.side_b { 1.0 } else { 0.0 };
+            b_out[i] = if active && self.side_b { 1.0 } else { 0.0 };
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "prob" | "probability" => {
+                self.probability = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "mode" => {
+                self.set_mode_from_param(value);
+                Ok(())
+            }
+            "seed" => {
+                self.seed = value as u32;
+                self.rng = Lcg::new(self.seed);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "prob" | "probability" => Some(self.probability),
+            "mode" => Some(match self.mode {
+                BernoulliMode::Trigger => 0.0,
+                BernoulliMode::Latch => 1.0,
+                BernoulliMode::Toggle => 2.0,
+            }),
+            "seed" => Some(self.seed as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Largest matrix dimension in either direction
+const MAX_MATRIX_SIZE: usize = 16;
+
+/// Matrix mixer - any input to any output with its own level
+///
+/// Levels default to zero. Each `inX_outY` level is set with `SetParam` and
+/// a connection to the input port of the same name is added on top, so a
+/// patch can fix a crosspoint and modulate around it. When outputs are
+/// patched back into inputs the loop runs one buffer late.
+pub struct GraphMatrix {
+    input_count: usize,
+    output_count: usize,
+    levels: Vec<f32>, // input-major: levels[input * output_count + output]
+    input_names: Vec<String>,
+    output_names: Vec<String>,
+    level_names: Vec<String>,
+}
+
+impl GraphMatrix {
+    pub fn new(input_count: usize, output_count: usize) -> Self {
+        let input_count = input_count.clamp(1, MAX_MATRIX_SIZE);
+        let output_count = output_count.clamp(1, MAX_MATRIX_SIZE);
+        let mut level_names = Vec::with_capacity(input_count * output_count);
+        for input in 1..=input_count {
+            for output in 1..=output_count {
+                level_names.push(format!("in{input}_out{output}"));
+            }
+        }
+        Self {
+            input_count,
+            output_count,
+            levels: vec![0.0; input_count * output_count],
+            input_names: (1..=input_count).map(|i| format!("in{i}")).collect(),
+            output_names: (1..=output_count).map(|i| format!("out{i}")).collect(),
+            level_names,
+        }
+    }
+
+    fn level_index(&self, name: &str) -> Option<usize> {
+        self.level_names.iter().position(|level| level == name)
+    }
+}
+
+impl Default for GraphMatrix {
+    fn default() -> Self {
+        Self::new(4, 4)
+    }
+}
+
+impl GraphModule for GraphMatrix {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        let mut inputs: Vec<PortDescriptor> = self
+            .input_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| PortDescriptor {
+                name: name.clone(),
+                default_value: 0.0,
+                description: format!("Signal input {}", i + 1),
+            })
+            .collect();
+
+        for (index, name) in self.level_names.iter().enumerate() {
+            inputs.push(PortDescriptor {
+                name: name.clone(),
+                default_value: 0.0,
+                description: format!(
+                    "Level CV from input {} to output {}",
+                    index / self.output_count + 1,
+                    index % self.output_count + 1
+                ),
+            });
+        }
+
+        inputs
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        self.output_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| PortDescriptor {
+                name: name.clone(),
+                default_value: 0.0,
+                description: format!("Mixed output {}", i + 1),
+            })
+            .collect()
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input_buffers: Vec<&[f32]> = self
+            .input_names
+            .iter()
+            .map(|name| inputs.get(name).map(|b| b.as_slice()).unwrap_or(&[]))
+            .collect();
+        let level_buffers: Vec<&[f32]> = self
+            .level_names
+            .iter()
+            .map(|name| inputs.get(name).map(|b| b.as_slice()).unwrap_or(&[]))
+            .collect();
+
+        for (output, name) in self.output_names.iter().enumerate() {
+            let Some(out) = outputs.get_mut(name) else {
+                continue;
+            };
+
+            for i in 0..sample_count {
+                let mut mixed = 0.0;
+                for (input, input_buf) in input_buffers.iter().enumerate() {
+                    let index = input * self.output_count + output;
+                    let level_buf = level_buffers[index];
+                    let level_cv = if i < level_buf.len() { level_buf[i] } else { 0.0 };
+                    let sample = if i < input_buf.len() { input_buf[i] } else { 0.0 };
+                    mixed += sample * (self.levels[index] + level_cv);
+                }
+                out[i] = mixed;
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match self.level_index(name) {
+            Some(index) => {
+                self.levels[index] = value;
+                Ok(())
+            }
+            None => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "inputs" => Some(self.input_count as f32),
+            "outputs" => Some(self.output_count as f32),
+            _ => self.level_index(name).map(|index| self.levels[index]),
+        }
+    }
+}
+
+/// Voltage-controlled crossfader between two inputs
+pub struct GraphCrossfade {
+    position: f32,
+    law: PanLaw,
+}
+
+impl GraphCrossfade {
+    pub fn new(position: f32, law: PanLaw) -> Self {
+        Self { position: position.clamp(0.0, 1.0), law }
+    }
+}
+
+impl Default for GraphCrossfade {
+    fn default() -> Self {
+        Self::new(0.5, PanLaw::EqualPower)
+    }
+}
+
+impl GraphModule for GraphCrossfade {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "a".to_string(),
+                default_value: 0.0,
+                description: "Input heard at position 0".to_string(),
+            },
+            PortDescriptor {
+                name: "b".to_string(),
+                default_value: 0.0,
+                description: "Input heard at position 1".to_string(),
+            },
+            PortDescriptor {
+                name: "cv".to_string(),
+                default_value: 0.0,
+                description: "CV added to the crossfade position (0-1)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Crossfaded output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let a = inputs.get("a").map(|b| b.as_slice()).unwrap_or(&[]);
+        let b = inputs.get("b").map(|b| b.as_slice()).unwrap_or(&[]);
+        let cv = inputs.get("cv").map(|b| b.as_slice()).unwrap_or(&[]);
+        let out = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            let a_val = if i < a.len() { a[i] } else { 0.0 };
+            let b_val = if i < b.len() { b[i] } else { 0.0 };
+            let cv_val = if i < cv.len() { cv[i] } else { 0.0 };
+
+            let position = (self.position + cv_val).clamp(0.0, 1.0);
+            let (a_gain, b_gain) = self.law.gains(position * 2.0 - 1.0);
+            out[i] = a_val * a_gain + b_val * b_gain;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "position" | "mix" => {
+                self.position = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "law" | "curve" => {
+                self.law = PanLaw::from_param(value);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "position" | "mix" => Some(self.position),
+            "law" | "curve" => Some(self.law.as_param()),
+            _ => None,
+        }
+    }
+}
+
+/// Mono to stereo panner with a selectable pan law
+pub struct GraphPan {
+    pan: f32,
+    law: PanLaw,
+}
+
+impl GraphPan {
+    pub fn new(pan: f32, law: PanLaw) -> Self {
+        Self { pan: pan.clamp(-1.0, 1.0), law }
+    }
+}
+
+impl Default for GraphPan {
+    fn default() -> Self {
+        Self::new(0.0, PanLaw::EqualPower)
+    }
+}
+
+impl GraphModule for GraphPan {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Mono input".to_string(),
+            },
+            PortDescriptor {
+                name: "pan".to_string(),
+                default_value: 0.0,
+                description: "CV added to the pan position (-1=left, +1=right)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "left".to_string(),
+                default_value: 0.0,
+                description: "Left output".to_string(),
+            },
+            PortDescriptor {
+                name: "right".to_string(),
+                default_value: 0.0,
+                description: "Right output".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let pan_cv = inputs.get("pan").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [left_out, right_out] = outputs.get_many_mut(["left", "right"]);
+        let left_out = left_out.unwrap();
+        let right_out = right_out.unwrap();
+
+        for i in 0..sample_count {
+            let sample = if i < input.len() { input[i] } else { 0.0 };
+            let pan_val = if i < pan_cv.len() { pan_cv[i] } else { 0.0 };
+
+            let (left_gain, right_gain) = self.law.gains(self.pan + pan_val);
+            left_out[i] = sample * left_gain;
+            right_out[i] = sample * right_gain;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "pan" => {
+                self.pan = value.clamp(-1.0, 1.0);
+                Ok(())
+            }
+            "law" => {
+                self.law = PanLaw::from_param(value);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "pan" => Some(self.pan),
+            "law" => Some(self.law.as_param()),
+            _ => None,
+        }
+    }
+}
+
+/// Peak follower core shared by the follower and ducker
+struct PeakFollower {
+    level: f32,
+}
+
+impl PeakFollower {
+    fn new() -> Self {
+        Self { level: 0.0 }
+    }
+
+    /// Track the rectified input with separate attack and release times in seconds
+    fn process(&mut self, input: f32, attack: f32, release: f32, sample_rate: f32) -> f32 {
+        let rectified = input.abs();
+        let time = if rectified > self.level { attack } else { release };
+        let coeff = (-1.0 / (time.max(0.0001) * sample_rate)).exp();
+        self.level = rectified + (self.level - rectified) * coeff;
+        self.level
+    }
+}
+
+/// Envelope follower - derives a control envelope and gate from audio
+pub struct GraphFollower {
+    attack: f32,
+    release: f32,
+    threshold: f32,
+    sample_rate: f32,
+    follower: PeakFollower,
+    gate_open: bool,
+}
+
+impl GraphFollower {
+    pub fn new(attack: f32, release: f32) -> Self {
+        Self {
+            attack,
+            release,
+            threshold: 0.1,
+            sample_rate: 44100.0,
+            follower: PeakFollower::new(),
+            gate_open: false,
+        }
+    }
+}
+
+impl Default for GraphFollower {
+    fn default() -> Self {
+        Self::new(0.01, 0.1)
+    }
+}
+
+impl GraphModule for GraphFollower {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Audio input to follow".to_string(),
+            },
+            PortDescriptor {
+                name: "attack".to_string(),
+                default_value: 0.0,
+                description: "Attack time CV in seconds (overrides parameter when > 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "release".to_string(),
+                default_value: 0.0,
+                description: "Release time CV in seconds (overrides parameter when > 0)"
+                    .to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "env".to_string(),
+                default_value: 0.0,
+                description: "Envelope of the input".to_string(),
+            },
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "High while the envelope is above threshold".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let attack_cv = inputs.get("attack").map(|b| b.as_slice()).unwrap_or(&[]);
+        let release_cv = inputs.get("release").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [env_out, gate_out] = outputs.get_many_mut(["env", "gate"]);
+        let env_out = env_out.unwrap();
+        let gate_out = gate_out.unwrap();
+
+        for i in 0..sample_count {
+            let sample = if i < input.len() { input[i] } else { 0.0 };
+            let attack_val = if i < attack_cv.len() { attack_cv[i] } else { 0.0 };
+            let release_val = if i < release_cv.len() { release_cv[i] } else { 0.0 };
+
+            let attack = if attack_val > 0.0001 { attack_val } else { self.attack };
+            let release = if release_val > 0.0001 { release_val } else { self.release };
+            let env = self.follower.process(sample, attack, release, self.sample_rate);
+
+            // Closes 10% below threshold so a wobbling envelope doesn't chatter
+            if env > self.threshold {
+                self.gate_open = true;
+            } else if env < self.threshold * 0.9 {
+                self.gate_open = false;
+            }
+
+            env_out[i] = env;
+            gate_out[i] = if self.gate_open { 1.0 } else { 0.0 };
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "attack" => {
+                self.attack = value.max(0.0001);
+                Ok(())
+            }
+            "release" => {
+                self.release = value.max(0.0001);
+                Ok(())
+            }
+            "threshold" => {
+                self.threshold = value.max(0.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "attack" => Some(self.attack),
+            "release" => Some(self.release),
+            "threshold" => Some(self.threshold),
+            "level" => Some(self.follower.level),
+            _ => None,
+        }
+    }
+}
+
+/// Sidechain ducker - turns the input down while the sidechain is loud
+pub struct GraphDuck {
+    depth: f32,
+    range: f32,
+    attack: f32,
+    release: f32,
+    sample_rate: f32,
+    follower: PeakFollower,
+}
+
+impl GraphDuck {
+    pub fn new(depth: f32, range: f32) -> Self {
+        Self {
+            depth: depth.clamp(0.0, 1.0),
+            range: range.max(0.0001),
+            attack: 0.005,
+            release: 0.2,
+            sample_rate: 44100.0,
+            follower: PeakFollower::new(),
+        }
+    }
+}
+
+impl Default for GraphDuck {
+    fn default() -> Self {
+        Self::new(0.8, 0.5)
+    }
+}
+
+impl GraphModule for GraphDuck {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Audio to duck".to_string(),
+            },
+            PortDescriptor {
+                name: "sidechain".to_string(),
+                default_value: 0.0,
+                description: "Signal that triggers ducking".to_string(),
+            },
+            PortDescriptor {
+                name: "depth".to_string(),
+                default_value: 0.0,
+                description: "CV added to the maximum gain reduction (0-1)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "out".to_string(),
+                default_value: 0.0,
+                description: "Ducked audio".to_string(),
+            },
+            PortDescriptor {
+                name: "gain".to_string(),
+                default_value: 1.0,
+                description: "Applied gain (1 = untouched)".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let sidechain = inputs.get("sidechain").map(|b| b.as_slice()).unwrap_or(&[]);
+        let depth_cv = inputs.get("depth").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [out, gain_out] = outputs.get_many_mut(["out", "gain"]);
+        let out = out.unwrap();
+        let gain_out = gain_out.unwrap();
+
+        for i in 0..sample_count {
+            let sample = if i < input.len() { input[i] } else { 0.0 };
+            let side = if i < sidechain.len() { sidechain[i] } else { 0.0 };
+            let depth_val = if i < depth_cv.len() { depth_cv[i] } else { 0.0 };
+
+            let env = self.follower.process(side, self.attack, self.release, self.sample_rate);
+            // Full reduction once the sidechain envelope reaches `range`
+            let amount = (env / self.range).min(1.0);
+            let gain = 1.0 - (self.depth + depth_val).clamp(0.0, 1.0) * amount;
+
+            out[i] = sample * gain;
+            gain_out[i] = gain;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "depth" => {
+                self.depth = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "range" => {
+                self.range = value.max(0.0001);
+                Ok(())
+            }
+            "attack" => {
+                self.attack = value.max(0.0001);
+                Ok(())
+            }
+            "release" => {
+                self.release = value.max(0.0001);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "depth" => Some(self.depth),
+            "range" => Some(self.range),
+            "attack" => Some(self.attack),
+            "release" => Some(self.release),
+            _ => None,
+        }
+    }
+}
+
+/// Ring modulator - four-quadrant multiply of carrier and modulator
+pub struct GraphRingMod {
+    mix: f32,
+}
+
+impl GraphRingMod {
+    pub fn new(mix: f32) -> Self {
+        Self { mix: mix.clamp(0.0, 1.0) }
+    }
+}
+
+impl Default for GraphRingMod {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl GraphModule for GraphRingMod {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "carrier".to_string(),
+                default_value: 0.0,
+                description: "Carrier input (heard dry at mix 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "modulator".to_string(),
+                default_value: 0.0,
+                description: "Modulator input".to_string(),
+            },
+            PortDescriptor {
+                name: "mix".to_string(),
+                default_value: 0.0,
+                description: "CV added to the dry/ring mix (0-1)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Ring modulated output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let carrier = inputs.get("carrier").map(|b| b.as_slice()).unwrap_or(&[]);
+        let modulator = inputs.get("modulator").map(|b| b.as_slice()).unwrap_or(&[]);
+        let mix_cv = inputs.get("mix").map(|b| b.as_slice()).unwrap_or(&[]);
+        let out = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            let carrier_val = if i < carrier.len() { carrier[i] } else { 0.0 };
+            let modulator_val = if i < modulator.len() { modulator[i] } else { 0.0 };
+            let mix_val = if i < mix_cv.len() { mix_cv[i] } else { 0.0 };
+
+            let mix = (self.mix + mix_val).clamp(0.0, 1.0);
+            let ring = carrier_val * modulator_val;
+            out[i] = carrier_val + (ring - carrier_val) * mix;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "mix" => {
+                self.mix = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+}
+
+/// Allpass coefficients for a 90 degree phase-difference network (Olli Niemitalo)
+const HILBERT_COEFFS: [[f32; 4]; 2] = [
+    [0.692_387_8, 0.936_065_4, 0.988_229_5, 0.998_748_8],
+    [0.402_192_1, 0.856_171_1, 0.972_290_9, 0.995_288_5],
+];
+
+/// Two allpass chains whose outputs stay 90 degrees apart across the audio band
+struct HilbertPair {
+    // Per path, per stage: [x1, x2, y1, y2]
+    state: [[[f32; 4]; 4]; 2],
+    delayed_in_phase: f32,
+}
+
+impl HilbertPair {
+    fn new() -> Self {
+        Self {
+            state: [[[0.0; 4]; 4]; 2],
+            delayed_in_phase: 0.0,
+        }
+    }
+
+    /// Returns the in-phase and quadrature components of the input
+    fn process(&mut self, input: f32) -> (f32, f32) {
+        let mut outputs = [0.0; 2];
+        for (path, output) in outputs.iter_mut().enumerate() {
+            let mut signal = input;
+            for (stage, coeff) in HILBERT_COEFFS[path].iter().enumerate() {
+                let [x1, x2, y1, y2] = self.state[path][stage];
+                let a = coeff * coeff;
+                let y = a * (signal + y2) - x2;
+                self.state[path][stage] = [signal, x1, y, y1];
+                signal = y;
+            }
+            *output = signal;
+        }
+
+        // The first path is delayed one sample to line up with the second
+        let in_phase = self.delayed_in_phase;
+        self.delayed_in_phase = outputs[0];
+        (in_phase, outputs[1])
+    }
+}
+
+/// Frequency shifter - moves every partial by a fixed number of Hz
+pub struct GraphFreqShift {
+    shift: f32,
+    sample_rate: f32,
+    phase: f32,
+    hilbert: HilbertPair,
+}
+
+impl GraphFreqShift {
+    pub fn new(shift: f32) -> Self {
+        Self {
+            shift,
+            sample_rate: 44100.0,
+            phase: 0.0,
+            hilbert: HilbertPair::new(),
+        }
+    }
+}
+
+impl Default for GraphFreqShift {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl GraphModule for GraphFreqShift {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Audio input".to_string(),
+            },
+            PortDescriptor {
+                name: "shift".to_string(),
+                default_value: 0.0,
+                description: "Shift amount CV in Hz, added to the shift parameter".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "up".to_string(),
+                default_value: 0.0,
+                description: "Input shifted up by the shift amount".to_string(),
+            },
+            PortDescriptor {
+                name: "down".to_string(),
+                default_value: 0.0,
+                description: "Input shifted down by the shift amount".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let shift_cv = inputs.get("shift").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [up_out, down_out] = outputs.get_many_mut(["up", "down"]);
+        let up_out = up_out.unwrap();
+        let down_out = down_out.unwrap();
+
+        for i in 0..sample_count {
+            let sample = if i < input.len() { input[i] } else { 0.0 };
+            let shift_val = if i < shift_cv.len() { shift_cv[i] } else { 0.0 };
+
+            let (in_phase, quadrature) = self.hilbert.process(sample);
+            let (sin, cos) = (self.phase * TAU).sin_cos();
+
+            up_out[i] = in_phase * cos + quadrature * sin;
+            down_out[i] = in_phase * cos - quadrature * sin;
+
+            // Negative shifts simply run the oscillator backwards
+            self.phase = (self.phase + (self.shift + shift_val) / self.sample_rate).rem_euclid(1.0);
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "shift" | "freq" => {
+                self.shift = value;
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "shift" | "freq" => Some(self.shift),
+            _ => None,
+        }
+    }
+}
+
+/// Delay line length for the modulation effects (about 93ms at 44.1kHz)
+const MOD_DELAY_SIZE: usize = 4096;
+/// Allpass stages in the phaser
+const PHASER_STAGES: usize = 6;
+
+/// Which effect a modulation module runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModulationKind {
+    Chorus,
+    Flanger,
+    Phaser,
+}
+
+impl ModulationKind {
+    /// Default (rate, depth, feedback, mix) for each effect
+    fn defaults(self) -> (f32, f32, f32, f32) {
+        match self {
+            Self::Chorus => (0.8, 0.5, 0.0, 0.5),
+            Self::Flanger => (0.2, 0.7, 0.5, 0.5),
+            Self::Phaser => (0.4, 0.8, 0.3, 0.5),
+        }
+    }
+}
+
+/// Fractional delay line read with linear interpolation
+struct ModulatedDelay {
+    buffer: Vec<f32>,
+    write_pos: usize,
+}
+
+impl ModulatedDelay {
+    fn new() -> Self {
+        Self {
+            buffer: vec![0.0; MOD_DELAY_SIZE],
+            write_pos: 0,
+        }
+    }
+
+    /// Read `delay` samples behind the write position
+    fn read(&self, delay: f32) -> f32 {
+        let delay = delay.clamp(1.0, (MOD_DELAY_SIZE - 2) as f32);
+        let whole = delay.floor() as usize;
+        let frac = delay - whole as f32;
+        let a = self.buffer[(self.write_pos + MOD_DELAY_SIZE - whole) % MOD_DELAY_SIZE];
+        let b = self.buffer[(self.write_pos + MOD_DELAY_SIZE - whole - 1) % MOD_DELAY_SIZE];
+        a + (b - a) * frac
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.write_pos] = sample;
+        self.write_pos = (self.write_pos + 1) % MOD_DELAY_SIZE;
+    }
+}
+
+/// Chain of first-order allpass filters sharing one break frequency
+struct AllpassChain {
+    x1: [f32; PHASER_STAGES],
+    y1: [f32; PHASER_STAGES],
+}
+
+impl AllpassChain {
+    fn new() -> Self {
+        Self {
+            x1: [0.0; PHASER_STAGES],
+            y1: [0.0; PHASER_STAGES],
+        }
+    }
+
+    fn process(&mut self, input: f32, freq: f32, sample_rate: f32) -> f32 {
+        let t = (std::f32::consts::PI * freq / sample_rate).tan();
+        let a = (t - 1.0) / (t + 1.0);
+        let mut signal = input;
+        for stage in 0..PHASER_STAGES {
+            let y = a * signal + self.x1[stage] - a * self.y1[stage];
+            self.x1[stage] = signal;
+            self.y1[stage] = y;
+            signal = y;
+        }
+        signal
+    }
+}
+
+/// One channel of a modulation effect
+struct ModulationVoice {
+    delay: ModulatedDelay,
+    allpass: AllpassChain,
+    feedback: f32,
+}
+
+impl ModulationVoice {
+    fn new() -> Self {
+        Self {
+            delay: ModulatedDelay::new(),
+            allpass: AllpassChain::new(),
+            feedback: 0.0,
+        }
+    }
+
+    /// Process one sample with the LFO at `lfo` (-1 to 1), returning the wet signal
+    fn process(
+        &mut self,
+        kind: ModulationKind,
+        input: f32,
+        lfo: f32,
+        depth: f32,
+        feedback: f32,
+        sample_rate: f32,
+    ) -> f32 {
+        let driven = input + self.feedback * feedback;
+        let wet = match kind {
+            ModulationKind::Chorus | ModulationKind::Flanger => {
+                // Base delay and sweep range in milliseconds
+                let (base_ms, sweep_ms) =
+                    if kind == ModulationKind::Chorus { (15.0, 10.0) } else { (3.0, 2.5) };
+                let delay_ms = base_ms + sweep_ms * depth * lfo;
+                let wet = self.delay.read(delay_ms * 0.001 * sample_rate);
+                self.delay.write(driven);
+                wet
+            }
+            ModulationKind::Phaser => {
+                // Sweep the notches exponentially between 200Hz and up to 3.2kHz
+                let octaves = 4.0 * depth * (lfo + 1.0) * 0.5;
+                let freq = 200.0 * octaves.exp2();
+                self.allpass.process(driven, freq, sample_rate)
+            }
+        };
+        // Soft clip the loop so high feedback rings instead of exploding
+        self.feedback = wet.tanh();
+        wet
+    }
+}
+
+/// Chorus, flanger or phaser with stereo outputs from quadrature LFOs
+pub struct GraphModulation {
+    kind: ModulationKind,
+    rate: f32,
+    depth: f32,
+    feedback: f32,
+    mix: f32,
+    sample_rate: f32,
+    lfo_phase: f32,
+    voices: [ModulationVoice; 2],
+}
+
+impl GraphModulation {
+    pub fn new(kind: ModulationKind) -> Self {
+        let (rate, depth, feedback, mix) = kind.defaults();
+        Self {
+            kind,
+            rate,
+            depth,
+            feedback,
+            mix,
+            sample_rate: 44100.0,
+            lfo_phase: 0.0,
+            voices: [ModulationVoice::new(), ModulationVoice::new()],
+        }
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate.max(0.0);
+        self
+    }
+}
+
+impl GraphModule for GraphModulation {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Audio input".to_string(),
+            },
+            PortDescriptor {
+                name: "rate".to_string(),
+                default_value: 0.0,
+                description: "CV added to the LFO rate in Hz".to_string(),
+            },
+            PortDescriptor {
+                name: "depth".to_string(),
+                default_value: 0.0,
+                description: "CV added to the sweep depth (0-1)".to_string(),
+            },
+            PortDescriptor {
+                name: "feedback".to_string(),
+                default_value: 0.0,
+                description: "CV added to the feedback amount (-0.95 to 0.95)".to_string(),
+            },
+            PortDescriptor {
+                name: "mix".to_string(),
+                default_value: 0.0,
+                description: "CV added to the dry/wet mix (0-1)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "left".to_string(),
+                default_value: 0.0,
+                description: "Left output".to_string(),
+            },
+            PortDescriptor {
+                name: "right".to_string(),
+                default_value: 0.0,
+                description: "Right output (LFO a quarter cycle ahead)".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let rate_cv = inputs.get("rate").map(|b| b.as_slice()).unwrap_or(&[]);
+        let depth_cv = inputs.get("depth").map(|b| b.as_slice()).unwrap_or(&[]);
+        let feedback_cv = inputs.get("feedback").map(|b| b.as_slice()).unwrap_or(&[]);
+        let mix_cv = inputs.get("mix").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [left_out, right_out] = outputs.get_many_mut(["left", "right"]);
+        let left_out = left_out.unwrap();
+        let right_out = right_out.unwrap();
+
+        for i in 0..sample_count {
+            let sample = if i < input.len() { input[i] } else { 0.0 };
+            let rate_val = if i < rate_cv.len() { rate_cv[i] } else { 0.0 };
+            let depth_val = if i < depth_cv.len() { depth_cv[i] } else { 0.0 };
+            let feedback_val = if i < feedback_cv.len() { feedback_cv[i] } else { 0.0 };
+            let mix_val = if i < mix_cv.len() { mix_cv[i] } else { 0.0 };
+
+            let depth = (self.depth + depth_val).clamp(0.0, 1.0);
+            let feedback = (self.feedback + feedback_val).clamp(-0.95, 0.95);
+            let mix = (self.mix + mix_val).clamp(0.0, 1.0);
+
+            for (channel, out) in [&mut *left_out, &mut *right_out].into_iter().enumerate() {
+                let lfo = ((self.lfo_phase + channel as f32 * 0.25) * TAU).sin();
+                let wet = self.voices[channel].process(
+                    self.kind,
+                    sample,
+                    lfo,
+                    depth,
+                    feedback,
+                    self.sample_rate,
+                );
+                out[i] = sample + (wet - sample) * mix;
+            }
+
+            let rate = (self.rate + rate_val).max(0.0);
+            self.lfo_phase = (self.lfo_phase + rate / self.sample_rate).fract();
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "rate" => {
+                self.rate = value.max(0.0);
+                Ok(())
+            }
+            "depth" => {
+                self.depth = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "feedback" | "fb" => {
+                self.feedback = value.clamp(-0.95, 0.95);
+                Ok(())
+            }
+            "mix" => {
+                self.mix = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.rate),
+            "depth" => Some(self.depth),
+            "feedback" | "fb" => Some(self.feedback),
+            "mix" => Some(self.mix),
+            _ => None,
+        }
+    }
+}
+
+/// Fewest and most bands the vocoder can split into
+const MIN_VOCODER_BANDS: usize = 8;
+const MAX_VOCODER_BANDS: usize = 32;
+/// Centre frequencies of the lowest and highest vocoder bands
+const VOCODER_LOW_HZ: f32 = 100.0;
+const VOCODER_HIGH_HZ: f32 = 8000.0;
+/// Corner of the sibilance detector and unvoiced noise filter
+const SIBILANCE_HZ: f32 = 5000.0;
+
+/// Direct form I biquad with bandpass and highpass designs
+#[derive(Clone, Copy)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    fn new() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    /// Forget past samples, for when the filter is moved somewhere else
+    fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.x2 = 0.0;
+        self.y1 = 0.0;
+        self.y2 = 0.0;
+    }
+
+    /// Constant 0dB peak gain bandpass
+    fn set_bandpass(&mut self, freq: f32, q: f32, sample_rate: f32) {
+        let w0 = TAU * freq / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        self.b0 = alpha / a0;
+        self.b1 = 0.0;
+        self.b2 = -alpha / a0;
+        self.a1 = -2.0 * w0.cos() / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    fn set_highpass(&mut self, freq: f32, q: f32, sample_rate: f32) {
+        let w0 = TAU * freq / sample_rate;
+        let cos = w0.cos();
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        self.b0 = (1.0 + cos) / 2.0 / a0;
+        self.b1 = -(1.0 + cos) / a0;
+        self.b2 = (1.0 + cos) / 2.0 / a0;
+        self.a1 = -2.0 * cos / a0;
+        self.a2 = (1.0 - alpha) / a0;
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = output;
+        output
+    }
+}
+
+/// Channel vocoder - imposes the modulator's spectral envelope on the carrier
+pub struct GraphVocoder {
+    bands: usize,
+    active_bands: usize,
+    shift: f32,
+    attack: f32,
+    release: f32,
+    noise: f32,
+    sample_rate: f32,
+    // Two cascaded sections per band for steeper skirts
+    analysis: [[Biquad; 2]; MAX_VOCODER_BANDS],
+    synthesis: [[Biquad; 2]; MAX_VOCODER_BANDS],
+    followers: Vec<PeakFollower>,
+    levels: [f32; MAX_VOCODER_BANDS],
+    sibilance_detect: Biquad,
+    sibilance_follower: PeakFollower,
+    noise_filter: Biquad,
+    rng: Lcg,
+}
+
+impl GraphVocoder {
+    pub fn new(bands: usize) -> Self {
+        let bands = bands.clamp(MIN_VOCODER_BANDS, MAX_VOCODER_BANDS);
+        let sample_rate = 44100.0;
+        let mut sibilance_detect = Biquad::new();
+        sibilance_detect.set_highpass(SIBILANCE_HZ, 0.707, sample_rate);
+        let mut vocoder = Self {
+            bands,
+            active_bands: 0,
+            shift: 0.0,
+            attack: 0.005,
+            release: 0.05,
+            noise: 0.0,
+            sample_rate,
+            analysis: [[Biquad::new(); 2]; MAX_VOCODER_BANDS],
+            synthesis: [[Biquad::new(); 2]; MAX_VOCODER_BANDS],
+            followers: (0..MAX_VOCODER_BANDS).map(|_| PeakFollower::new()).collect(),
+            levels: [0.0; MAX_VOCODER_BANDS],
+            sibilance_detect,
+            sibilance_follower: PeakFollower::new(),
+            noise_filter: sibilance_detect,
+            rng: Lcg::new(24680),
+        };
+        vocoder.configure_bands(bands);
+        vocoder
+    }
+
+    /// Spread `count` bands log-evenly between the lowest and highest centres
+    ///
+    /// Each band starts from silence, since its old state belongs to a
+    /// different centre frequency.
+    fn configure_bands(&mut self, count: usize) {
+        if count == self.active_bands {
+            return;
+        }
+        let ratio = (VOCODER_HIGH_HZ / VOCODER_LOW_HZ).powf(1.0 / (count - 1) as f32);
+        // Neighbouring bands cross over at their geometric midpoint
+        let q = 1.0 / (ratio.sqrt() - 1.0 / ratio.sqrt());
+        for band in 0..count {
+            let freq = VOCODER_LOW_HZ * ratio.powi(band as i32);
+            for section in self.analysis[band].iter_mut().chain(&mut self.synthesis[band]) {
+                section.set_bandpass(freq, q, self.sample_rate);
+                section.reset();
+            }
+            self.followers[band] = PeakFollower::new();
+            self.levels[band] = 0.0;
+        }
+        self.active_bands = count;
+    }
+
+    /// Modulator level for a synthesis band, read `shift` bands lower
+    fn shifted_level(&self, band: usize, shift: f32) -> f32 {
+        let position = band as f32 - shift;
+        if position < 0.0 || position > (self.active_bands - 1) as f32 {
+            return 0.0;
+        }
+        let lower = position.floor() as usize;
+        let upper = (lower + 1).min(self.active_bands - 1);
+        let frac = position - lower as f32;
+        self.levels[lower] + (self.levels[upper] - self.levels[lower]) * frac
+    }
+}
+
+impl Default for GraphVocoder {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl GraphModule for GraphVocoder {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "carrier".to_string(),
+                default_value: 0.0,
+                description: "Signal to be shaped, ideally bright (saw, noise)".to_string(),
+            },
+            PortDescriptor {
+                name: "modulator".to_string(),
+                default_value: 0.0,
+                description: "Signal whose spectrum is imposed (voice, drums)".to_string(),
+            },
+            PortDescriptor {
+                name: "bands".to_string(),
+                default_value: 0.0,
+                description: "CV added to the band count (8-32)".to_string(),
+            },
+            PortDescriptor {
+                name: "shift".to_string(),
+                default_value: 0.0,
+                description: "CV added to the formant shift in bands".to_string(),
+            },
+            PortDescriptor {
+                name: "attack".to_string(),
+                default_value: 0.0,
+                description: "Attack time CV in seconds (overrides parameter when > 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "release".to_string(),
+                default_value: 0.0,
+                description: "Release time CV in seconds (overrides parameter when > 0)"
+                    .to_string(),
+            },
+            PortDescriptor {
+                name: "noise".to_string(),
+                default_value: 0.0,
+                description: "CV added to the unvoiced noise blend (0-1)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Vocoded output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let carrier = inputs.get("carrier").map(|b| b.as_slice()).unwrap_or(&[]);
+        let modulator = inputs.get("modulator").map(|b| b.as_slice()).unwrap_or(&[]);
+        let bands_cv = inputs.get("bands").map(|b| b.as_slice()).unwrap_or(&[]);
+        let shift_cv = inputs.get("shift").map(|b| b.as_slice()).unwrap_or(&[]);
+        let attack_cv = inputs.get("attack").map(|b| b.as_slice()).unwrap_or(&[]);
+        let release_cv = inputs.get("release").map(|b| b.as_slice()).unwrap_or(&[]);
+        let noise_cv = inputs.get("noise").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        if let Some(output) = outputs.get_mut("out") {
+            for i in 0..sample_count {
+                let carrier_val = if i < carrier.len() { carrier[i] } else { 0.0 };
+                let modulator_val = if i < modulator.len() { modulator[i] } else { 0.0 };
+                let bands_val = if i < bands_cv.len() { bands_cv[i] } else { 0.0 };
+                let shift_val = if i < shift_cv.len() { shift_cv[i] } else { 0.0 };
+                let attack_val = if i < attack_cv.len() { attack_cv[i] } else { 0.0 };
+                let release_val = if i < release_cv.len() { release_cv[i] } else { 0.0 };
+                let noise_val = if i < noise_cv.len() { noise_cv[i] } else { 0.0 };
+
+                let bands = (self.bands as f32 + bands_val)
+                    .round()
+                    .clamp(MIN_VOCODER_BANDS as f32, MAX_VOCODER_BANDS as f32)
+                    as usize;
+                self.configure_bands(bands);
+
+                let attack = if attack_val > 0.0001 { attack_val } else { self.attack };
+                let release = if release_val > 0.0001 { release_val } else { self.release };
+                let max_shift = (bands - 1) as f32;
+                let shift = (self.shift + shift_val).clamp(-max_shift, max_shift);
+                let noise = (self.noise + noise_val).clamp(0.0, 1.0);
+
+                for band in 0..bands {
+                    let [first, second] = &mut self.analysis[band];
+                    let filtered = second.process(first.process(modulator_val));
+                    self.levels[band] =
+                        self.followers[band].process(filtered, attack, release, self.sample_rate);
+                }
+
+                let mut sum = 0.0;
+                for band in 0..bands {
+                    let level = self.shifted_level(band, shift);
+                    let [first, second] = &mut self.synthesis[band];
+                    sum += second.process(first.process(carrier_val)) * level;
+                }
+
+                // Sibilants have little energy in the voiced bands, so pass
+                // them through as filtered noise following the modulator's top end
+                let hiss = self.sibilance_detect.process(modulator_val);
+                let hiss_level =
+                    self.sibilance_follower.process(hiss, 0.001, release, self.sample_rate);
+                let unvoiced = self.noise_filter.process(self.rng.next_bipolar()) * hiss_level;
+
+                // Narrower bands pass less of the carrier, so make up the loss
+                output[i] = sum * (bands as f32).sqrt() + unvoiced * noise * 4.0;
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "bands" => {
+                self.bands = (value.round() as usize).clamp(MIN_VOCODER_BANDS, MAX_VOCODER_BANDS);
+                Ok(())
+            }
+            "shift" => {
+                self.shift = value;
+                Ok(())
+            }
+            "attack" => {
+                self.attack = value.max(0.0001);
+                Ok(())
+            }
+            "release" => {
+                self.release = value.max(0.0001);
+                Ok(())
+            }
+            "noise" | "unvoiced" => {
+                self.noise = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "bands" => Some(self.bands as f32),
+            "shift" => Some(self.shift),
+            "attack" => Some(self.attack),
+            "release" => Some(self.release),
+            "noise" | "unvoiced" => Some(self.noise),
+            _ => None,
+        }
+    }
+}
+
+/// Longest string delay, enough for about 11Hz at 44.1kHz
+const STRING_DELAY_SIZE: usize = 4096;
+
+/// Karplus-Strong plucked string with a pick-position comb on the excitation
+pub struct GraphString {
+    frequency: f32,
+    damping: f32,
+    brightness: f32,
+    position: f32,
+    sample_rate: f32,
+    delay: Vec<f32>,
+    write_pos: usize,
+    pick: Vec<f32>,
+    last_read: f32,
+    excite_lp: f32,
+    burst_remaining: usize,
+    last_trig: f32,
+    rng: Lcg,
+}
+
+impl GraphString {
+    pub fn new(frequency: f32) -> Self {
+        Self {
+            frequency: frequency.max(1.0),
+            damping: 0.3,
+            brightness: 0.5,
+            position: 0.2,
+            sample_rate: 44100.0,
+            delay: vec![0.0; STRING_DELAY_SIZE],
+            write_pos: 0,
+            pick: vec![0.0; STRING_DELAY_SIZE],
+            last_read: 0.0,
+            excite_lp: 0.0,
+            burst_remaining: 0,
+            last_trig: 0.0,
+            rng: Lcg::new(97531),
+        }
+    }
+
+    /// Value written `samples` ago into a circular buffer, linearly interpolated
+    fn read(buffer: &[f32], write_pos: usize, samples: f32) -> f32 {
+        let position = write_pos as f32 - samples;
+        let position = position.rem_euclid(STRING_DELAY_SIZE as f32);
+        let index = position.floor() as usize;
+        let frac = position - index as f32;
+        let next = (index + 1) % STRING_DELAY_SIZE;
+        buffer[index] + (buffer[next] - buffer[index]) * frac
+    }
+}
+
+impl Default for GraphString {
+    fn default() -> Self {
+        Self::new(220.0)
+    }
+}
+
+impl GraphModule for GraphString {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "trig".to_string(),
+                default_value: 0.0,
+                description: "Pluck with a noise burst on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "pitch".to_string(),
+                default_value: 0.0,
+                description: "Frequency in Hz (overrides parameter when > 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "damping".to_string(),
+                default_value: 0.0,
+                description: "CV added to damping (0 = long ring, 1 = dead)".to_string(),
+            },
+            PortDescriptor {
+                name: "brightness".to_string(),
+                default_value: 0.0,
+                description: "CV added to brightness (0-1)".to_string(),
+            },
+            PortDescriptor {
+                name: "position".to_string(),
+                default_value: 0.0,
+                description: "CV added to pluck position along the string (0-1)".to_string(),
+            },
+            PortDescriptor {
+                name: "exciter".to_string(),
+                default_value: 0.0,
+                description: "Audio fed into the string alongside plucks".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "String output".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let trig = inputs.get("trig").map(|b| b.as_slice()).unwrap_or(&[]);
+        let pitch = inputs.get("pitch").map(|b| b.as_slice()).unwrap_or(&[]);
+        let damping_cv = inputs.get("damping").map(|b| b.as_slice()).unwrap_or(&[]);
+        let brightness_cv = inputs.get("brightness").map(|b| b.as_slice()).unwrap_or(&[]);
+        let position_cv = inputs.get("position").map(|b| b.as_slice()).unwrap_or(&[]);
+        let exciter = inputs.get("exciter").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        if let Some(output) = outputs.get_mut("out") {
+            for i in 0..sample_count {
+                let trig_val = if i < trig.len() { trig[i] } else { 0.0 };
+                let pitch_val = if i < pitch.len() { pitch[i] } else { 0.0 };
+                let damping_val = if i < damping_cv.len() { damping_cv[i] } else { 0.0 };
+                let brightness_val = if i < brightness_cv.len() { brightness_cv[i] } else { 0.0 };
+                let position_val = if i < position_cv.len() { position_cv[i] } else { 0.0 };
+                let exciter_val = if i < exciter.len() { exciter[i] } else { 0.0 };
+
+                let freq = if pitch_val > 0.0 { pitch_val } else { self.frequency };
+                let min_freq = self.sample_rate / (STRING_DELAY_SIZE - 2) as f32;
+                let period = self.sample_rate / freq.clamp(min_freq, self.sample_rate * 0.25);
+                let damping = (self.damping + damping_val).clamp(0.0, 1.0);
+                let brightness = (self.brightness + brightness_val).clamp(0.0, 1.0);
+                let position = (self.position + position_val).clamp(0.02, 0.98);
+
+                if trig_val > 0.5 && self.last_trig <= 0.5 {
+                    self.burst_remaining = period as usize;
+                }
+                self.last_trig = trig_val;
+
+                let mut excite = exciter_val;
+                if self.burst_remaining > 0 {
+                    self.burst_remaining -= 1;
+                    excite += self.rng.next_bipolar();
+                }
+
+                // Darker plucks for darker strings
+                self.excite_lp += (excite - self.excite_lp) * (0.2 + 0.8 * brightness);
+                // Plucking a fraction of the way along cancels the harmonics
+                // with a node there
+                self.pick[self.write_pos] = self.excite_lp;
+                let pick_delay = (position * period).max(1.0);
+                let excite = self.excite_lp - Self::read(&self.pick, self.write_pos, pick_delay);
+
+                // Two-tap loop filter delays by (1 - brightness) / 2 samples,
+                // which comes off the line length to keep the string in tune
+                let fir_delay = (1.0 - brightness) * 0.5;
+                let current = Self::read(&self.delay, self.write_pos, period - fir_delay);
+                let tap = (1.0 + brightness) * 0.5;
+                let filtered = current * tap + self.last_read * (1.0 - tap);
+                self.last_read = current;
+
+                // Damping 0-1 maps a 10s ring down to 10ms
+                let t60 = 10.0f32.powf(1.0 - 3.0 * damping);
+                let gain = 0.001f32.powf(1.0 / (t60 * freq));
+
+                let value = filtered * gain + excite;
+                self.delay[self.write_pos] = value;
+                self.write_pos = (self.write_pos + 1) % STRING_DELAY_SIZE;
+                output[i] = value;
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "freq" | "frequency" | "pitch" => {
+                self.frequency = value.max(1.0);
+                Ok(())
+            }
+            "damping" => {
+                self.damping = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "brightness" => {
+                self.brightness = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "position" => {
+                self.position = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "freq" | "frequency" | "pitch" => Some(self.frequency),
+            "damping" => Some(self.damping),
+            "brightness" => Some(self.brightness),
+            "position" => Some(self.position),
+            _ => None,
+        }
+    }
+}
+
+/// Number of modes in the resonator bank
+const RESONATOR_MODES: usize = 8;
+
+/// Mode frequency ratios of the resonator bodies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResonatorShape {
+    /// Whole-number partials, like a string or tube
+    Harmonic,
+    /// Free-free bar partials, like a marimba or glockenspiel
+    Bar,
+    /// Circular membrane partials, like a tom or tabla
+    Membrane,
+}
+
+impl ResonatorShape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "harmonic" | "string" | "tube" => Some(Self::Harmonic),
+            "bar" | "marimba" => Some(Self::Bar),
+            "membrane" | "drum" => Some(Self::Membrane),
+            _ => None,
+        }
+    }
+
+    pub fn from_param(value: f32) -> Self {
+        match value as i32 {
+            1 => Self::Bar,
+            2 => Self::Membrane,
+            _ => Self::Harmonic,
+        }
+    }
+
+    pub fn as_param(self) -> f32 {
+        match self {
+            Self::Harmonic => 0.0,
+            Self::Bar => 1.0,
+            Self::Membrane => 2.0,
+        }
+    }
+
+    fn ratios(self) -> [f32; RESONATOR_MODES] {
+        match self {
+            Self::Harmonic => [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0],
+            Self::Bar => [1.0, 2.756, 5.404, 8.933, 13.345, 18.638, 24.813, 31.870],
+            Self::Membrane => [1.0, 1.594, 2.136, 2.296, 2.653, 2.918, 3.156, 3.501],
+        }
+    }
+}
+
+/// Two-pole resonator for one mode, normalised to ring at unit amplitude
+/// after a unit impulse
+#[derive(Clone, Copy, Default)]
+struct ModeFilter {
+    a1: f32,
+    a2: f32,
+    input_gain: f32,
+    level: f32,
+    y1: f32,
+    y2: f32,
+}
+
+/// Modal resonator - a bank of tuned, decaying modes to strike or excite
+pub struct GraphResonator {
+    frequency: f32,
+    decay: f32,
+    brightness: f32,
+    shape: ResonatorShape,
+    sample_rate: f32,
+    modes: [ModeFilter; RESONATOR_MODES],
+    tuned_for: (f32, f32, f32),
+    last_trig: f32,
+}
+
+impl GraphResonator {
+    pub fn new(frequency: f32, shape: ResonatorShape) -> Self {
+        Self {
+            frequency: frequency.max(1.0),
+            decay: 1.0,
+            brightness: 0.5,
+            shape,
+            sample_rate: 44100.0,
+            modes: [ModeFilter::default(); RESONATOR_MODES],
+            tuned_for: (0.0, 0.0, 0.0),
+            last_trig: 0.0,
+        }
+    }
+
+    /// Recompute mode coefficients, skipping the work when nothing changed
+    fn tune(&mut self, freq: f32, decay: f32, brightness: f32) {
+        if self.tuned_for == (freq, decay, brightness) {
+            return;
+        }
+        self.tuned_for = (freq, decay, brightness);
+
+        for (mode, ratio) in self.modes.iter_mut().zip(self.shape.ratios()) {
+            let mode_freq = freq * ratio;
+            if mode_freq >= self.sample_rate * 0.45 {
+                mode.level = 0.0;
+                continue;
+            }
+            // Higher modes die away faster, as they do in real bodies
+            let mode_decay = decay / ratio.sqrt();
+            let radius = 0.001f32.powf(1.0 / (mode_decay * self.sample_rate));
+            let theta = TAU * mode_freq / self.sample_rate;
+            mode.a1 = 2.0 * radius * theta.cos();
+            mode.a2 = -radius * radius;
+            mode.input_gain = theta.sin();
+            // Dark settings roll the upper modes off at up to 12dB per octave
+            mode.level = ratio.powf(-2.0 * (1.0 - brightness));
+        }
+    }
+}
+
+impl Default for GraphResonator {
+    fn default() -> Self {
+        Self::new(220.0, ResonatorShape::Harmonic)
+    }
+}
+
+impl GraphModule for GraphResonator {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "in".to_string(),
+                default_value: 0.0,
+                description: "Exciter audio, such as short noise bursts".to_string(),
+            },
+            PortDescriptor {
+                name: "trig".to_string(),
+                default_value: 0.0,
+                description: "Strike with a unit impulse on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "pitch".to_string(),
+                default_value: 0.0,
+                description: "Fundamental in Hz (overrides parameter when > 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "decay".to_string(),
+                default_value: 0.0,
+                description: "Decay time CV in seconds (overrides parameter when > 0)".to_string(),
+            },
+            PortDescriptor {
+                name: "brightness".to_string(),
+                default_value: 0.0,
+                description: "CV added to brightness (0-1)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Sum of all modes".to_string(),
+        }]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let input = inputs.get("in").map(|b| b.as_slice()).unwrap_or(&[]);
+        let trig = inputs.get("trig").map(|b| b.as_slice()).unwrap_or(&[]);
+        let pitch = inputs.get("pitch").map(|b| b.as_slice()).unwrap_or(&[]);
+        let decay_cv = inputs.get("decay").map(|b| b.as_slice()).unwrap_or(&[]);
+        let brightness_cv = inputs.get("brightness").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        if let Some(output) = outputs.get_mut("out") {
+            for i in 0..sample_count {
+                let input_val = if i < input.len() { input[i] } else { 0.0 };
+                let trig_val = if i < trig.len() { trig[i] } else { 0.0 };
+                let pitch_val = if i < pitch.len() { pitch[i] } else { 0.0 };
+                let decay_val = if i < decay_cv.len() { decay_cv[i] } else { 0.0 };
+                let brightness_val = if i < brightness_cv.len() { brightness_cv[i] } else { 0.0 };
+
+                let freq = if pitch_val > 0.0 { pitch_val } else { self.frequency };
+                let decay = if decay_val > 0.0001 { decay_val } else { self.decay };
+                let brightness = (self.brightness + brightness_val).clamp(0.0, 1.0);
+                self.tune(freq, decay, brightness);
+
+                let mut excite = input_val;
+                if trig_val > 0.5 && self.last_trig <= 0.5 {
+                    excite += 1.0;
+                }
+                self.last_trig = trig_val;
+
+                let mut sum = 0.0;
+                let mut total_level = 0.0;
+                for mode in &mut self.modes {
+                    let y = excite * mode.input_gain + mode.a1 * mode.y1 + mode.a2 * mode.y2;
+                    mode.y2 = mode.y1;
+                    mode.y1 = y;
+                    sum += y * mode.level;
+                    total_level += mode.level;
+                }
+                output[i] = if total_level > 0.0 { sum / total_level } else { 0.0 };
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "freq" | "frequency" | "pitch" => {
+                self.frequency = value.max(1.0);
+                Ok(())
+            }
+            "decay" => {
+                self.decay = value.max(0.001);
+                Ok(())
+            }
+            "brightness" => {
+                self.brightness = value.clamp(0.0, 1.0);
+                Ok(())
+            }
+            "shape" => {
+                self.shape = ResonatorShape::from_param(value);
+                self.tuned_for = (0.0, 0.0, 0.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "freq" | "frequency" | "pitch" => Some(self.frequency),
+            "decay" => Some(self.decay),
+            "brightness" => Some(self.brightness),
+            "shape" => Some(self.shape.as_param()),
+            _ => None,
+        }
+    }
+}
+
+/// Random voltage source - new values at a clocked rate or on triggers,
+/// available stepped, smoothly interpolated and slewed
+pub struct GraphRandom {
+    rate: f32,
+    slew: f32,
+    seed: u32,
+    sample_rate: f32,
+    rng: Lcg,
+    phase: f32,
+    previous: f32,
+    current: f32,
+    slewed: f32,
+    samples_since_step: f32,
+    step_length: f32,
+    last_trig: f32,
+}
+
+impl GraphRandom {
+    pub fn new(rate: f32, seed: u32) -> Self {
+        let mut random = Self {
+            rate: rate.max(0.0),
+            slew: 0.1,
+            seed,
+            sample_rate: 44100.0,
+            rng: Lcg::new(seed),
+            phase: 0.0,
+            previous: 0.0,
+            current: 0.0,
+            slewed: 0.0,
+            samples_since_step: 0.0,
+            step_length: 1.0,
+            last_trig: 0.0,
+        };
+        random.reseed(seed);
+        random
+    }
+
+    /// Restart the random sequence so a seed always gives the same values
+    fn reseed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.rng = Lcg::new(seed);
+        self.current = self.rng.next_bipolar();
+        self.previous = self.current;
+        self.slewed = self.current;
+        self.phase = 0.0;
+        self.samples_since_step = 0.0;
+    }
+}
+
+impl Default for GraphRandom {
+    fn default() -> Self {
+        Self::new(1.0, 12345)
+    }
+}
+
+impl GraphModule for GraphRandom {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "rate".to_string(),
+                default_value: 0.0,
+                description: "CV added to the rate in Hz".to_string(),
+            },
+            PortDescriptor {
+                name: "trig".to_string(),
+                default_value: 0.0,
+                description: "Pick a new value on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "slew".to_string(),
+                default_value: 0.0,
+                description: "Slew time CV in seconds (overrides parameter when > 0)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "stepped".to_string(),
+                default_value: 0.0,
+                description: "Random value held until the next step (-1 to 1)".to_string(),
+            },
+            PortDescriptor {
+                name: "smooth".to_string(),
+                default_value: 0.0,
+                description: "Cosine interpolation from the previous value to the current one"
+                    .to_string(),
+            },
+            PortDescriptor {
+                name: "slewed".to_string(),
+                default_value: 0.0,
+                description: "Stepped value through a lag".to_string(),
+            },
+            PortDescriptor {
+                name: "trigger".to_string(),
+                default_value: 0.0,
+                description: "Single-sample pulse on every new value".to_string(),
+            },
+        ]
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let rate_cv = inputs.get("rate").map(|b| b.as_slice()).unwrap_or(&[]);
+        let trig = inputs.get("trig").map(|b| b.as_slice()).unwrap_or(&[]);
+        let slew_cv = inputs.get("slew").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [stepped_out, smooth_out, slewed_out, trigger_out] =
+            outputs.get_many_mut(["stepped", "smooth", "slewed", "trigger"]);
+        let stepped_out = stepped_out.unwrap();
+        let smooth_out = smooth_out.unwrap();
+        let slewed_out = slewed_out.unwrap();
+        let trigger_out = trigger_out.unwrap();
+
+        for i in 0..sample_count {
+            let rate_val = if i < rate_cv.len() { rate_cv[i] } else { 0.0 };
+            let trig_val = if i < trig.len() { trig[i] } else { 0.0 };
+            let slew_val = if i < slew_cv.len() { slew_cv[i] } else { 0.0 };
+
+            let rate = (self.rate + rate_val).max(0.0);
+            let triggered = trig_val > 0.5 && self.last_trig <= 0.5;
+            self.last_trig = trig_val;
+
+            self.phase += rate / self.sample_rate;
+            let clocked = self.phase >= 1.0;
+
+            trigger_out[i] = 0.0;
+            if triggered || clocked {
+                // Triggered steps are spaced however the triggers are, so the
+                // smooth output glides over the last interval seen
+                self.step_length = if triggered {
+                    self.phase = 0.0;
+                    self.samples_since_step.max(1.0)
+                } else {
+                    self.phase = self.phase.fract();
+                    self.sample_rate / rate
+                };
+                self.previous = self.current;
+                self.current = self.rng.next_bipolar();
+                self.samples_since_step = 0.0;
+                trigger_out[i] = 1.0;
+            }
+            self.samples_since_step += 1.0;
+
+            let progress = (self.samples_since_step / self.step_length).min(1.0);
+            let eased = (1.0 - (progress * std::f32::consts::PI).cos()) * 0.5;
+
+            let slew = if slew_val > 0.0001 { slew_val } else { self.slew };
+            let coeff = (-1.0 / (slew.max(0.0001) * self.sample_rate)).exp();
+            self.slewed = self.current + (self.slewed - self.current) * coeff;
+
+            stepped_out[i] = self.current;
+            smooth_out[i] = self.previous + (self.current - self.previous) * eased;
+            slewed_out[i] = self.slewed;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "rate" => {
+                self.rate = value.max(0.0);
+                Ok(())
+            }
+            "slew" => {
+                self.slew = value.max(0.0001);
+                Ok(())
+            }
+            "seed" => {
+                self.reseed(value as u32);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.rate),
+            "slew" => Some(self.slew),
+            "seed" => Some(self.seed as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Strange attractors the chaos module can integrate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChaosKind {
+    /// Two-lobed butterfly; parameters sigma, rho and beta
+    Lorenz,
+    /// Single spiral with occasional spikes; parameters a, b and c
+    Rossler,
+}
+
+impl ChaosKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lorenz" => Some(Self::Lorenz),
+            "rossler" | "roessler" => Some(Self::Rossler),
+            _ => None,
+        }
+    }
+
+    pub fn from_param(value: f32) -> Self {
+        match value as i32 {
+            1 => Self::Rossler,
+            _ => Self::Lorenz,
+        }
+    }
+
+    pub fn as_param(self) -> f32 {
+        match self {
+            Self::Lorenz => 0.0,
+            Self::Rossler => 1.0,
+        }
+    }
+
+    /// The classic chaotic parameter set
+    fn defaults(self) -> [f32; 3] {
+        match self {
+            Self::Lorenz => [10.0, 28.0, 8.0 / 3.0],
+            Self::Rossler => [0.2, 0.2, 5.7],
+        }
+    }
+
+    /// Attractor time units per second at rate 1, about one orbit
+    fn time_scale(self) -> f64 {
+        match self {
+            Self::Lorenz => 0.75,
+            Self::Rossler => 6.0,
+        }
+    }
+
+    /// Largest stable integration step
+    fn max_step(self) -> f64 {
+        match self {
+            Self::Lorenz => 0.005,
+            Self::Rossler => 0.05,
+        }
+    }
+
+    fn derivatives(self, params: [f64; 3], [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let [a, b, c] = params;
+        match self {
+            Self::Lorenz => [a * (y - x), x * (b - z) - y, x * y - c * z],
+            Self::Rossler => [-y - z, x + a * y, b + z * (x - c)],
+        }
+    }
+
+    /// Offset and scale that bring each axis to roughly -1 to 1
+    fn normalise(self, [x, y, z]: [f64; 3]) -> [f32; 3] {
+        match self {
+            Self::Lorenz => [(x / 20.0) as f32, (y / 27.0) as f32, ((z - 25.0) / 25.0) as f32],
+            Self::Rossler => [(x / 12.0) as f32, (y / 12.0) as f32, (z / 10.0 - 1.0) as f32],
+        }
+    }
+}
+
+/// Chaotic modulation source - a Lorenz or Rossler attractor run at audio rate
+pub struct GraphChaos {
+    kind: ChaosKind,
+    rate: f32,
+    params: [f32; 3],
+    seed: u32,
+    sample_rate: f32,
+    state: [f64; 3],
+}
+
+impl GraphChaos {
+    pub fn new(kind: ChaosKind, rate: f32, seed: u32) -> Self {
+        let mut chaos = Self {
+            kind,
+            rate: rate.max(0.0),
+            params: kind.defaults(),
+            seed,
+            sample_rate: 44100.0,
+            state: [0.0; 3],
+        };
+        chaos.reseed(seed);
+        chaos
+    }
+
+    /// Start from a point near the attractor picked by the seed
+    fn reseed(&mut self, seed: u32) {
+        self.seed = seed;
+        let mut rng = Lcg::new(seed);
+        self.state = [0.0; 3].map(|_: f64| 1.0 + 0.5 * f64::from(rng.next_bipolar()));
+    }
+
+    /// One fourth-order Runge-Kutta step
+    fn step(&mut self, params: [f64; 3], dt: f64) {
+        let s = self.state;
+        let offset = |k: [f64; 3], h: f64| [s[0] + k[0] * h, s[1] + k[1] * h, s[2] + k[2] * h];
+        let k1 = self.kind.derivatives(params, s);
+        let k2 = self.kind.derivatives(params, offset(k1, dt * 0.5));
+        let k3 = self.kind.derivatives(params, offset(k2, dt * 0.5));
+        let k4 = self.kind.derivatives(params, offset(k3, dt));
+        for axis in 0..3 {
+            self.state[axis] += dt / 6.0 * (k1[axis] + 2.0 * k2[axis] + 2.0 * k3[axis] + k4[axis]);
+        }
+    }
+}
+
+impl Default for GraphChaos {
+    fn default() -> Self {
+        Self::new(ChaosKind::Lorenz, 1.0, 12345)
+    }
+}
+
+impl GraphModule for GraphChaos {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "rate".to_string(),
+                default_value: 0.0,
+                description: "CV added to the speed (about one orbit per second at 1)".to_string(),
+            },
+            PortDescriptor {
+                name: "a".to_string(),
+                default_value: 0.0,
+                description: "CV added to the first parameter (sigma or a)".to_string(),
+            },
+            PortDescriptor {
+                name: "b".to_string(),
+                default_value: 0.0,
+                description: "CV added to the second parameter (rho or b)".to_string(),
+            },
+            PortDescriptor {
+                name: "c".to_string(),
+                default_value: 0.0,
+                description: "CV added to the third parameter (beta or c)".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        ["x", "y", "z"]
+            .iter()
+            .map(|axis| PortDescriptor {
+                name: (*axis).to_string(),
+                default_value: 0.0,
+                description: format!("Attractor {axis} axis, roughly -1 to 1"),
+            })
+            .collect()
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let rate_cv = inputs.get("rate").map(|b| b.as_slice()).unwrap_or(&[]);
+        let a_cv = inputs.get("a").map(|b| b.as_slice()).unwrap_or(&[]);
+        let b_cv = inputs.get("b").map(|b| b.as_slice()).unwrap_or(&[]);
+        let c_cv = inputs.get("c").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let [x_out, y_out, z_out] = outputs.get_many_mut(["x", "y", "z"]);
+        let x_out = x_out.unwrap();
+        let y_out = y_out.unwrap();
+        let z_out = z_out.unwrap();
+
+        for i in 0..sample_count {
+            let rate_val = if i < rate_cv.len() { rate_cv[i] } else { 0.0 };
+            let a_val = if i < a_cv.len() { a_cv[i] } else { 0.0 };
+            let b_val = if i < b_cv.len() { b_cv[i] } else { 0.0 };
+            let c_val = if i < c_cv.len() { c_cv[i] } else { 0.0 };
+
+            let rate = f64::from((self.rate + rate_val).max(0.0));
+            let params = [
+                f64::from(self.params[0] + a_val),
+                f64::from(self.params[1] + b_val),
+                f64::from(self.params[2] + c_val),
+            ];
+
+            // Split fast rates into several steps so the integration stays stable
+            let dt = rate * self.kind.time_scale() / f64::from(self.sample_rate);
+            let steps = (dt / self.kind.max_step()).ceil().clamp(1.0, 64.0);
+            for _ in 0..steps as usize {
+                self.step(params, dt / steps);
+            }
+
+            // Parameter CVs can throw the system off the attractor entirely
+            if self.state.iter().any(|v| !v.is_finite() || v.abs() > 1.0e4) {
+                self.reseed(self.seed);
+            }
+
+            let [x, y, z] = self.kind.normalise(self.state);
+            x_out[i] = x;
+            y_out[i] = y;
+            z_out[i] = z;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "rate" => {
+                self.rate = value.max(0.0);
+                Ok(())
+            }
+            "a" | "sigma" => {
+                self.params[0] = value;
+                Ok(())
+            }
+            "b" | "rho" => {
+                self.params[1] = value;
+                Ok(())
+            }
+            "c" | "beta" => {
+                self.params[2] = value;
+                Ok(())
+            }
+            "seed" => {
+                self.reseed(value as u32);
+                Ok(())
+            }
+            "kind" => {
+                self.kind = ChaosKind::from_param(value);
+                self.params = self.kind.defaults();
+                self.reseed(self.seed);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.rate),
+            "a" | "sigma" => Some(self.params[0]),
+            "b" | "rho" => Some(self.params[1]),
+            "c" | "beta" => Some(self.params[2]),
+            "seed" => Some(self.seed as f32),
+            "kind" => Some(self.kind.as_param()),
+            _ => None,
+        }
+    }
+}
+
+/// Sum every channel of an input into one block, using `scratch` (kept by
+/// the module so nothing is allocated once it has grown) when there are
+/// several
+fn mixdown<'a>(
+    inputs: &'a PortBuffers,
+    port: &str,
+    sample_count: usize,
+    scratch: &'a mut Vec<f32>,
+) -> &'a [f32] {
+    let buffer = inputs.get(port).map(|b| b.as_slice()).unwrap_or(&[]);
+    let channels = inputs.channels(port);
+    if channels == 1 {
+        return buffer;
+    }
+
+    scratch.clear();
+    scratch.resize(sample_count, 0.0);
+    for channel in 0..channels {
+        if let Some(samples) = inputs.channel(port, channel) {
+            for (total, sample) in scratch.iter_mut().zip(samples) {
+                *total += sample;
+            }
+        }
+    }
+    scratch
+}
+
+/// What the voice allocator does when a note arrives and every voice is busy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoicePolicy {
+    /// Voices are taken in turn, stealing the next one if none are free
+    RoundRobin,
+    /// Free voices are taken in turn, otherwise the longest-held note is cut
+    StealOldest,
+    /// Notes that arrive with every voice busy are dropped
+    NoSteal,
+}
+
+impl VoicePolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "round_robin" | "roundrobin" | "rr" => Some(Self::RoundRobin),
+            "steal" | "steal_oldest" | "oldest" => Some(Self::StealOldest),
+            "none" | "no_steal" | "nosteal" => Some(Self::NoSteal),
+            _ => None,
+        }
+    }
+
+    pub fn from_param(value: f32) -> Self {
+        match value as i32 {
+            1 => Self::StealOldest,
+            2 => Self::NoSteal,
+            _ => Self::RoundRobin,
+        }
+    }
+
+    pub fn as_param(self) -> f32 {
+        match self {
+            Self::RoundRobin => 0.0,
+            Self::StealOldest => 1.0,
+            Self::NoSteal => 2.0,
+        }
+    }
+}
+
+/// State of one voice handed out by the allocator
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Voice {
+    pub held: bool,
+    pub key: u32,
+    pub pitch: f32,
+    pub velocity: f32,
+    started: u64,
+    /// Hold the gate low for the next sample so envelopes restart
+    retrigger: bool,
+    /// Whether the gate was high the last time it was read
+    sounding: bool,
+}
+
+/// Assigns notes to a fixed set of voices
+///
+/// Notes are identified by a key (a MIDI note number or an input lane) so
+/// note-offs find the voice that is playing them. A voice that is stolen
+/// or re-struck drops its gate for one sample so envelopes restart. That
+/// includes a note released and started again before the gate was next
+/// read, such as a MIDI note-off and note-on at the same tick: the gate
+/// never went low in between, so it drops for the new note.
+pub struct VoiceAllocator {
+    voices: Vec<Voice>,
+    policy: VoicePolicy,
+    next: usize,
+    tick: u64,
+}
+
+impl VoiceAllocator {
+    pub fn new(voices: usize, policy: VoicePolicy) -> Self {
+        Self {
+            voices: vec![Voice::default(); voices.clamp(1, MAX_CHANNELS)],
+            policy,
+            next: 0,
+            tick: 0,
+        }
+    }
+
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
+
+    pub fn voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn set_voice_count(&mut self, voices: usize) {
+        self.voices.resize(voices.clamp(1, MAX_CHANNELS), Voice::default());
+        self.next %= self.voices.len();
+    }
+
+    pub fn policy(&self) -> VoicePolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: VoicePolicy) {
+        self.policy = policy;
+    }
+
+    /// Start a note, returning the voice that plays it
+    pub fn note_on(&mut self, key: u32, pitch: f32, velocity: f32) -> Option<usize> {
+        self.tick += 1;
+        let count = self.voices.len();
+
+        let index = if let Some(index) = self.voices.iter().position(|v| v.held && v.key == key) {
+            // Same note again: restart the voice already playing it
+            index
+        } else if let Some(index) =
+            (0..count).map(|i| (self.next + i) % count).find(|&i| !self.voices[i].held)
+        {
+            index
+        } else {
+            match self.policy {
+                VoicePolicy::RoundRobin => self.next,
+                VoicePolicy::StealOldest => {
+                    (0..count).min_by_key(|&i| self.voices[i].started).unwrap_or(0)
+                }
+                VoicePolicy::NoSteal => return None,
+            }
+        };
+
+        // A note that restarts a voice whose gate is still up, even one
+        // released this same sample, needs the gate to drop first
+        let voice = &mut self.voices[index];
+        voice.retrigger = voice.held || voice.sounding;
+        voice.held = true;
+        voice.key = key;
+        voice.pitch = pitch;
+        voice.velocity = velocity;
+        voice.started = self.tick;
+        self.next = (index + 1) % count;
+        Some(index)
+    }
+
+    /// Release the voice playing a note; it keeps its pitch for the tail
+    pub fn note_off(&mut self, key: u32) {
+        for voice in &mut self.voices {
+            if voice.held && voice.key == key {
+                voice.held = false;
+                voice.retrigger = false;
+            }
+        }
+    }
+
+    /// Move a held note to a new pitch without restarting it
+    pub fn set_pitch(&mut self, key: u32, pitch: f32) {
+        for voice in &mut self.voices {
+            if voice.held && voice.key == key {
+                voice.pitch = pitch;
+            }
+        }
+    }
+
+    /// Release every voice, as for a MIDI all-notes-off or a song restart
+    pub fn all_notes_off(&mut self) {
+        for voice in &mut self.voices {
+            voice.held = false;
+            voice.retrigger = false;
+        }
+    }
+
+    /// Gate level for a voice this sample, low for one sample after a steal
+    pub fn gate(&mut self, index: usize) -> f32 {
+        let voice = &mut self.voices[index];
+        voice.sounding = voice.held && !voice.retrigger;
+        voice.retrigger = false;
+        if voice.sounding {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Polyphonic voice allocator - spreads notes from gate/pitch lanes across
+/// a poly cable with one channel per voice
+///
+/// Each channel of the gate input is a lane: a rising edge starts a note at
+/// that lane's pitch and velocity, a falling edge releases it. A mono
+/// sequencer therefore plays overlapping notes round the voices.
+pub struct GraphPoly {
+    allocator: VoiceAllocator,
+    last_gates: [f32; MAX_CHANNELS],
+}
+
+impl GraphPoly {
+    pub fn new(voices: usize, policy: VoicePolicy) -> Self {
+        Self {
+            allocator: VoiceAllocator::new(voices, policy),
+            last_gates: [0.0; MAX_CHANNELS],
+        }
+    }
+}
+
+impl Default for GraphPoly {
+    fn default() -> Self {
+        Self::new(4, VoicePolicy::RoundRobin)
+    }
+}
+
+impl GraphModule for GraphPoly {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "Note gates, one lane per channel".to_string(),
+            },
+            PortDescriptor {
+                name: "pitch".to_string(),
+                default_value: 0.0,
+                description: "Pitch for each lane, passed through unchanged".to_string(),
+            },
+            PortDescriptor {
+                name: "velocity".to_string(),
+                default_value: 1.0,
+                description: "Velocity for each lane, sampled at note-on".to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "Gate per voice (poly)".to_string(),
+            },
+            PortDescriptor {
+                name: "pitch".to_string(),
+                default_value: 0.0,
+                description: "Pitch per voice, held through the release (poly)".to_string(),
+            },
+            PortDescriptor {
+                name: "velocity".to_string(),
+                default_value: 0.0,
+                description: "Velocity per voice (poly)".to_string(),
+            },
+        ]
+    }
+
+    fn is_polyphonic(&self) -> bool {
+        true
+    }
+
+    fn output_channels(&self) -> usize {
+        self.allocator.voice_count()
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let voices = self.allocator.voice_count();
+        let lanes = inputs.channels("gate");
+        for port in ["gate", "pitch", "velocity"] {
+            outputs.set_channels(port, voices, sample_count);
+        }
+
+        let [gate_out, pitch_out, velocity_out] =
+            outputs.get_many_mut(["gate", "pitch", "velocity"]);
+        let gate_out = gate_out.unwrap();
+        let pitch_out = pitch_out.unwrap();
+        let velocity_out = velocity_out.unwrap();
+
+        for i in 0..sample_count {
+            for lane in 0..lanes {
+                let gate = lane_sample(inputs, "gate", lane, i, 0.0);
+                let pitch = lane_sample(inputs, "pitch", lane, i, 0.0);
+                let last = self.last_gates[lane];
+                self.last_gates[lane] = gate;
+
+                if gate > 0.5 && last <= 0.5 {
+                    let velocity = lane_sample(inputs, "velocity", lane, i, 1.0);
+                    self.allocator.note_on(lane as u32, pitch, velocity);
+                } else if gate <= 0.5 && last > 0.5 {
+                    self.allocator.note_off(lane as u32);
+                } else if gate > 0.5 {
+                    self.allocator.set_pitch(lane as u32, pitch);
+                }
+            }
+
+            for v in 0..voices {
+                let index = v * sample_count + i;
+                gate_out[index] = self.allocator.gate(v);
+                pitch_out[index] = self.allocator.voices()[v].pitch;
+                velocity_out[index] = self.allocator.voices()[v].velocity;
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "voices" => {
+                self.allocator.set_voice_count(value.max(1.0) as usize);
+                Ok(())
+            }
+            "policy" => {
+                self.allocator.set_policy(VoicePolicy::from_param(value));
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "voices" => Some(self.allocator.voice_count() as f32),
+            "policy" => Some(self.allocator.policy().as_param()),
+            _ => None,
+        }
+    }
+}
+
+/// One sample of one lane of an input; mono inputs feed every lane
+fn lane_sample(inputs: &PortBuffers, port: &str, lane: usize, i: usize, default: f32) -> f32 {
+    let channels = inputs.channels(port);
+    let channel = if channels == 1 { 0 } else { lane };
+    inputs
+        .channel(port, channel)
+        .and_then(|samples| samples.get(i))
+        .copied()
+        .unwrap_or(default)
+}
+
+/// Mixes every channel of a poly cable down to mono
+pub struct GraphSum {
+    level: f32,
+    // Where the channels are summed
+    mix: Vec<f32>,
+}
+
+impl GraphSum {
+    pub fn new(level: f32) -> Self {
+        Self { level, mix: Vec::new() }
+    }
+}
+
+impl Default for GraphSum {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl GraphModule for GraphSum {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "in".to_string(),
+            default_value: 0.0,
+            description: "Poly input".to_string(),
+        }]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        vec![PortDescriptor {
+            name: "out".to_string(),
+            default_value: 0.0,
+            description: "Sum of all channels".to_string(),
+        }]
+    }
+
+    fn is_polyphonic(&self) -> bool {
+        true
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let sum = mixdown(inputs, "in", sample_count, &mut self.mix);
+        let output = outputs.get_mut("out").unwrap();
+
+        for i in 0..sample_count {
+            let sample = if i < sum.len() { sum[i] } else { 0.0 };
+            output[i] = sample * self.level;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "level" | "gain" => {
+                self.level = value;
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "level" | "gain" => Some(self.level),
+            _ => None,
+        }
+    }
+}
+
+/// Frequency in Hz of a MIDI note number
+fn note_to_hz(note: u8) -> f32 {
+    440.0 * 2.0_f32.powf((f32::from(note) - 69.0) / 12.0)
+}
+
+/// Playback state of one part of a MIDI file
+struct MidiPart {
+    allocator: VoiceAllocator,
+    cursor: usize,
+    controllers: Vec<(u8, f32)>,
+    // Output ports, the first part also writes to the unnumbered ones
+    ports: Vec<(String, MidiPort)>,
+}
+
+#[derive(Clone, Copy)]
+enum MidiPort {
+    Pitch,
+    Gate,
+    Velocity,
+    Control(usize),
+}
+
+/// Standard MIDI File player - pitch (Hz), gate, velocity and controller
+/// outputs for every part of a song
+///
+/// Plays at the file's own tempo, or follows an incoming clock (one pulse
+/// per quarter note unless `ppq` says otherwise) when synced. Parts are
+/// numbered from 1 in file order: `pitch2`, `gate2`, `velocity2`, `cc74_2`.
+/// The unnumbered outputs follow the first part. With more than one voice
+/// the note outputs are poly cables.
+pub struct GraphMidiFile {
+    song: MidiFile,
+    parts: Vec<MidiPart>,
+    voices: usize,
+    rate: f32,
+    looping: bool,
+    sync: bool,
+    ppq: f32,
+    sample_rate: f32,
+    position: f64,
+    playing: bool,
+    clock_target: f64,
+    clock_speed: f64,
+    samples_since_clock: f64,
+    clock_started: bool,
+    last_clock: f32,
+    last_reset: f32,
+}
+
+impl GraphMidiFile {
+    pub fn new(song: MidiFile, voices: usize) -> Self {
+        let voices = voices.clamp(1, MAX_CHANNELS);
+        let parts = song
+            .tracks
+            .iter()
+            .enumerate()
+            .map(|(index, track)| {
+                let number = index + 1;
+                let mut ports = Vec::new();
+                let mut add = |name: &str, port: MidiPort| {
+                    if index == 0 {
+                        ports.push((name.to_string(), port));
+                    }
+                    let numbered = match name.strip_prefix("cc") {
+                        Some(_) => format!("{name}_{number}"),
+                        None => format!("{name}{number}"),
+                    };
+                    ports.push((numbered, port));
+                };
+                add("pitch", MidiPort::Pitch);
+                add("gate", MidiPort::Gate);
+                add("velocity", MidiPort::Velocity);
+                for (slot, controller) in track.controllers.iter().enumerate() {
+                    add(&format!("cc{controller}"), MidiPort::Control(slot));
+                }
+                MidiPart {
+                    allocator: VoiceAllocator::new(voices, VoicePolicy::RoundRobin),
+                    cursor: 0,
+                    controllers: track.controllers.iter().map(|&cc| (cc, 0.0)).collect(),
+                    ports,
+                }
+            })
+            .collect();
+
+        Self {
+            song,
+            parts,
+            voices,
+            rate: 1.0,
+            looping: true,
+            sync: false,
+            ppq: 1.0,
+            sample_rate: 44100.0,
+            position: 0.0,
+            playing: true,
+            clock_target: 0.0,
+            clock_speed: 0.0,
+            samples_since_clock: 0.0,
+            clock_started: false,
+            last_clock: 0.0,
+            last_reset: 0.0,
+        }
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Back to the start with every note released
+    fn restart(&mut self) {
+        self.position = 0.0;
+        self.playing = true;
+        self.clock_target = 0.0;
+        self.clock_speed = 0.0;
+        self.clock_started = false;
+        self.rewind();
+    }
+
+    fn rewind(&mut self) {
+        for part in &mut self.parts {
+            part.allocator.all_notes_off();
+            part.cursor = 0;
+        }
+    }
+
+    /// Play every event up to the current position
+    fn dispatch(&mut self) {
+        for (part, track) in self.parts.iter_mut().zip(&self.song.tracks) {
+            while let Some(event) = track.events.get(part.cursor) {
+                if event.tick as f64 > self.position {
+                    break;
+                }
+                match event.kind {
+                    EventKind::NoteOn { key, velocity } => {
+                        let velocity = f32::from(velocity) / 127.0;
+                        part.allocator.note_on(u32::from(key), note_to_hz(key), velocity);
+                    }
+                    EventKind::NoteOff { key } => part.allocator.note_off(u32::from(key)),
+                    EventKind::Control { controller, value } => {
+                        if let Some(slot) =
+                            part.controllers.iter_mut().find(|(cc, _)| *cc == controller)
+                        {
+                            slot.1 = f32::from(value) / 127.0;
+                        }
+                    }
+                }
+                part.cursor += 1;
+            }
+        }
+    }
+
+    /// Ticks to move this sample, following the clock when synced
+    fn advance(&mut self, clocked: bool) -> f64 {
+        let tempo = f64::from(self.song.tempo_at(self.position as u64));
+        let quarters_per_second = 1_000_000.0 / tempo;
+        let free =
+            f64::from(self.song.ticks_per_quarter) * quarters_per_second * f64::from(self.rate)
+                / f64::from(self.sample_rate);
+        if !self.sync {
+            return free;
+        }
+
+        let step = f64::from(self.song.ticks_per_quarter) / f64::from(self.ppq.max(0.001));
+        if clocked {
+            // Each pulse lands exactly on its beat, and the time since the
+            // last one sets the speed until the next
+            let jump = if self.clock_started { self.clock_target - self.position } else { 0.0 };
+            if self.clock_started && self.samples_since_clock > 0.0 {
+                self.clock_speed = step / self.samples_since_clock;
+            }
+            self.clock_target = self.position + jump + step;
+            self.clock_started = true;
+            self.samples_since_clock = 0.0;
+            return jump;
+        }
+
+        // Until two pulses have set a speed, assume the file's own tempo
+        self.samples_since_clock += 1.0;
+        if !self.clock_started {
+            return 0.0;
+        }
+        let speed = if self.clock_speed > 0.0 { self.clock_speed } else { free };
+        speed.min((self.clock_target - self.position).max(0.0))
+    }
+}
+
+impl Default for GraphMidiFile {
+    fn default() -> Self {
+        Self::new(MidiFile::default(), 1)
+    }
+}
+
+impl GraphModule for GraphMidiFile {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        vec![
+            PortDescriptor {
+                name: "clock".to_string(),
+                default_value: 0.0,
+                description: "Clock pulses to follow when synced".to_string(),
+            },
+            PortDescriptor {
+                name: "reset".to_string(),
+                default_value: 0.0,
+                description: "Back to the start on rising edge".to_string(),
+            },
+            PortDescriptor {
+                name: "loop".to_string(),
+                default_value: 0.0,
+                description: "Loop the song while high (in addition to the loop parameter)"
+                    .to_string(),
+            },
+        ]
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        let mut outputs = Vec::new();
+        if self.parts.is_empty() {
+            for name in ["pitch", "gate", "velocity"] {
+                outputs.push(PortDescriptor {
+                    name: name.to_string(),
+                    default_value: 0.0,
+                    description: "Note output (the song has no parts)".to_string(),
+                });
+            }
+        }
+        for part in &self.parts {
+            for (name, port) in &part.ports {
+                let description = match port {
+                    MidiPort::Pitch => "Note pitch in Hz",
+                    MidiPort::Gate => "High while the note is held",
+                    MidiPort::Velocity => "Note-on velocity (0 to 1)",
+                    MidiPort::Control(_) => "Controller value (0 to 1)",
+                };
+                outputs.push(PortDescriptor {
+                    name: name.clone(),
+                    default_value: 0.0,
+                    description: description.to_string(),
+                });
+            }
+        }
+        outputs.push(PortDescriptor {
+            name: "eoc".to_string(),
+            default_value: 0.0,
+            description: "Single-sample pulse at the end of the song".to_string(),
+        });
+        outputs
+    }
+
+    fn is_polyphonic(&self) -> bool {
+        true
+    }
+
+    fn output_channels(&self) -> usize {
+        self.voices
+    }
+
+    fn process(&mut self, inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let clock = inputs.get("clock").map(|b| b.as_slice()).unwrap_or(&[]);
+        let reset = inputs.get("reset").map(|b| b.as_slice()).unwrap_or(&[]);
+        let loop_in = inputs.get("loop").map(|b| b.as_slice()).unwrap_or(&[]);
+
+        let voices = self.voices;
+        for part in &self.parts {
+            for (name, port) in &part.ports {
+                let channels = if matches!(port, MidiPort::Control(_)) { 1 } else { voices };
+                outputs.set_channels(name, channels, sample_count);
+            }
+        }
+        if let Some(eoc) = outputs.get_mut("eoc") {
+            eoc[..sample_count].fill(0.0);
+        }
+
+        for i in 0..sample_count {
+            let clock_val = if i < clock.len() { clock[i] } else { 0.0 };
+            let reset_val = if i < reset.len() { reset[i] } else { 0.0 };
+            let loop_val = if i < loop_in.len() { loop_in[i] } else { 0.0 };
+
+            if reset_val > 0.5 && self.last_reset <= 0.5 {
+                self.restart();
+            }
+            self.last_reset = reset_val;
+            let clocked = clock_val > 0.5 && self.last_clock <= 0.5;
+            self.last_clock = clock_val;
+
+            if self.playing {
+                self.position += self.advance(clocked);
+                self.dispatch();
+
+                let length = self.song.length as f64;
+                if length > 0.0 && self.position >= length {
+                    self.rewind();
+                    if self.looping || loop_val > 0.5 {
+                        self.position -= length;
+                        self.clock_target -= length;
+                        self.dispatch();
+                    } else {
+                        self.playing = false;
+                    }
+                    if let Some(eoc) = outputs.get_mut("eoc") {
+                        eoc[i] = 1.0;
+                    }
+                }
+            }
+
+            for part in &mut self.parts {
+                let mut gates = [0.0; MAX_CHANNELS];
+                for (v, gate) in gates.iter_mut().enumerate().take(voices) {
+                    *gate = part.allocator.gate(v);
+                }
+                for (name, port) in &part.ports {
+                    let Some(buffer) = outputs.get_mut(name) else {
+                        continue;
+                    };
+                    match *port {
+                        MidiPort::Control(slot) => buffer[i] = part.controllers[slot].1,
+                        port => {
+                            for (v, voice) in part.allocator.voices().iter().enumerate() {
+                                buffer[v * sample_count + i] = match port {
+                                    MidiPort::Pitch => voice.pitch,
+                                    MidiPort::Gate => gates[v],
+                                    _ => voice.velocity,
+                                };
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "rate" => {
+                self.rate = value.max(0.0);
+                Ok(())
+            }
+            "loop" => {
+                self.looping = value > 0.5;
+                Ok(())
+            }
+            "sync" => {
+                self.sync = value > 0.5;
+                Ok(())
+            }
+            "ppq" => {
+                self.ppq = value.max(1.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "rate" => Some(self.rate),
+            "loop" => Some(if self.looping { 1.0 } else { 0.0 }),
+            "sync" => Some(if self.sync { 1.0 } else { 0.0 }),
+            "ppq" => Some(self.ppq),
+            "voices" => Some(self.voices as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Which held note a mono MIDI input plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotePriority {
+    /// The most recently pressed note
+    Last,
+    /// The lowest held note
+    Low,
+    /// The highest held note
+    High,
+}
+
+impl NotePriority {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "last" | "latest" => Some(Self::Last),
+            "low" | "lowest" => Some(Self::Low),
+            "high" | "highest" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    pub fn from_param(value: f32) -> Self {
+        match value as i32 {
+            1 => Self::Low,
+            2 => Self::High,
+            _ => Self::Last,
+        }
+    }
+
+    pub fn as_param(self) -> f32 {
+        match self {
+            Self::Last => 0.0,
+            Self::Low => 1.0,
+            Self::High => 2.0,
+        }
+    }
+
+    /// The note to sound out of the held ones, oldest first
+    fn select(self, held: &[(u8, f32)]) -> Option<(u8, f32)> {
+        match self {
+            Self::Last => held.last().copied(),
+            Self::Low => held.iter().copied().min_by_key(|&(key, _)| key),
+            Self::High => held.iter().copied().max_by_key(|&(key, _)| key),
+        }
+    }
+}
+
+/// Live MIDI input - note (Hz), gate, velocity, trigger, pitch bend,
+/// aftertouch and every controller (`cc0` to `cc127`) from a sequencer port
+///
+/// With one voice, held notes are picked by the note priority; with more,
+/// notes go through a round-robin voice allocator and the note outputs are
+/// poly cables. Messages are applied at the start of each block.
+pub struct GraphMidiIn {
+    receiver: Receiver<MidiMessage>,
+    _port: Option<MidiInputPort>,
+    channel: u8,
+    priority: NotePriority,
+    legato: bool,
+    bend_range: f32,
+    voices: usize,
+    allocator: VoiceAllocator,
+    held: Vec<(u8, f32)>,
+    key: u8,
+    velocity: f32,
+    gate_on: bool,
+    retrigger: bool,
+    triggered: bool,
+    bend: f32,
+    pressure: f32,
+    controllers: [f32; 128],
+    // Port name for each controller, made once so processing doesn't allocate
+    controller_ports: Vec<String>,
+}
+
+impl GraphMidiIn {
+    /// Listen on a new virtual sequencer port
+    ///
+    /// # Errors
+    /// Returns an error if the port cannot be opened
+    pub fn open(port_name: &str, voices: usize, priority: NotePriority) -> Result<Self> {
+        let (port, receiver) = MidiInputPort::open(port_name)?;
+        let mut midi = Self::from_receiver(receiver, voices, priority);
+        midi._port = Some(port);
+        Ok(midi)
+    }
+
+    /// Take messages from any source, such as a test or another transport
+    pub fn from_receiver(
+        receiver: Receiver<MidiMessage>,
+        voices: usize,
+        priority: NotePriority,
+    ) -> Self {
+        let voices = voices.clamp(1, MAX_CHANNELS);
+        Self {
+            receiver,
+            _port: None,
+            channel: 0,
+            priority,
+            legato: false,
+            bend_range: 2.0,
+            voices,
+            allocator: VoiceAllocator::new(voices, VoicePolicy::RoundRobin),
+            held: Vec::new(),
+            key: 60,
+            velocity: 0.0,
+            gate_on: false,
+            retrigger: false,
+            triggered: false,
+            bend: 0.0,
+            pressure: 0.0,
+            controllers: [0.0; 128],
+            controller_ports: (0..128).map(|controller| format!("cc{controller}")).collect(),
+        }
+    }
+
+    pub fn with_legato(mut self, legato: bool) -> Self {
+        self.legato = legato;
+        self
+    }
+
+    fn handle(&mut self, message: MidiMessage) {
+        if self.channel != 0 && message.channel() + 1 != self.channel {
+            return;
+        }
+        match message {
+            MidiMessage::NoteOn { key, velocity, .. } => {
+                let velocity = f32::from(velocity) / 127.0;
+                self.triggered = true;
+                if self.voices > 1 {
+                    self.allocator.note_on(u32::from(key), note_to_hz(key), velocity);
+                    return;
+                }
+                self.held.retain(|&(held, _)| held != key);
+                self.held.push((key, velocity));
+                if self.priority.select(&self.held).map(|(k, _)| k) == Some(key) {
+                    self.retrigger |= self.gate_on && !self.legato;
+                    self.key = key;
+                    self.velocity = velocity;
+                    self.gate_on = true;
+                }
+            }
+            MidiMessage::NoteOff { key, .. } => {
+                if self.voices > 1 {
+                    self.allocator.note_off(u32::from(key));
+                    return;
+                }
+                self.held.retain(|&(held, _)| held != key);
+                match self.priority.select(&self.held) {
+                    // Falling back to a note still held glides without retriggering
+                    Some((key, velocity)) => {
+                        self.key = key;
+                        self.velocity = velocity;
+                    }
+                    None => self.gate_on = false,
+                }
+            }
+            MidiMessage::Control { controller, value, .. } => {
+                self.controllers[usize::from(controller & 0x7F)] = f32::from(value) / 127.0;
+            }
+            MidiMessage::PitchBend { value, .. } => {
+                self.bend = f32::from(value) / 8192.0;
+            }
+            MidiMessage::ChannelPressure { value, .. } => {
+                self.pressure = f32::from(value) / 127.0;
+            }
+            MidiMessage::PolyPressure { key, value, .. } => {
+                if self.voices > 1 || key == self.key {
+                    self.pressure = f32::from(value) / 127.0;
+                }
+            }
+        }
+    }
+}
+
+impl Default for GraphMidiIn {
+    fn default() -> Self {
+        Self::from_receiver(mpsc::channel().1, 1, NotePriority::Last)
+    }
+}
+
+impl GraphModule for GraphMidiIn {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        let mut outputs = vec![
+            PortDescriptor {
+                name: "note".to_string(),
+                default_value: 0.0,
+                description: "Note pitch in Hz, including pitch bend".to_string(),
+            },
+            PortDescriptor {
+                name: "gate".to_string(),
+                default_value: 0.0,
+                description: "High while a note is held".to_string(),
+            },
+            PortDescriptor {
+                name: "velocity".to_string(),
+                default_value: 0.0,
+                description: "Note-on velocity (0 to 1)".to_string(),
+            },
+            PortDescriptor {
+                name: "trigger".to_string(),
+                default_value: 0.0,
+                description: "Single-sample pulse on every note-on".to_string(),
+            },
+            PortDescriptor {
+                name: "pitchbend".to_string(),
+                default_value: 0.0,
+                description: "Pitch bend wheel (-1 to 1)".to_string(),
+            },
+            PortDescriptor {
+                name: "aftertouch".to_string(),
+                default_value: 0.0,
+                description: "Channel or key pressure (0 to 1)".to_string(),
+            },
+        ];
+        for (controller, name) in self.controller_ports.iter().enumerate() {
+            outputs.push(PortDescriptor {
+                name: name.clone(),
+                default_value: 0.0,
+                description: format!("Controller {controller} (0 to 1)"),
+            });
+        }
+        outputs
+    }
+
+    fn is_polyphonic(&self) -> bool {
+        true
+    }
+
+    fn output_channels(&self) -> usize {
+        self.voices
+    }
+
+    fn process(&mut self, _inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        while let Ok(message) = self.receiver.try_recv() {
+            self.handle(message);
+        }
+
+        let voices = self.voices;
+        for port in ["note", "gate", "velocity"] {
+            outputs.set_channels(port, voices, sample_count);
+        }
+        let bend_ratio = 2.0_f32.powf(self.bend * self.bend_range / 12.0);
+
+        let [note_out, gate_out, velocity_out, trigger_out] =
+            outputs.get_many_mut(["note", "gate", "velocity", "trigger"]);
+        let note_out = note_out.unwrap();
+        let gate_out = gate_out.unwrap();
+        let velocity_out = velocity_out.unwrap();
+        let trigger_out = trigger_out.unwrap();
+
+        for i in 0..sample_count {
+            trigger_out[i] = if i == 0 && self.triggered { 1.0 } else { 0.0 };
+            if voices > 1 {
+                for v in 0..voices {
+                    let index = v * sample_count + i;
+                    gate_out[index] = self.allocator.gate(v);
+                    note_out[index] = self.allocator.voices()[v].pitch * bend_ratio;
+                    velocity_out[index] = self.allocator.voices()[v].velocity;
+                }
+            } else {
+                // A new note over a held one drops the gate for one sample
+                let dip = i == 0 && self.retrigger;
+                gate_out[i] = if self.gate_on && !dip { 1.0 } else { 0.0 };
+                note_out[i] = note_to_hz(self.key) * bend_ratio;
+                velocity_out[i] = self.velocity;
+            }
+        }
+        self.triggered = false;
+        self.retrigger = false;
+
+        for (port, value) in [("pitchbend", self.bend), ("aftertouch", self.pressure)] {
+            if let Some(buffer) = outputs.get_mut(port) {
+                buffer[..sample_count].fill(value);
+            }
+        }
+        for (port, value) in self.controller_ports.iter().zip(&self.controllers) {
+            if let Some(buffer) = outputs.get_mut(port) {
+                buffer[..sample_count].fill(*value);
+            }
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "channel" => {
+                self.channel = value.clamp(0.0, 16.0) as u8;
+                Ok(())
+            }
+            "priority" => {
+                self.priority = NotePriority::from_param(value);
+                Ok(())
+            }
+            "legato" => {
+                self.legato = value > 0.5;
+                Ok(())
+            }
+            "bend" => {
+                self.bend_range = value.clamp(0.0, 48.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "channel" => Some(f32::from(self.channel)),
+            "priority" => Some(self.priority.as_param()),
+            "legato" => Some(if self.legato { 1.0 } else { 0.0 }),
+            "bend" => Some(self.bend_range),
+            "voices" => Some(self.voices as f32),
+            _ => None,
+        }
+    }
+}
+
+/// Values from OSC addresses - `outN` follows the Nth address smoothly,
+/// `rawN` jumps straight to each new value and `triggerN` pulses for one
+/// sample whenever a message arrives, even one repeating the last value
+///
+/// The unnumbered `out`, `raw` and `trigger` follow the first address.
+/// Values come from the engine's OSC server; until a module is attached to
+/// one, or before the first message, the outputs sit at zero.
+pub struct GraphOscIn {
+    values: OscValues,
+    addresses: Vec<String>,
+    targets: Vec<f32>,
+    current: Vec<f32>,
+    counts: Vec<u64>,
+    smooth: f32,
+    sample_rate: f32,
+    // out, raw and trigger port names for each address, the first also
+    // unnumbered, made once so processing doesn't allocate
+    ports: Vec<Vec<[String; 3]>>,
+}
+
+impl GraphOscIn {
+    pub fn new(addresses: Vec<String>, smooth: f32) -> Self {
+        let count = addresses.len();
+        let ports = (0..count)
+            .map(|index| {
+                let number = (index + 1).to_string();
+                let suffixes = if index == 0 { vec![String::new(), number] } else { vec![number] };
+                suffixes
+                    .iter()
+                    .map(|suffix| {
+                        [format!("out{suffix}"), format!("raw{suffix}"), format!("trigger{suffix}")]
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            values: OscValues::new(),
+            ports,
+            addresses,
+            targets: vec![0.0; count],
+            current: vec![0.0; count],
+            counts: vec![0; count],
+            smooth: smooth.max(0.0),
+            sample_rate: 44100.0,
+        }
+    }
+
+    /// Read values from a shared store, normally the engine's
+    pub fn set_values(&mut self, values: OscValues) {
+        self.values = values;
+    }
+}
+
+impl Default for GraphOscIn {
+    fn default() -> Self {
+        Self::new(vec!["/1/fader1".to_string()], 0.02)
+    }
+}
+
+impl GraphModule for GraphOscIn {
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<PortDescriptor> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<PortDescriptor> {
+        let mut outputs = Vec::new();
+        for (address, ports) in self.addresses.iter().zip(&self.ports) {
+            for [out, raw, trigger] in ports {
+                outputs.push(PortDescriptor {
+                    name: out.clone(),
+                    default_value: 0.0,
+                    description: format!("{address}, smoothed"),
+                });
+                outputs.push(PortDescriptor {
+                    name: raw.clone(),
+                    default_value: 0.0,
+                    description: format!("{address}, unsmoothed"),
+                });
+                outputs.push(PortDescriptor {
+                    name: trigger.clone(),
+                    default_value: 0.0,
+                    description: format!("Single-sample pulse on each {address} message"),
+                });
+            }
+        }
+        outputs
+    }
+
+    fn process(&mut self, _inputs: &PortBuffers, outputs: &mut PortBuffers, sample_count: usize) {
+        let coeff = if self.smooth > 0.0 {
+            (-1.0 / (self.smooth * self.sample_rate)).exp()
+        } else {
+            0.0
+        };
+
+        for index in 0..self.addresses.len() {
+            let mut triggered = false;
+            // Never wait for the network thread; while it writes, keep the last value
+            if let Some((value, count)) = self.values.get(&self.addresses[index]) {
+                if count != self.counts[index] {
+                    self.counts[index] = count;
+                    self.targets[index] = value;
+                    triggered = true;
+                }
+            }
+
+            // The first address also fills the unnumbered ports
+            let target = self.targets[index];
+            let start = self.current[index];
+            let mut current = start;
+            for [out_port, raw_port, trigger_port] in &self.ports[index] {
+                if let Some(out) = outputs.get_mut(out_port) {
+                    current = start;
+                    for sample in out.iter_mut().take(sample_count) {
+                        current = target + (current - target) * coeff;
+                        *sample = current;
+                    }
+                }
+                if let Some(raw) = outputs.get_mut(raw_port) {
+                    raw[..sample_count].fill(target);
+                }
+                if let Some(trigger) = outputs.get_mut(trigger_port) {
+                    trigger[..sample_count].fill(0.0);
+                    if triggered && sample_count > 0 {
+                        trigger[0] = 1.0;
+                    }
+                }
+            }
+            self.current[index] = current;
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: f32) -> Result<()> {
+        match name {
+            "smooth" => {
+                self.smooth = value.max(0.0);
+                Ok(())
+            }
+            _ => Err(anyhow!("Unknown parameter: {name}")),
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f32> {
+        match name {
+            "smooth" => Some(self.smooth),
+            _ => None,
+        }
+    }
+}
@@ -2,12 +2,31 @@
 
 #![allow(clippy::multiple_crate_versions)]
 
+pub mod cv_utility_tests;
+pub mod dynamics_tests;
+pub mod effects_tests;
+pub mod fm_tests;
 pub mod graph;
 pub mod graph_engine;
 pub mod graph_modules;
+pub mod logic_tests;
+pub mod midi_file;
+pub mod midi_input;
+pub mod midi_tests;
+pub mod mixer_tests;
 pub mod modules;
 pub mod observability;
+pub mod osc;
+pub mod osc_tests;
+pub mod output_stage;
 pub mod parser;
+pub mod physical_tests;
+pub mod poly_tests;
+pub mod random_tests;
+pub mod reload_tests;
+pub mod rhythm_tests;
+pub mod save_tests;
 pub mod slew_tests;
 pub mod test_framework;
 pub mod user_modules;
+pub mod wavetable;
@@ -0,0 +1,74 @@
+//! Tests for comparator, gate logic and edge detector modules
+
+#[cfg(test)]
+mod tests {
+    use crate::graph_modules::{GraphComparator, GraphEdge, GraphLogic};
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    #[test]
+    fn test_comparator_hysteresis() {
+        let mut comp = GraphComparator::new(0.5, 0.2);
+        let signal = vec![0.0, 0.55, 0.65, 0.5, 0.45, 0.35, 0.5];
+        let outputs = process_module(&mut comp, &[("in", signal)], 7);
+
+        // Rises above 0.6, stays high until it drops below 0.4
+        assert_eq!(outputs.get("gate").unwrap(), &vec![0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(outputs.get("inv").unwrap(), &vec![1.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_logic_truth_table() {
+        let mut logic = GraphLogic::new(2);
+        let outputs = process_module(
+            &mut logic,
+            &[("in1", vec![0.0, 1.0, 0.0, 1.0]), ("in2", vec![0.0, 0.0, 1.0, 1.0])],
+            4,
+        );
+
+        assert_eq!(outputs.get("and").unwrap(), &vec![0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(outputs.get("or").unwrap(), &vec![0.0, 1.0, 1.0, 1.0]);
+        assert_eq!(outputs.get("xor").unwrap(), &vec![0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(outputs.get("nand").unwrap(), &vec![1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(outputs.get("not").unwrap(), &vec![1.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_edge_triggers() {
+        let mut edge = GraphEdge::new(0.0); // Single-sample triggers
+        let signal = vec![0.0, 1.0, 1.0, 1.0, 0.0, 0.0];
+        let outputs = process_module(&mut edge, &[("in", signal)], 6);
+
+        assert_eq!(outputs.get("rise").unwrap(), &vec![0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(outputs.get("fall").unwrap(), &vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(outputs.get("both").unwrap(), &vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_comparator_gates_envelope() {
+        let mut runner = TestRunner::new();
+
+        // Generative patch: noise crossing a threshold fires the envelope
+        let patch = r"
+            noise: noise
+            clock: lfo 8.0
+            sh: samplehold
+            sh.signal <- noise.white
+            sh.gate <- clock.gate
+            comp: comparator 0.3
+            comp.in <- sh.out
+            env: envelope 0.001 0.05
+            env.gate <- comp.gate
+            out <- env.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Comparator patch should load and run");
+
+        result.assert_gate_fired("comp", "gate").expect("Comparator gate should fire");
+        result
+            .assert_signal_range("comp", "gate", 0.0, 1.0)
+            .expect("Comparator gate should be 0 or 1");
+    }
+}
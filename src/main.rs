@@ -4,35 +4,64 @@
 
 #![allow(clippy::multiple_crate_versions)] // Dependencies have conflicting sub-dependencies
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rustyline::error::ReadlineError;
 use rustyline::{Config, EditMode, Editor};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
 
 mod graph;
 mod graph_engine;
 mod graph_modules;
+mod midi_file;
+mod midi_input;
 mod modules;
 mod observability;
+mod osc;
+mod output_stage;
 mod parser;
 mod test_framework;
 mod user_modules;
+mod wavetable;
 
 use graph_engine::GraphEngine;
 
+/// How often `--watch` checks the patch file for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
-    match args.get(1).map(String::as_str) {
+    // Options can go anywhere; the remaining argument is the patch file
+    let mut osc_port = None;
+    let mut watch = false;
+    let mut positional = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--osc" => {
+                let addr = iter.next().ok_or_else(|| anyhow!("--osc needs a port or host:port"))?;
+                if !addr.contains(':') && addr.parse::<u16>().is_err() {
+                    return Err(anyhow!("Invalid OSC port: {addr}"));
+                }
+                osc_port = Some(addr.clone());
+            }
+            "--watch" | "-w" => watch = true,
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    match positional.first().copied() {
         Some("help" | "-h" | "--help") => {
             print_help();
         }
         Some(file_path) => {
             // First argument is a file path
-            play_patch(file_path)?;
+            play_patch(file_path, osc_port, watch)?;
         }
         None => {
             // No arguments - go to REPL
-            run_repl()?;
+            run_repl(osc_port)?;
         }
     }
 
@@ -40,25 +69,29 @@ fn main() -> Result<()> {
 }
 
 #[allow(clippy::too_many_lines)]
-fn play_patch(patch_file: &str) -> Result<()> {
+fn play_patch(patch_file: &str, osc_port: Option<String>, watch: bool) -> Result<()> {
     println!("Loading patch: {patch_file}");
 
     let mut engine = GraphEngine::new_with_patch_context(Some(patch_file));
+    if let Some(port) = osc_port {
+        osc_command(&mut engine, &port);
+    }
     let patch_content = std::fs::read_to_string(patch_file)?;
 
     // Check if the patch contains a "start" command
     let has_start_command = patch_content.lines().any(|line| line.trim() == "start");
 
     // Filter out "start" command from patch content since it's a control command, not DSL
-    let filtered_patch_content: String = patch_content
-        .lines()
-        .filter(|line| line.trim() != "start")
-        .collect::<Vec<_>>()
-        .join("\n");
+    let filtered_patch_content = without_start(&patch_content);
 
     engine.load_patch(&filtered_patch_content)?;
 
-    if has_start_command {
+    if watch {
+        // Live coding: play straight away and follow edits to the file
+        engine.start()?;
+        watch_patch(&mut engine, patch_file, patch_content)?;
+        engine.stop();
+    } else if has_start_command {
         // Auto-play mode for scripts with explicit "start"
         engine.start()?;
         println!("Playing... Press Enter to stop");
@@ -128,14 +161,34 @@ fn play_patch(patch_file: &str) -> Result<()> {
                             println!("Goodbye!");
                             break;
                         }
+                        "panic" => {
+                            engine.panic();
+                            println!("Output muted - type 'unmute' to resume");
+                        }
+                        "unmute" => {
+                            engine.unmute();
+                            println!("Output unmuted");
+                        }
                         "help" => {
                             println!("Available commands:");
                             println!("  start - Start audio playback");
                             println!("  stop  - Stop audio playback");
+                            println!("  panic - Silence output immediately");
+                            println!("  unmute - Resume output after panic or a NaN fault");
                             println!("  inspect <name> - Inspect module ports");
+                            println!(
+                                "  osc <port>|<host:port>|off - Listen for OSC control messages"
+                            );
+                            println!("  save <file.zim> - Save the patch as it is now");
                             println!("  quit  - Exit program");
                         }
                         "" => {} // Empty input, continue
+                        _ if command == "osc" || command.starts_with("osc ") => {
+                            osc_command(&mut engine, command["osc".len()..].trim());
+                        }
+                        _ if command == "save" || command.starts_with("save ") => {
+                            save_command(&engine, command["save".len()..].trim());
+                        }
                         _ if command.starts_with("inspect ") => {
                             let module_name = command.strip_prefix("inspect ").unwrap().trim();
                             if let Some(info) = engine.inspect_module(module_name) {
@@ -201,7 +254,7 @@ fn play_patch(patch_file: &str) -> Result<()> {
 }
 
 #[allow(clippy::too_many_lines)]
-fn run_repl() -> Result<()> {
+fn run_repl(osc_port: Option<String>) -> Result<()> {
     println!("Zim-DSP REPL - Type 'help' for commands, 'quit' to exit");
     println!("Vi mode enabled: ESC for normal mode, 'i' for insert mode");
 
@@ -225,6 +278,9 @@ fn run_repl() -> Result<()> {
     }
 
     let mut engine = GraphEngine::new();
+    if let Some(port) = osc_port {
+        osc_command(&mut engine, &port);
+    }
 
     loop {
         let readline = rl.readline("> ");
@@ -251,6 +307,31 @@ fn run_repl() -> Result<()> {
                         engine.stop();
                         println!("Audio stopped");
                     }
+                    "panic" => {
+                        engine.panic();
+                        println!("Output muted - type 'unmute' to resume");
+                    }
+                    "unmute" => {
+                        engine.unmute();
+                        println!("Output unmuted");
+                    }
+                    "limiter on" => {
+                        engine.set_limiter_enabled(true);
+                        println!("Output limiter enabled");
+                    }
+                    "limiter off" => {
+                        engine.set_limiter_enabled(false);
+                        println!("Output limiter bypassed");
+                    }
+                    "status" => {
+                        match (engine.is_output_muted(), engine.output_fault()) {
+                            (true, Some(fault)) => println!("Output muted: {fault}"),
+                            (true, None) => println!("Output muted by panic"),
+                            (false, _) => println!("Output live"),
+                        }
+                        let limiter = if engine.limiter_enabled() { "on" } else { "off" };
+                        println!("Limiter {limiter}");
+                    }
                     "gate" | "g" => {
                         // Activate manual gate modules
                         if engine.activate_manual_gates() > 0 {
@@ -293,6 +374,12 @@ fn run_repl() -> Result<()> {
                             }
                         }
                     }
+                    _ if input == "osc" || input.starts_with("osc ") => {
+                        osc_command(&mut engine, input["osc".len()..].trim());
+                    }
+                    _ if input == "save" || input.starts_with("save ") => {
+                        save_command(&engine, input["save".len()..].trim());
+                    }
                     "validate" => {
                         let errors = engine.validate_connections();
                         if errors.is_empty() {
@@ -393,12 +480,103 @@ fn run_repl() -> Result<()> {
     Ok(())
 }
 
+/// Patch text without the `start` control command, which isn't DSL
+fn without_start(patch_content: &str) -> String {
+    patch_content
+        .lines()
+        .filter(|line| line.trim() != "start")
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reload the patch whenever its file changes, until Enter is pressed
+///
+/// Modules that survive an edit keep running with their state; a patch
+/// that fails to load leaves the previous one playing.
+fn watch_patch(engine: &mut GraphEngine, patch_file: &str, mut last: String) -> Result<()> {
+    println!("Watching {patch_file} for changes... Press Enter to stop");
+
+    let (stop_sender, stop) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut input = String::new();
+        let _ = std::io::stdin().read_line(&mut input);
+        let _ = stop_sender.send(());
+    });
+
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(WATCH_INTERVAL) {
+        // The audio thread leaves a faded-out old graph for us to free
+        engine.release_crossfade();
+
+        // Editors may briefly remove the file while saving; try again next time
+        let Ok(content) = std::fs::read_to_string(patch_file) else {
+            continue;
+        };
+        if content == last {
+            continue;
+        }
+        match engine.reload_patch(&without_start(&content)) {
+            Ok(summary) => println!("Reloaded {patch_file}: {summary}"),
+            Err(e) => eprintln!("Reload failed, previous patch still playing: {e}"),
+        }
+        last = content;
+    }
+    Ok(())
+}
+
+/// Handle `osc <port>`, `osc <host:port>`, `osc off` and a bare `osc` status query
+///
+/// A bare port listens on this machine only; a host such as `0.0.0.0`
+/// opens it to the network.
+fn osc_command(engine: &mut GraphEngine, argument: &str) {
+    match argument {
+        "" => match engine.osc_addr() {
+            Some(addr) => println!("OSC listening on {addr}"),
+            None => println!("OSC off"),
+        },
+        "off" => {
+            engine.stop_osc();
+            println!("OSC off");
+        }
+        addr => {
+            let started = if addr.contains(':') {
+                engine.start_osc_on(addr)
+            } else if let Ok(port) = addr.parse::<u16>() {
+                engine.start_osc(port)
+            } else {
+                eprintln!("Usage: osc <port> | osc <host:port> | osc off");
+                return;
+            };
+            match started {
+                Ok(addr) => println!("OSC listening on {addr} (/zim/<module>/<param> value)"),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        }
+    }
+}
+
+/// Handle `save <path>`, writing the live graph as a patch file
+fn save_command(engine: &GraphEngine, path: &str) {
+    if path.is_empty() {
+        eprintln!("Usage: save <file.zim>");
+        return;
+    }
+    match engine.save_patch(path) {
+        Ok(()) => println!("Saved patch to {path}"),
+        Err(e) => eprintln!("Error: {e}"),
+    }
+}
+
 fn print_help() {
     println!(
         "Zim-DSP - Text-based modular synthesizer
 
 Usage:
     zim-dsp <patch_file>    Load and play a patch file 
+    zim-dsp <patch_file> --osc 9000
+                            ...and take OSC control messages on local UDP port 9000
+                            (--osc 0.0.0.0:9000 takes them from the network)
+    zim-dsp <patch_file> --watch
+                            Play and reload the patch each time it is saved
     zim-dsp                 Start interactive mode
     zim-dsp help            Show this help
 
@@ -406,6 +584,7 @@ Behavior:
     • Files with 'start' command auto-play
     • Files without 'start' enter interactive mode  
     • Interactive mode: type 'start', 'stop', 'quit'
+    • --watch keeps unchanged modules running across edits (phase, steps)
 
 Examples:
     zim-dsp examples/simple_test.zim     # Auto-plays
@@ -421,6 +600,10 @@ fn print_repl_help() {
     help      - Show this help
     start     - Start audio processing
     stop      - Stop audio processing
+    panic     - Silence output immediately (patch keeps running)
+    unmute    - Resume output after panic or a NaN/Inf fault
+    limiter on/off - Enable or bypass the master brickwall limiter
+    status    - Show output mute state, fault source and limiter state
     gate/g    - Turn on manual gates
     release/r - Turn off manual gates
     clear     - Clear current patch
@@ -429,10 +612,16 @@ fn print_repl_help() {
     inspect <name> - Inspect module ports (e.g., 'inspect osc1' or 'inspect simple_gain')
     expand <patch> - Expand user modules in patch for debugging
     validate  - Validate all connections
+    save <file.zim> - Save the patch as it is now, with current parameter values
+    osc <port> - Listen for local OSC on a UDP port ('osc 0.0.0.0:<port>' for the
+                network, 'osc off' to stop, 'osc' for status)
+                /zim/<module>/<param> value sets a parameter, /zim/gate 1|0 the manual gates
     quit      - Exit REPL
     
 Patch Syntax:
     vco: osc sine 440           - Create oscillator
+    wt: wavetable basic 220     - Create wavetable oscillator (built-in or .wav)
+    op: fm 220 2.0 1.5          - Create FM operator (freq, ratio, index)
     vcf: filter moog            - Create filter
     env: envelope 0.01 0.1      - Create envelope
     vca: vca 1.0                - Create VCA
@@ -441,9 +630,44 @@ Patch Syntax:
     noise: noise                - Create noise generator
     mix: mixer                  - Create 4-input mono mixer
     mix: mixer 3                - Create 3-input mono mixer
+    mtx: matrix 4 2             - Create 4-in/2-out matrix mixer (mtx.in1_out2 levels)
+    xf: xfade 0.5 equal         - Create crossfader (position, linear/equal/compromise)
+    pn: pan 0 linear            - Create panner (position, linear/equal/compromise)
+    fol: follower 0.01 0.1      - Create envelope follower (attack, release)
+    dk: duck 0.8 0.5            - Create sidechain ducker (depth, full-duck level)
+    rm: ringmod 1.0             - Create ring modulator (dry/ring mix)
+    fs: freqshift 10            - Create frequency shifter (shift Hz, up/down outputs)
+    ch: chorus 0.8              - Create stereo chorus (rate; depth/feedback/mix params)
+    fl: flanger 0.2             - Create stereo flanger (rate; depth/feedback/mix params)
+    ph: phaser 0.4              - Create stereo phaser (rate; depth/feedback/mix params)
+    voc: vocoder 16             - Create vocoder (bands 8-32; carrier/modulator inputs)
+    str: string 110             - Create plucked string (freq; trig/pitch/exciter inputs)
+    res: resonator 220 bar      - Create modal resonator (freq, harmonic/bar/membrane)
+    rnd: random 2 42            - Create random source (rate, seed; stepped/smooth/slewed)
+    att: chaos 1 7 lorenz       - Create attractor (rate, seed, lorenz/rossler; x/y/z outputs)
     slew: slew_gen 0.1 0.1      - Create slew generator (rise/fall times)
     slew: slew_gen 0.2          - Create slew generator (same rise/fall)
+    fg: slew 0.1 0.3 cycle exp  - Create cycling function generator (trig/cycle/curve inputs)
     seq: seq8                   - Create 8-step sequencer
+    comp: comparator 0.5 0.1    - Create comparator (threshold, hysteresis)
+    lg: logic 3                 - Create gate logic (and/or/xor/nand/not)
+    trig: edge                  - Create edge detector (rise/fall/both)
+    atten: attenuverter -1 1    - Create attenuverter (min/max gain)
+    rect: rectifier             - Create rectifier (half/full)
+    inv: inverter               - Create inverter
+    sd: sumdiff                 - Create sum/difference (a+b, a-b)
+    c: constant 0.5             - Create constant voltage source
+    clk: clock 120              - Create master clock (bpm, swing, tap, divisions)
+    eu: euclid 16 5 0           - Create Euclidean rhythm (steps, fills, rotate)
+    tm: turing 8 1234           - Create Turing machine shift register (length, seed)
+    coin: bernoulli 0.5 latch   - Create Bernoulli gate (prob, trigger/latch/toggle)
+    alloc: poly 4 round_robin   - Create voice allocator (voices, round_robin/steal/none)
+    mixdown: sum 0.5            - Create poly-to-mono sum (level)
+    song: midifile a.mid 4      - Play a MIDI file (voices; loop/once/clock; pitchN/gateN/ccX_N)
+    kb: midi 1 low              - Live MIDI input port (voices; last/low/high, legato; ccN outputs)
+    ctl: osc_in /1/fader1 0.05  - OSC address values (addresses, smoothing; outN/rawN/triggerN)
+    viz: osc_out 127.0.0.1:9001 vco.freq env.gate=/beat 30
+                                - Send signals (rate Hz) and gate edges over OSC
     
 Connections:
     vcf.audio <- vco.sine       - Simple connection
@@ -453,6 +677,7 @@ Connections:
     vcf.cutoff <- lfo.sine * 2000 + 1000  - Scaled/offset
     out <- vca.out              - Mono to stereo output
     out.left <- vca1.out        - Left channel only
-    out.right <- vca2.out       - Right channel only"
+    out.right <- vca2.out       - Right channel only
+    vco.freq <- alloc.pitch     - Poly cable: one vco per voice"
     );
 }
@@ -0,0 +1,290 @@
+//! Standard MIDI File loading for the MIDI file player
+//!
+//! Files are parsed into note and controller events per part, with times
+//! kept in ticks so playback can follow either the file's tempo map or an
+//! external clock. Tracks without notes or controllers (tempo maps, lyrics)
+//! are dropped, and format 0 files are split by MIDI channel so each part
+//! still gets its own outputs.
+
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/// Tempo used until the file sets one: 120 BPM
+pub const DEFAULT_TEMPO: u32 = 500_000;
+
+/// What happens at one point in a part
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    NoteOn { key: u8, velocity: u8 },
+    NoteOff { key: u8 },
+    Control { controller: u8, value: u8 },
+}
+
+/// An event and the tick it happens on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiEvent {
+    pub tick: u64,
+    pub kind: EventKind,
+}
+
+/// The events of one part, in playing order
+#[derive(Debug, Clone, Default)]
+pub struct MidiTrack {
+    pub events: Vec<MidiEvent>,
+    /// Controller numbers the part uses, in ascending order
+    pub controllers: Vec<u8>,
+}
+
+/// A parsed Standard MIDI File
+#[derive(Debug, Clone)]
+pub struct MidiFile {
+    pub ticks_per_quarter: u32,
+    pub tracks: Vec<MidiTrack>,
+    /// Length in ticks, up to the last end of track
+    pub length: u64,
+    // (tick, microseconds per quarter note), sorted by tick
+    tempo_map: Vec<(u64, u32)>,
+}
+
+impl MidiFile {
+    /// Load a MIDI file from disk
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not a valid SMF
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow!("Failed to read MIDI file {}: {e}", path.display()))?;
+        Self::parse(&bytes).map_err(|e| anyhow!("{}: {e}", path.display()))
+    }
+
+    /// Parse the bytes of a Standard MIDI File (format 0 or 1)
+    ///
+    /// # Errors
+    /// Returns an error if the header or a track is malformed
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != b"MThd" {
+            return Err(anyhow!("Not a MIDI file"));
+        }
+        let header_len = reader.u32()? as usize;
+        let header = reader.take(header_len)?;
+        if header.len() < 6 {
+            return Err(anyhow!("MIDI header is too short"));
+        }
+        let format = u16::from_be_bytes([header[0], header[1]]);
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        if format > 1 {
+            return Err(anyhow!("Unsupported MIDI file format {format}"));
+        }
+
+        // SMPTE timing counts ticks in real time, which is the same as a
+        // fixed 120 BPM with the matching ticks per quarter note
+        let (ticks_per_quarter, smpte) = if division & 0x8000 == 0 {
+            (u32::from(division).max(1), false)
+        } else {
+            let fps = u32::from((-((division >> 8) as i8)) as u8);
+            let per_frame = u32::from(division & 0xFF);
+            ((fps * per_frame / 2).max(1), true)
+        };
+
+        let mut parts: Vec<(Option<u8>, MidiTrack)> = Vec::new();
+        let mut tempo_map = Vec::new();
+        let mut length = 0;
+
+        while reader.remaining() >= 8 {
+            let id = reader.take(4)?;
+            let size = reader.u32()? as usize;
+            let body = reader.take(size.min(reader.remaining()))?;
+            if id != b"MTrk" {
+                continue;
+            }
+
+            let track = parse_track(body)?;
+            length = length.max(track.end);
+            if !smpte {
+                tempo_map.extend(track.tempos);
+            }
+
+            // Format 0 keeps every channel in one track; give each its own part
+            let split = format == 0;
+            let first_part = parts.len();
+            for (channel, event) in track.events {
+                let key = split.then_some(channel);
+                let index = match parts[first_part..].iter().position(|(k, _)| *k == key) {
+                    Some(index) => first_part + index,
+                    None => {
+                        parts.push((key, MidiTrack::default()));
+                        parts.len() - 1
+                    }
+                };
+                let part = &mut parts[index].1;
+                if let EventKind::Control { controller, .. } = event.kind {
+                    if let Err(at) = part.controllers.binary_search(&controller) {
+                        part.controllers.insert(at, controller);
+                    }
+                }
+                part.events.push(event);
+            }
+        }
+
+        tempo_map.sort_by_key(|&(tick, _)| tick);
+        Ok(Self {
+            ticks_per_quarter,
+            tracks: parts.into_iter().map(|(_, track)| track).collect(),
+            length,
+            tempo_map,
+        })
+    }
+
+    /// Microseconds per quarter note at a tick
+    #[must_use]
+    pub fn tempo_at(&self, tick: u64) -> u32 {
+        let index = self.tempo_map.partition_point(|&(at, _)| at <= tick);
+        if index == 0 {
+            DEFAULT_TEMPO
+        } else {
+            self.tempo_map[index - 1].1
+        }
+    }
+}
+
+impl Default for MidiFile {
+    /// An empty song with no parts
+    fn default() -> Self {
+        Self {
+            ticks_per_quarter: 480,
+            tracks: Vec::new(),
+            length: 0,
+            tempo_map: Vec::new(),
+        }
+    }
+}
+
+/// Events of one MTrk chunk, tagged with their channel
+struct ParsedTrack {
+    events: Vec<(u8, MidiEvent)>,
+    tempos: Vec<(u64, u32)>,
+    end: u64,
+}
+
+fn parse_track(body: &[u8]) -> Result<ParsedTrack> {
+    let mut reader = Reader { bytes: body, pos: 0 };
+    let mut track = ParsedTrack {
+        events: Vec::new(),
+        tempos: Vec::new(),
+        end: 0,
+    };
+    let mut tick = 0u64;
+    let mut running: Option<u8> = None;
+
+    while reader.remaining() > 0 {
+        tick += u64::from(reader.vlq()?);
+        let mut status = reader.u8()?;
+        let first_data = if status & 0x80 == 0 {
+            // Running status: the byte just read is already data
+            let data = status;
+            status = running.ok_or_else(|| anyhow!("MIDI data byte without a status"))?;
+            Some(data)
+        } else {
+            None
+        };
+
+        match status {
+            0xFF => {
+                // Meta and system exclusive events cancel running status
+                running = None;
+                let kind = reader.u8()?;
+                let len = reader.vlq()? as usize;
+                let data = reader.take(len)?;
+                match kind {
+                    0x51 if data.len() == 3 => {
+                        let tempo = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                        track.tempos.push((tick, tempo.max(1)));
+                    }
+                    0x2F => break,
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.vlq()? as usize;
+                reader.take(len)?;
+                running = None;
+            }
+            0x80..=0xEF => {
+                running = Some(status);
+                let channel = status & 0x0F;
+                let a = match first_data {
+                    Some(byte) => byte,
+                    None => reader.u8()?,
+                };
+                let kind = match status & 0xF0 {
+                    0x80 => {
+                        reader.u8()?;
+                        Some(EventKind::NoteOff { key: a })
+                    }
+                    0x90 => match reader.u8()? {
+                        0 => Some(EventKind::NoteOff { key: a }),
+                        velocity => Some(EventKind::NoteOn { key: a, velocity }),
+                    },
+                    0xB0 => Some(EventKind::Control { controller: a, value: reader.u8()? }),
+                    0xA0 | 0xE0 => {
+                        reader.u8()?;
+                        None
+                    }
+                    _ => None, // program change and channel pressure have one data byte
+                };
+                if let Some(kind) = kind {
+                    track.events.push((channel, MidiEvent { tick, kind }));
+                }
+            }
+            _ => return Err(anyhow!("Unsupported MIDI status byte {status:#04x}")),
+        }
+    }
+
+    track.end = tick;
+    Ok(track)
+}
+
+/// Big-endian byte reader with bounds checks
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(anyhow!("MIDI file ends unexpectedly"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Variable-length quantity: seven bits per byte, high bit set on all but the last
+    fn vlq(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(anyhow!("MIDI variable-length value is too long"))
+    }
+}
@@ -0,0 +1,230 @@
+//! Live MIDI input through an ALSA sequencer virtual port
+//!
+//! Each `midi` module opens its own sequencer client with one writable port
+//! that controllers or other programs can be connected to (`aconnect`). A
+//! background thread reads events from the port and passes them to the
+//! module over a channel; the module applies whatever has arrived at the
+//! start of every block. Dropping the port closes the client.
+
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+
+/// Name of the sequencer client as shown by `aconnect -l`
+pub const CLIENT_NAME: &str = "zim-dsp";
+
+/// A channel message from a MIDI input, channels numbered from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+    },
+    Control {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    /// -8192 to 8191, 0 is centred
+    PitchBend {
+        channel: u8,
+        value: i16,
+    },
+    ChannelPressure {
+        channel: u8,
+        value: u8,
+    },
+    PolyPressure {
+        channel: u8,
+        key: u8,
+        value: u8,
+    },
+}
+
+impl MidiMessage {
+    pub fn channel(self) -> u8 {
+        match self {
+            Self::NoteOn { channel, .. }
+            | Self::NoteOff { channel, .. }
+            | Self::Control { channel, .. }
+            | Self::PitchBend { channel, .. }
+            | Self::ChannelPressure { channel, .. }
+            | Self::PolyPressure { channel, .. } => channel,
+        }
+    }
+}
+
+/// An open virtual input port, closed when dropped
+pub struct MidiInputPort {
+    stop: Arc<AtomicBool>,
+}
+
+impl MidiInputPort {
+    /// Open a sequencer client with a virtual input port named `port_name`
+    ///
+    /// Messages sent to the port arrive on the returned receiver.
+    ///
+    /// # Errors
+    /// Returns an error if the ALSA sequencer is not available
+    pub fn open(port_name: &str) -> Result<(Self, Receiver<MidiMessage>)> {
+        let (sender, receiver) = mpsc::channel();
+        let (ready_sender, ready) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let port_name = port_name.to_string();
+
+        std::thread::Builder::new()
+            .name("midi-input".to_string())
+            .spawn(move || backend::run(&port_name, &sender, &ready_sender, &thread_stop))
+            .map_err(|e| anyhow!("Failed to start MIDI input thread: {e}"))?;
+
+        // The thread reports whether it managed to open the port
+        ready.recv().map_err(|_| anyhow!("MIDI input thread stopped unexpectedly"))??;
+        Ok((Self { stop }, receiver))
+    }
+}
+
+impl Drop for MidiInputPort {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod backend {
+    use super::{MidiMessage, CLIENT_NAME};
+    use alsa::seq::{EvCtrl, EvNote, EventType, PortCap, PortType, Seq};
+    use anyhow::{anyhow, Result};
+    use std::ffi::CString;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::Sender;
+    use std::time::Duration;
+
+    /// How often the thread checks for new events and for being stopped
+    const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    pub fn run(
+        port_name: &str,
+        sender: &Sender<MidiMessage>,
+        ready: &Sender<Result<()>>,
+        stop: &AtomicBool,
+    ) {
+        let seq = match open(port_name) {
+            Ok(seq) => {
+                let _ = ready.send(Ok(()));
+                seq
+            }
+            Err(e) => {
+                let _ = ready.send(Err(e));
+                return;
+            }
+        };
+
+        let mut input = seq.input();
+        while !stop.load(Ordering::Relaxed) {
+            while input.event_input_pending(true).unwrap_or(0) > 0 {
+                let Ok(event) = input.event_input() else {
+                    break;
+                };
+                if let Some(message) = convert(&event) {
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn open(port_name: &str) -> Result<Seq> {
+        let seq = Seq::open(None, Some(alsa::Direction::Capture), true)
+            .map_err(|e| anyhow!("Failed to open ALSA sequencer: {e}"))?;
+        let client = CString::new(CLIENT_NAME)?;
+        seq.set_client_name(&client)
+            .map_err(|e| anyhow!("Failed to name ALSA sequencer client: {e}"))?;
+        let port = CString::new(port_name)?;
+        seq.create_simple_port(
+            &port,
+            PortCap::WRITE | PortCap::SUBS_WRITE,
+            PortType::MIDI_GENERIC | PortType::APPLICATION,
+        )
+        .map_err(|e| anyhow!("Failed to create MIDI input port: {e}"))?;
+        Ok(seq)
+    }
+
+    fn convert(event: &alsa::seq::Event) -> Option<MidiMessage> {
+        let byte = |value: i32| value.clamp(0, 127) as u8;
+        match event.get_type() {
+            EventType::Noteon => {
+                let note: EvNote = event.get_data()?;
+                Some(if note.velocity == 0 {
+                    MidiMessage::NoteOff { channel: note.channel, key: note.note }
+                } else {
+                    MidiMessage::NoteOn {
+                        channel: note.channel,
+                        key: note.note,
+                        velocity: note.velocity,
+                    }
+                })
+            }
+            EventType::Noteoff => {
+                let note: EvNote = event.get_data()?;
+                Some(MidiMessage::NoteOff { channel: note.channel, key: note.note })
+            }
+            EventType::Keypress => {
+                let note: EvNote = event.get_data()?;
+                Some(MidiMessage::PolyPressure {
+                    channel: note.channel,
+                    key: note.note,
+                    value: note.velocity,
+                })
+            }
+            EventType::Controller => {
+                let ctrl: EvCtrl = event.get_data()?;
+                Some(MidiMessage::Control {
+                    channel: ctrl.channel,
+                    controller: byte(ctrl.param as i32),
+                    value: byte(ctrl.value),
+                })
+            }
+            EventType::Pitchbend => {
+                let ctrl: EvCtrl = event.get_data()?;
+                Some(MidiMessage::PitchBend {
+                    channel: ctrl.channel,
+                    value: ctrl.value.clamp(-8192, 8191) as i16,
+                })
+            }
+            EventType::Chanpress => {
+                let ctrl: EvCtrl = event.get_data()?;
+                Some(MidiMessage::ChannelPressure {
+                    channel: ctrl.channel,
+                    value: byte(ctrl.value),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod backend {
+    use super::MidiMessage;
+    use anyhow::{anyhow, Result};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc::Sender;
+
+    pub fn run(
+        _port_name: &str,
+        _sender: &Sender<MidiMessage>,
+        ready: &Sender<Result<()>>,
+        _stop: &AtomicBool,
+    ) {
+        let _ = ready.send(Err(anyhow!("Live MIDI input needs the ALSA sequencer (Linux only)")));
+    }
+}
@@ -0,0 +1,358 @@
+//! Tests for MIDI file parsing, the MIDI file player and live MIDI input
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{GraphMidiFile, GraphMidiIn, NotePriority};
+    use crate::midi_file::{EventKind, MidiEvent, MidiFile};
+    use crate::midi_input::MidiMessage;
+    use crate::test_framework::{process_module, TestRunner};
+    use std::sync::mpsc::{self, Sender};
+    use std::time::Duration;
+
+    const SAMPLE_RATE: usize = 44100;
+
+    fn vlq(mut value: u32) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    /// A track chunk from (delta, raw bytes) pairs, closed at `end_delta`
+    fn track(events: &[(u32, &[u8])], end_delta: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (delta, bytes) in events {
+            data.extend(vlq(*delta));
+            data.extend_from_slice(bytes);
+        }
+        data.extend(vlq(end_delta));
+        data.extend_from_slice(&[0xFF, 0x2F, 0x00]);
+
+        let mut chunk = b"MTrk".to_vec();
+        chunk.extend((data.len() as u32).to_be_bytes());
+        chunk.extend(data);
+        chunk
+    }
+
+    fn smf(format: u16, tracks: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend(6u32.to_be_bytes());
+        bytes.extend(format.to_be_bytes());
+        bytes.extend((tracks.len() as u16).to_be_bytes());
+        bytes.extend(96u16.to_be_bytes());
+        for track in tracks {
+            bytes.extend_from_slice(track);
+        }
+        bytes
+    }
+
+    /// One part at 120 BPM: A4 on the second beat for half a beat
+    fn one_note(length: u32) -> MidiFile {
+        let notes = track(&[(96, &[0x90, 69, 100]), (48, &[0x80, 69, 0])], length - 144);
+        MidiFile::parse(&smf(1, &[notes])).unwrap()
+    }
+
+    fn rises(signal: &[f32]) -> Vec<usize> {
+        (0..signal.len())
+            .filter(|&i| signal[i] > 0.5 && (i == 0 || signal[i - 1] <= 0.5))
+            .collect()
+    }
+
+    fn pulses(len: usize, every: usize) -> Vec<f32> {
+        (0..len).map(|i| if i % every == 0 { 1.0 } else { 0.0 }).collect()
+    }
+
+    fn live(voices: usize, priority: NotePriority) -> (Sender<MidiMessage>, GraphMidiIn) {
+        let (sender, receiver) = mpsc::channel();
+        (sender, GraphMidiIn::from_receiver(receiver, voices, priority))
+    }
+
+    fn on(key: u8) -> MidiMessage {
+        MidiMessage::NoteOn { channel: 0, key, velocity: 127 }
+    }
+
+    fn off(key: u8) -> MidiMessage {
+        MidiMessage::NoteOff { channel: 0, key }
+    }
+
+    /// Send messages, then run one block and return (note, gate) at its
+    /// first and last samples
+    fn play(
+        sender: &Sender<MidiMessage>,
+        midi: &mut GraphMidiIn,
+        messages: &[MidiMessage],
+    ) -> ((f32, f32), (f32, f32)) {
+        for message in messages {
+            sender.send(*message).unwrap();
+        }
+        let outputs = process_module(midi, &[], 64);
+        let note = outputs.get("note").unwrap();
+        let gate = outputs.get("gate").unwrap();
+        ((note[0], gate[0]), (note[63], gate[63]))
+    }
+
+    #[test]
+    fn test_parse_running_status_and_format_zero() {
+        // Running status, note-on with velocity 0 as note-off, two channels
+        let events = track(
+            &[
+                (0, &[0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]),
+                (0, &[0x90, 60, 100]),
+                (0, &[64, 90]),
+                (10, &[60, 0]),
+                (0, &[0xB3, 74, 64]),
+                (5, &[0x93, 48, 80]),
+                (0, &[0xC3, 5]),
+                (20, &[0x83, 48, 0]),
+            ],
+            5,
+        );
+        let song = MidiFile::parse(&smf(0, &[events])).unwrap();
+
+        assert_eq!(song.ticks_per_quarter, 96);
+        assert_eq!(song.length, 40);
+        assert_eq!(song.tempo_at(0), 500_000);
+        assert_eq!(song.tracks.len(), 2);
+
+        let first = &song.tracks[0].events;
+        assert_eq!(first.len(), 3);
+        assert_eq!(
+            first[1],
+            MidiEvent {
+                tick: 0,
+                kind: EventKind::NoteOn { key: 64, velocity: 90 }
+            }
+        );
+        assert_eq!(
+            first[2],
+            MidiEvent {
+                tick: 10,
+                kind: EventKind::NoteOff { key: 60 }
+            }
+        );
+
+        let second = &song.tracks[1];
+        assert_eq!(second.controllers, vec![74]);
+        assert_eq!(second.events.last().unwrap().tick, 35);
+    }
+
+    #[test]
+    fn test_parse_example_file_and_reject_bad_data() {
+        let song = MidiFile::from_file("examples/midi/groove.mid").unwrap();
+        // The conductor track has no notes, so it is not a part
+        assert_eq!(song.tracks.len(), 2);
+        assert_eq!(song.length, 768);
+        assert_eq!(song.tempo_at(400), 600_000);
+        assert_eq!(song.tracks[1].controllers, vec![74]);
+
+        assert!(MidiFile::parse(b"RIFF....WAVE").is_err());
+        let mut truncated = smf(1, &[track(&[(0, &[0x90, 60, 100])], 10)]);
+        truncated.truncate(truncated.len() - 3);
+        assert!(MidiFile::parse(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_player_follows_file_tempo() {
+        let mut player = GraphMidiFile::new(one_note(192), 1);
+        let outputs = process_module(&mut player, &[], SAMPLE_RATE * 2);
+        let gate = outputs.get("gate").unwrap();
+
+        // Half a second per beat at 120 BPM, and the song loops after one second
+        let on = rises(gate);
+        assert_eq!(on.len(), 2);
+        assert!(on[0].abs_diff(22050) <= 1, "note at {}", on[0]);
+        assert!(on[1].abs_diff(SAMPLE_RATE + 22050) <= 2, "note at {}", on[1]);
+        let off = (on[0]..SAMPLE_RATE).find(|&i| gate[i] < 0.5).unwrap();
+        assert!(off.abs_diff(33075) <= 1, "released at {off}");
+
+        assert!((outputs.get("pitch1").unwrap()[on[0]] - 440.0).abs() < 0.01);
+        assert!((outputs.get("velocity").unwrap()[on[0]] - 100.0 / 127.0).abs() < 1e-6);
+        assert_eq!(outputs.get("gate1").unwrap(), gate);
+    }
+
+    #[test]
+    fn test_player_loop_reset_and_eoc() {
+        let mut player = GraphMidiFile::new(one_note(192), 1).with_looping(false);
+        let outputs = process_module(&mut player, &[], SAMPLE_RATE * 2);
+        assert_eq!(rises(outputs.get("gate").unwrap()).len(), 1);
+        let eoc = rises(outputs.get("eoc").unwrap());
+        assert_eq!(eoc.len(), 1);
+        assert!(eoc[0].abs_diff(SAMPLE_RATE) <= 1);
+
+        // Holding the loop input keeps it going anyway
+        let mut player = GraphMidiFile::new(one_note(192), 1).with_looping(false);
+        let outputs =
+            process_module(&mut player, &[("loop", vec![1.0; SAMPLE_RATE * 2])], SAMPLE_RATE * 2);
+        assert_eq!(rises(outputs.get("gate").unwrap()).len(), 2);
+
+        // Reset starts the song over from its first tick
+        let mut reset = vec![0.0; SAMPLE_RATE * 2];
+        reset[30000] = 1.0;
+        let mut player = GraphMidiFile::new(one_note(192), 1).with_looping(false);
+        let outputs = process_module(&mut player, &[("reset", reset)], SAMPLE_RATE * 2);
+        let gate = outputs.get("gate").unwrap();
+        assert_eq!(gate[30000], 0.0, "held note is released");
+        let on = rises(gate);
+        assert_eq!(on.len(), 2);
+        assert!(on[1].abs_diff(30000 + 22050) <= 1, "note at {}", on[1]);
+    }
+
+    #[test]
+    fn test_player_follows_clock() {
+        // Quarter notes every 1000 samples, much faster than the file's tempo
+        let notes = track(&[(96, &[0x90, 60, 100]), (48, &[0x80, 60, 0])], 48);
+        let song = MidiFile::parse(&smf(1, &[notes])).unwrap();
+        let mut player = GraphMidiFile::new(song, 1).with_sync(true);
+        let outputs = process_module(&mut player, &[("clock", pulses(4000, 1000))], 4000);
+        let gate = outputs.get("gate").unwrap();
+
+        // The note lands on the second pulse and lasts half the pulse spacing
+        assert_eq!(rises(gate)[0], 1000);
+        let off = (1000..4000).find(|&i| gate[i] < 0.5).unwrap();
+        assert!(off.abs_diff(1500) <= 1, "released at {off}");
+
+        // With no clock a synced player waits
+        let notes = track(&[(0, &[0x90, 60, 100]), (1, &[0x80, 60, 0])], 95);
+        let song = MidiFile::parse(&smf(1, &[notes])).unwrap();
+        let mut player = GraphMidiFile::new(song, 1).with_sync(true);
+        let outputs = process_module(&mut player, &[], 1000);
+        assert_eq!(outputs.get("gate").unwrap()[999], 1.0);
+        assert_eq!(player.get_param("sync"), Some(1.0));
+    }
+
+    #[test]
+    fn test_player_chords_and_controllers() {
+        let pad = track(
+            &[
+                (0, &[0xB0, 74, 127]),
+                (0, &[0x90, 60, 100]),
+                (0, &[0x90, 64, 100]),
+                (0, &[0x90, 67, 100]),
+                (48, &[0xB0, 74, 0]),
+            ],
+            48,
+        );
+        let song = MidiFile::parse(&smf(1, &[pad])).unwrap();
+        let mut player = GraphMidiFile::new(song, 3);
+        let outputs = process_module(&mut player, &[], 512);
+
+        assert_eq!(outputs.channels("pitch"), 3);
+        let pitches: Vec<f32> = (0..3).map(|v| outputs.channel("pitch", v).unwrap()[10]).collect();
+        assert!((pitches[2] / pitches[0] - 1.498).abs() < 0.01, "{pitches:?}");
+        assert!((0..3).all(|v| outputs.channel("gate", v).unwrap()[10] == 1.0));
+
+        assert_eq!(outputs.channels("cc74"), 1);
+        assert_eq!(outputs.get("cc74_1").unwrap()[10], 1.0);
+    }
+
+    #[test]
+    fn test_groove_example() {
+        let mut runner = TestRunner::new();
+        let result = runner
+            .run_patch_file("examples/midi/groove.zim", Duration::from_secs(3))
+            .expect("example should run");
+
+        result.assert_signal_varied("mix", "out").unwrap();
+        result.assert_signal_varied("song", "cc74_2").unwrap();
+        assert!(result.gate_fire_count("song", "gate1") > 0);
+    }
+
+    #[test]
+    fn test_live_last_note_priority() {
+        let (sender, mut midi) = live(1, NotePriority::Last);
+        let (_, (note, gate)) = play(&sender, &mut midi, &[on(69)]);
+        assert!((note - 440.0).abs() < 0.01);
+        assert_eq!(gate, 1.0);
+
+        // A second note takes over, dropping the gate for one sample
+        let ((_, first_gate), (note, gate)) = play(&sender, &mut midi, &[on(81)]);
+        assert_eq!(first_gate, 0.0);
+        assert!((note - 880.0).abs() < 0.01);
+        assert_eq!(gate, 1.0);
+
+        // Releasing it falls back to the held note without retriggering
+        let ((_, first_gate), (note, _)) = play(&sender, &mut midi, &[off(81)]);
+        assert_eq!(first_gate, 1.0);
+        assert!((note - 440.0).abs() < 0.01);
+
+        let (_, (note, gate)) = play(&sender, &mut midi, &[off(69)]);
+        assert_eq!(gate, 0.0);
+        assert!((note - 440.0).abs() < 0.01, "pitch holds for the release");
+    }
+
+    #[test]
+    fn test_live_low_note_priority_and_legato() {
+        let (sender, midi) = live(1, NotePriority::Low);
+        let mut midi = midi.with_legato(true);
+        play(&sender, &mut midi, &[on(60)]);
+
+        // A higher note is ignored while the low one is held
+        let (_, (note, _)) = play(&sender, &mut midi, &[on(72)]);
+        assert!((note - 261.63).abs() < 0.01);
+
+        // A lower one takes over, legato so the gate stays up
+        let ((_, first_gate), (note, _)) = play(&sender, &mut midi, &[on(48)]);
+        assert_eq!(first_gate, 1.0);
+        assert!((note - 130.81).abs() < 0.01);
+
+        let (_, (note, gate)) = play(&sender, &mut midi, &[off(48), off(60)]);
+        assert!((note - 523.25).abs() < 0.01);
+        assert_eq!(gate, 1.0);
+
+        midi.set_param("priority", NotePriority::High.as_param()).unwrap();
+        assert_eq!(NotePriority::from_name("highest"), Some(NotePriority::High));
+    }
+
+    #[test]
+    fn test_live_controllers_bend_and_channel() {
+        let (sender, mut midi) = live(1, NotePriority::Last);
+        midi.set_param("channel", 2.0).unwrap();
+        for message in [
+            on(69),
+            MidiMessage::Control { channel: 1, controller: 74, value: 127 },
+            MidiMessage::Control { channel: 0, controller: 1, value: 127 },
+            MidiMessage::PitchBend { channel: 1, value: 8191 },
+            MidiMessage::ChannelPressure { channel: 1, value: 64 },
+            MidiMessage::NoteOn { channel: 1, key: 69, velocity: 64 },
+        ] {
+            sender.send(message).unwrap();
+        }
+        let outputs = process_module(&mut midi, &[], 64);
+
+        // Only channel 2 (numbered 1 on the wire) gets through
+        assert_eq!(outputs.get("cc74").unwrap()[10], 1.0);
+        assert_eq!(outputs.get("cc1").unwrap()[10], 0.0);
+        assert!((outputs.get("velocity").unwrap()[10] - 64.0 / 127.0).abs() < 1e-6);
+        assert!((outputs.get("aftertouch").unwrap()[10] - 64.0 / 127.0).abs() < 1e-6);
+        assert_eq!(outputs.get("trigger").unwrap()[0], 1.0);
+        assert_eq!(outputs.get("trigger").unwrap()[1], 0.0);
+
+        // Full bend is two semitones up by default
+        let bend = outputs.get("pitchbend").unwrap()[10];
+        assert!((bend - 1.0).abs() < 0.001);
+        let note = outputs.get("note").unwrap()[10];
+        assert!((note - 493.88).abs() < 0.1, "note {note}");
+    }
+
+    #[test]
+    fn test_live_poly_voices() {
+        let (sender, mut midi) = live(3, NotePriority::Last);
+        for message in [on(60), on(64), on(67)] {
+            sender.send(message).unwrap();
+        }
+        let outputs = process_module(&mut midi, &[], 64);
+        assert_eq!(outputs.channels("note"), 3);
+        let notes: Vec<f32> = (0..3).map(|v| outputs.channel("note", v).unwrap()[10]).collect();
+        assert!((notes[1] - 329.63).abs() < 0.01, "{notes:?}");
+        assert!((0..3).all(|v| outputs.channel("gate", v).unwrap()[10] == 1.0));
+
+        sender.send(off(64)).unwrap();
+        let outputs = process_module(&mut midi, &[], 64);
+        assert_eq!(outputs.channel("gate", 1).unwrap()[10], 0.0);
+        assert_eq!(outputs.channel("gate", 2).unwrap()[10], 1.0);
+    }
+}
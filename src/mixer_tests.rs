@@ -0,0 +1,173 @@
+//! Tests for matrix, crossfade and panning modules
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{GraphCrossfade, GraphMatrix, GraphPan, GraphStereoMixer, PanLaw};
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    #[test]
+    fn test_matrix_routes_by_level() {
+        let mut matrix = GraphMatrix::new(2, 3);
+        matrix.set_param("in1_out2", 0.5).unwrap();
+        matrix.set_param("in2_out2", -1.0).unwrap();
+        matrix.set_param("in2_out3", 2.0).unwrap();
+
+        let outputs =
+            process_module(&mut matrix, &[("in1", vec![1.0, 2.0]), ("in2", vec![0.25, 0.5])], 2);
+
+        assert_eq!(outputs.get("out1").unwrap(), &vec![0.0, 0.0]);
+        assert_eq!(outputs.get("out2").unwrap(), &vec![0.25, 0.5]);
+        assert_eq!(outputs.get("out3").unwrap(), &vec![0.5, 1.0]);
+        assert!(matrix.set_param("in3_out1", 1.0).is_err());
+    }
+
+    #[test]
+    fn test_matrix_level_cv_adds_to_param() {
+        let mut matrix = GraphMatrix::new(1, 1);
+        matrix.set_param("in1_out1", 0.5).unwrap();
+
+        let outputs = process_module(
+            &mut matrix,
+            &[("in1", vec![1.0; 3]), ("in1_out1", vec![0.0, 0.25, -0.5])],
+            3,
+        );
+
+        assert_eq!(outputs.get("out1").unwrap(), &vec![0.5, 0.75, 0.0]);
+    }
+
+    #[test]
+    fn test_matrix_feedback_patch() {
+        let mut runner = TestRunner::new();
+
+        // Output 2 feeds back into input 2 through the matrix itself,
+        // while an LFO sweeps how much of it returns
+        let patch = r"
+            vco: osc saw 110
+            mtx: matrix 2 2
+            mtx.in1 <- vco.saw
+            mtx.in2 <- mtx.out2
+            mtx.in1_out2 <- 0.5
+            mtx.in2_out2 <- 0.4
+            mtx.in2_out1 <- 1.0
+            sweep: lfo 0.5
+            mtx.in2_out2 <- sweep.sine * 0.3
+            out <- mtx.out1
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Matrix feedback patch should load and run");
+
+        result
+            .assert_signal_varied("mtx", "out1")
+            .expect("Feedback path should carry signal");
+        // Loop gain stays below one, so the feedback settles instead of running away
+        result
+            .assert_signal_range("mtx", "out1", -2.0, 2.0)
+            .expect("Feedback should stay bounded");
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_pan_law_center_gains() {
+        let (left, right) = PanLaw::Linear.gains(0.0);
+        assert_close(left, 0.5);
+        assert_close(right, 0.5);
+
+        let (left, right) = PanLaw::EqualPower.gains(0.0);
+        assert_close(left, std::f32::consts::FRAC_1_SQRT_2);
+        assert_close(left * left + right * right, 1.0);
+
+        // -4.5dB sits between -6dB and -3dB
+        let (left, _) = PanLaw::Compromise.gains(0.0);
+        assert_close(20.0 * left.log10(), -4.5154);
+
+        for law in [PanLaw::Linear, PanLaw::EqualPower, PanLaw::Compromise] {
+            assert_eq!(law.gains(-1.0), (1.0, 0.0));
+            let (left, right) = law.gains(1.0);
+            assert_close(left, 0.0);
+            assert_close(right, 1.0);
+        }
+    }
+
+    #[test]
+    fn test_pan_module() {
+        let mut pan = GraphPan::new(0.0, PanLaw::Linear);
+        let outputs =
+            process_module(&mut pan, &[("in", vec![1.0; 3]), ("pan", vec![-1.0, 0.0, 1.0])], 3);
+
+        assert_eq!(outputs.get("left").unwrap(), &vec![1.0, 0.5, 0.0]);
+        assert_eq!(outputs.get("right").unwrap(), &vec![0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_crossfade_curves() {
+        let mut linear = GraphCrossfade::new(0.0, PanLaw::Linear);
+        let outputs = process_module(
+            &mut linear,
+            &[("a", vec![1.0; 3]), ("b", vec![-1.0; 3]), ("cv", vec![0.0, 0.25, 1.0])],
+            3,
+        );
+        assert_eq!(outputs.get("out").unwrap(), &vec![1.0, 0.5, -1.0]);
+
+        // Equal power keeps uncorrelated signals at constant loudness through the middle
+        let mut equal = GraphCrossfade::new(0.5, PanLaw::EqualPower);
+        let outputs = process_module(&mut equal, &[("a", vec![1.0]), ("b", vec![0.0])], 1);
+        assert_close(outputs.get("out").unwrap()[0], std::f32::consts::FRAC_1_SQRT_2);
+
+        // The law param is named as on pan and stereomix; curve still works
+        equal.set_param("law", 0.0).unwrap();
+        let outputs = process_module(&mut equal, &[("a", vec![1.0]), ("b", vec![0.0])], 1);
+        assert_close(outputs.get("out").unwrap()[0], 0.5);
+        assert_eq!(equal.get_param("curve"), equal.get_param("law"));
+    }
+
+    #[test]
+    fn test_stereo_mixer_uses_pan_law() {
+        let mut mixer = GraphStereoMixer::new(2);
+        let outputs = process_module(&mut mixer, &[("l1", vec![1.0])], 1);
+        assert_close(outputs.get("left").unwrap()[0], std::f32::consts::FRAC_1_SQRT_2);
+
+        mixer.set_param("law", 0.0).unwrap();
+        let outputs = process_module(&mut mixer, &[("l1", vec![1.0])], 1);
+        assert_close(outputs.get("left").unwrap()[0], 0.5);
+        assert_close(outputs.get("right").unwrap()[0], 0.5);
+    }
+
+    #[test]
+    fn test_pan_and_xfade_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            saw: osc saw 110
+            sine: osc sine 220
+            morph: lfo 0.5
+            xf: xfade 0.5 linear
+            xf.a <- saw.saw
+            xf.b <- sine.sine
+            xf.cv <- morph.sine * 0.5
+            auto: lfo 0.25
+            pn: pan 0 compromise
+            pn.in <- xf.out
+            pn.pan <- auto.sine
+            mix: stereomix 2 linear
+            mix.l1 <- pn.left
+            mix.r1 <- pn.right
+            out.left <- mix.left
+            out.right <- mix.right
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Pan and crossfade patch should load and run");
+
+        result.assert_signal_varied("xf", "out").expect("Crossfader should pass audio");
+        result.assert_signal_varied("pn", "left").expect("Panner should pass audio");
+        assert!(runner.run_patch("pn: pan 0 wide", Duration::from_millis(10)).is_err());
+    }
+}
@@ -22,6 +22,41 @@ pub enum ModuleType {
     Switch,
     ClockDiv,
     SampleHold,
+    Wavetable,
+    FmOperator,
+    Comparator,
+    Logic,
+    Edge,
+    Attenuverter,
+    Rectifier,
+    Inverter,
+    SumDiff,
+    Constant,
+    Clock,
+    Euclid,
+    Turing,
+    Bernoulli,
+    Matrix,
+    Crossfade,
+    Pan,
+    Follower,
+    Duck,
+    RingMod,
+    FreqShift,
+    Chorus,
+    Flanger,
+    Phaser,
+    Vocoder,
+    String,
+    Resonator,
+    Random,
+    Chaos,
+    Poly,
+    Sum,
+    MidiFile,
+    MidiIn,
+    OscIn,
+    OscOut,
 }
 
 impl std::fmt::Display for ModuleType {
@@ -45,6 +80,41 @@ impl std::fmt::Display for ModuleType {
             Self::Switch => write!(f, "switch"),
             Self::ClockDiv => write!(f, "clockdiv"),
             Self::SampleHold => write!(f, "samplehold"),
+            Self::Wavetable => write!(f, "wavetable"),
+            Self::FmOperator => write!(f, "fm"),
+            Self::Comparator => write!(f, "comparator"),
+            Self::Logic => write!(f, "logic"),
+            Self::Edge => write!(f, "edge"),
+            Self::Attenuverter => write!(f, "attenuverter"),
+            Self::Rectifier => write!(f, "rectifier"),
+            Self::Inverter => write!(f, "inverter"),
+            Self::SumDiff => write!(f, "sumdiff"),
+            Self::Constant => write!(f, "constant"),
+            Self::Clock => write!(f, "clock"),
+            Self::Euclid => write!(f, "euclid"),
+            Self::Turing => write!(f, "turing"),
+            Self::Bernoulli => write!(f, "bernoulli"),
+            Self::Matrix => write!(f, "matrix"),
+            Self::Crossfade => write!(f, "xfade"),
+            Self::Pan => write!(f, "pan"),
+            Self::Follower => write!(f, "follower"),
+            Self::Duck => write!(f, "duck"),
+            Self::RingMod => write!(f, "ringmod"),
+            Self::FreqShift => write!(f, "freqshift"),
+            Self::Chorus => write!(f, "chorus"),
+            Self::Flanger => write!(f, "flanger"),
+            Self::Phaser => write!(f, "phaser"),
+            Self::Vocoder => write!(f, "vocoder"),
+            Self::String => write!(f, "string"),
+            Self::Resonator => write!(f, "resonator"),
+            Self::Random => write!(f, "random"),
+            Self::Chaos => write!(f, "chaos"),
+            Self::Poly => write!(f, "poly"),
+            Self::Sum => write!(f, "sum"),
+            Self::MidiFile => write!(f, "midifile"),
+            Self::MidiIn => write!(f, "midi"),
+            Self::OscIn => write!(f, "osc_in"),
+            Self::OscOut => write!(f, "osc_out"),
         }
     }
 }
@@ -72,6 +142,41 @@ pub fn parse_module_type(s: &str) -> Result<ModuleType> {
         "switch" | "seq_switch" => Ok(ModuleType::Switch),
         "clockdiv" | "clock_div" | "divider" => Ok(ModuleType::ClockDiv),
         "samplehold" | "sample_hold" | "sh" => Ok(ModuleType::SampleHold),
+        "wavetable" | "wt" => Ok(ModuleType::Wavetable),
+        "fm" | "fm_op" | "operator" => Ok(ModuleType::FmOperator),
+        "comparator" | "compare" | "comp" => Ok(ModuleType::Comparator),
+        "logic" | "gate_logic" => Ok(ModuleType::Logic),
+        "edge" | "edge_detect" => Ok(ModuleType::Edge),
+        "attenuverter" | "attenuvert" | "atten" => Ok(ModuleType::Attenuverter),
+        "rectifier" | "rect" => Ok(ModuleType::Rectifier),
+        "inverter" | "invert" | "inv" => Ok(ModuleType::Inverter),
+        "sumdiff" | "sum_diff" | "difference" => Ok(ModuleType::SumDiff),
+        "constant" | "const" | "dc" => Ok(ModuleType::Constant),
+        "clock" | "master_clock" => Ok(ModuleType::Clock),
+        "euclid" | "euclidean" => Ok(ModuleType::Euclid),
+        "turing" | "turing_machine" | "shift_register" => Ok(ModuleType::Turing),
+        "bernoulli" | "bernoulli_gate" | "coin" => Ok(ModuleType::Bernoulli),
+        "matrix" | "matrix_mixer" => Ok(ModuleType::Matrix),
+        "xfade" | "crossfade" | "crossfader" => Ok(ModuleType::Crossfade),
+        "pan" | "panner" => Ok(ModuleType::Pan),
+        "follower" | "envfollow" | "env_follower" => Ok(ModuleType::Follower),
+        "duck" | "ducker" | "sidechain" => Ok(ModuleType::Duck),
+        "ringmod" | "ring_mod" | "ring" => Ok(ModuleType::RingMod),
+        "freqshift" | "freq_shift" | "shifter" => Ok(ModuleType::FreqShift),
+        "chorus" | "ensemble" => Ok(ModuleType::Chorus),
+        "flanger" | "flange" => Ok(ModuleType::Flanger),
+        "phaser" | "phase_shifter" => Ok(ModuleType::Phaser),
+        "vocoder" | "vocode" => Ok(ModuleType::Vocoder),
+        "string" | "pluck" | "karplus" => Ok(ModuleType::String),
+        "resonator" | "modal" => Ok(ModuleType::Resonator),
+        "random" | "rand" | "rnd" => Ok(ModuleType::Random),
+        "chaos" | "attractor" => Ok(ModuleType::Chaos),
+        "poly" | "voices" => Ok(ModuleType::Poly),
+        "sum" | "polysum" => Ok(ModuleType::Sum),
+        "midifile" | "midi_file" | "smf" => Ok(ModuleType::MidiFile),
+        "midi" | "midi_in" | "midiin" => Ok(ModuleType::MidiIn),
+        "osc_in" | "oscin" | "osc_receive" => Ok(ModuleType::OscIn),
+        "osc_out" | "oscout" | "osc_send" => Ok(ModuleType::OscOut),
         _ => Err(anyhow!("Unknown module type: {s}")),
     }
 }
@@ -3,6 +3,10 @@
 //! Provides a clean interface for monitoring signals, parameters, and events
 //! without polluting the core audio processing code.
 
+use crate::osc::{OscArg, OscMessage, OscSender};
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+
 /// A single signal observation event
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Test framework event types
@@ -20,6 +24,7 @@ pub struct GateEvent {
     pub module: String,
     pub gate: String,
     pub sample_index: usize,
+    /// True on the rising edge, false when the gate falls again
     pub triggered: bool,
 }
 
@@ -36,7 +41,7 @@ pub trait SignalObserver: Send {
     /// Called when a signal value is observed
     fn observe_signal(&mut self, event: &SignalEvent);
 
-    /// Called when a gate/trigger fires
+    /// Called when a gate/trigger rises or falls
     fn observe_gate(&mut self, event: &GateEvent);
 
     /// Called when a parameter changes
@@ -187,21 +192,150 @@ impl SignalObserver for ConsoleObserver {
     }
 }
 
+/// A signal or gate sent over OSC, as `module.port` or every port of `module`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OscWatch {
+    pub module: String,
+    pub port: Option<String>,
+    /// Address to send to; `/<module>/<port>` when not given
+    pub address: Option<String>,
+}
+
+impl OscWatch {
+    /// Parse `module`, `module.port` or `module.port=/address`
+    ///
+    /// # Errors
+    /// Returns an error if the module name is empty or the address does not start with '/'
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (source, address) = match spec.split_once('=') {
+            Some((source, address)) if address.starts_with('/') => {
+                (source, Some(address.to_string()))
+            }
+            Some(_) => return Err(anyhow!("OSC addresses start with '/': {spec}")),
+            None => (spec, None),
+        };
+        let (module, port) = match source.split_once('.') {
+            Some((module, port)) => (module, Some(port.to_string())),
+            None => (source, None),
+        };
+        if module.is_empty() || port.as_deref() == Some("") {
+            return Err(anyhow!("Expected module.port to send over OSC, got '{spec}'"));
+        }
+        if address.is_some() && port.is_none() {
+            return Err(anyhow!("A custom OSC address needs a single port: {spec}"));
+        }
+        Ok(Self {
+            module: module.to_string(),
+            port,
+            address,
+        })
+    }
+
+    fn address_for(&self, module: &str, port: &str) -> Option<String> {
+        if self.module != module || self.port.as_deref().is_some_and(|p| p != port) {
+            return None;
+        }
+        Some(self.address.clone().unwrap_or_else(|| format!("/{module}/{port}")))
+    }
+}
+
+/// Sends watched signals and gate edges as OSC messages
+///
+/// Signals are sent at most `rate` times a second and only when their value
+/// changes; gates are sent on every edge, 1 as they rise and 0 as they fall.
+pub struct OscObserver {
+    sender: OscSender,
+    watches: Vec<OscWatch>,
+    interval: usize,
+    // Per address: sample index of the last send and the value sent
+    last_sent: HashMap<String, (usize, f32)>,
+    // Addresses already seen as gates, which only send their edges
+    gates: HashSet<String>,
+}
+
+impl OscObserver {
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn new(sender: OscSender, watches: Vec<OscWatch>, rate: f32, sample_rate: f32) -> Self {
+        Self {
+            sender,
+            watches,
+            interval: (sample_rate / rate.max(0.1)).max(1.0) as usize,
+            last_sent: HashMap::new(),
+            gates: HashSet::new(),
+        }
+    }
+
+    fn address_for(&self, module: &str, port: &str) -> Option<String> {
+        self.watches.iter().find_map(|watch| watch.address_for(module, port))
+    }
+}
+
+impl SignalObserver for OscObserver {
+    fn observe_signal(&mut self, event: &SignalEvent) {
+        let Some(address) = self.address_for(&event.module, &event.port) else {
+            return;
+        };
+        if self.gates.contains(&address) {
+            return;
+        }
+        let due = match self.last_sent.get(&address) {
+            Some(&(index, value)) => {
+                event.sample_index >= index + self.interval && value != event.value
+            }
+            None => true,
+        };
+        if due {
+            self.sender.send(OscMessage::new(&address, vec![OscArg::Float(event.value)]));
+            self.last_sent.insert(address, (event.sample_index, event.value));
+        }
+    }
+
+    fn observe_gate(&mut self, event: &GateEvent) {
+        let Some(address) = self.address_for(&event.module, &event.gate) else {
+            return;
+        };
+        let value = if event.triggered { 1.0 } else { 0.0 };
+        self.sender.send(OscMessage::new(&address, vec![OscArg::Float(value)]));
+        self.gates.insert(address);
+    }
+
+    fn observe_parameter(&mut self, _event: &ParameterEvent) {}
+}
+
 /// Manages multiple observers
 pub struct ObserverManager {
     observers: Vec<Box<dyn SignalObserver>>,
+    /// Observers declared by the patch (`osc_out`), which a reload replaces
+    patch_observers: Vec<Box<dyn SignalObserver>>,
 }
 
 impl ObserverManager {
     #[must_use]
     pub fn new() -> Self {
-        Self { observers: Vec::new() }
+        Self {
+            observers: Vec::new(),
+            patch_observers: Vec::new(),
+        }
     }
 
     pub fn add_observer(&mut self, observer: Box<dyn SignalObserver>) {
         self.observers.push(observer);
     }
 
+    pub fn add_patch_observer(&mut self, observer: Box<dyn SignalObserver>) {
+        self.patch_observers.push(observer);
+    }
+
+    /// Remove and return the observers that don't belong to the patch
+    pub fn take_observers(&mut self) -> Vec<Box<dyn SignalObserver>> {
+        std::mem::take(&mut self.observers)
+    }
+
+    fn all(&mut self) -> impl Iterator<Item = &mut Box<dyn SignalObserver>> {
+        self.observers.iter_mut().chain(self.patch_observers.iter_mut())
+    }
+
     pub fn observe_signal(&mut self, module: &str, port: &str, sample_index: usize, value: f32) {
         let event = SignalEvent {
             module: module.to_string(),
@@ -210,7 +344,7 @@ impl ObserverManager {
             value,
         };
 
-        for observer in &mut self.observers {
+        for observer in self.all() {
             observer.observe_signal(&event);
         }
     }
@@ -223,7 +357,7 @@ impl ObserverManager {
             triggered,
         };
 
-        for observer in &mut self.observers {
+        for observer in self.all() {
             observer.observe_gate(&event);
         }
     }
@@ -235,19 +369,19 @@ impl ObserverManager {
             value,
         };
 
-        for observer in &mut self.observers {
+        for observer in self.all() {
             observer.observe_parameter(&event);
         }
     }
 
     pub fn begin_process_cycle(&mut self, cycle: usize) {
-        for observer in &mut self.observers {
+        for observer in self.all() {
             observer.begin_process_cycle(cycle);
         }
     }
 
     pub fn end_process_cycle(&mut self, cycle: usize) {
-        for observer in &mut self.observers {
+        for observer in self.all() {
             observer.end_process_cycle(cycle);
         }
     }
@@ -0,0 +1,364 @@
+//! Open Sound Control over UDP
+//!
+//! A small OSC 1.0 codec (messages and bundles with int, float, double,
+//! string and boolean arguments), a UDP server that decodes incoming
+//! packets on a background thread and hands each message to a callback,
+//! and a sender that encodes and sends messages from its own thread so the
+//! audio thread never touches the socket. The engine decides what the
+//! messages mean; this module only moves them.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Largest packet the server will read
+const MAX_PACKET: usize = 65_507;
+
+/// How often the server thread checks whether it has been stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// One argument of an OSC message
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Double(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl OscArg {
+    /// The argument as a control value, if it is numeric or boolean
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::Int(value) => Some(*value as f32),
+            Self::Float(value) => Some(*value),
+            Self::Double(value) => Some(*value as f32),
+            Self::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            Self::Str(_) => None,
+        }
+    }
+}
+
+/// An address and its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: &str, args: Vec<OscArg>) -> Self {
+        Self { address: address.to_string(), args }
+    }
+
+    /// The first numeric or boolean argument
+    #[must_use]
+    pub fn value(&self) -> Option<f32> {
+        self.args.iter().find_map(OscArg::as_f32)
+    }
+
+    /// Encode as a single OSC packet
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        push_string(&mut bytes, &self.address);
+
+        let mut tags = String::from(",");
+        for arg in &self.args {
+            tags.push(match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::Double(_) => 'd',
+                OscArg::Str(_) => 's',
+                OscArg::Bool(true) => 'T',
+                OscArg::Bool(false) => 'F',
+            });
+        }
+        push_string(&mut bytes, &tags);
+
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(value) => bytes.extend(value.to_be_bytes()),
+                OscArg::Float(value) => bytes.extend(value.to_be_bytes()),
+                OscArg::Double(value) => bytes.extend(value.to_be_bytes()),
+                OscArg::Str(value) => push_string(&mut bytes, value),
+                OscArg::Bool(_) => {}
+            }
+        }
+        bytes
+    }
+}
+
+/// Decode a packet into its messages, flattening any bundles
+///
+/// Bundle time tags are ignored: everything is applied as it arrives.
+///
+/// # Errors
+/// Returns an error if the packet is not valid OSC
+pub fn decode_packet(bytes: &[u8]) -> Result<Vec<OscMessage>> {
+    let mut messages = Vec::new();
+    decode_into(bytes, &mut messages)?;
+    Ok(messages)
+}
+
+fn decode_into(bytes: &[u8], messages: &mut Vec<OscMessage>) -> Result<()> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let address = reader.string()?;
+
+    if address == "#bundle" {
+        reader.take(8)?; // time tag
+        while reader.remaining() > 0 {
+            let size = reader.u32()? as usize;
+            decode_into(reader.take(size)?, messages)?;
+        }
+        return Ok(());
+    }
+    if !address.starts_with('/') {
+        return Err(anyhow!("OSC address must start with '/': {address}"));
+    }
+
+    // Very old senders leave out the type tags; treat that as no arguments
+    let tags = if reader.remaining() > 0 { reader.string()? } else { String::from(",") };
+    let tags = tags
+        .strip_prefix(',')
+        .ok_or_else(|| anyhow!("Missing OSC type tags for {address}"))?;
+
+    let mut args = Vec::new();
+    for tag in tags.chars() {
+        match tag {
+            'i' => args.push(OscArg::Int(reader.u32()? as i32)),
+            'f' => args.push(OscArg::Float(f32::from_bits(reader.u32()?))),
+            'd' => args.push(OscArg::Double(f64::from_bits(reader.u64()?))),
+            // Too wide for an Int; a double keeps large values from wrapping
+            #[allow(clippy::cast_possible_wrap, clippy::cast_precision_loss)]
+            'h' => args.push(OscArg::Double(reader.u64()? as i64 as f64)),
+            's' | 'S' => args.push(OscArg::Str(reader.string()?)),
+            'T' => args.push(OscArg::Bool(true)),
+            'F' => args.push(OscArg::Bool(false)),
+            'N' | 'I' | '[' | ']' => {}
+            'b' => {
+                let size = reader.u32()? as usize;
+                reader.take((size + 3) & !3)?;
+            }
+            't' => {
+                reader.u64()?;
+            }
+            'c' | 'r' | 'm' => {
+                reader.u32()?;
+            }
+            _ => return Err(anyhow!("Unsupported OSC type tag '{tag}' in {address}")),
+        }
+    }
+
+    messages.push(OscMessage { address, args });
+    Ok(())
+}
+
+/// Append a null-terminated string padded to four bytes
+fn push_string(bytes: &mut Vec<u8>, value: &str) {
+    let padded = bytes.len() + ((value.len() + 4) & !3);
+    bytes.extend(value.as_bytes());
+    bytes.resize(padded, 0);
+}
+
+/// Big-endian reader over four-byte aligned OSC data
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(anyhow!("OSC packet ends unexpectedly"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok((u64::from(self.u32()?) << 32) | u64::from(self.u32()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let rest = &self.bytes[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| anyhow!("Unterminated OSC string"))?;
+        let value = std::str::from_utf8(&rest[..len])
+            .map_err(|_| anyhow!("OSC string is not UTF-8"))?
+            .to_string();
+        self.take(((len + 4) & !3).min(rest.len()))?;
+        Ok(value)
+    }
+}
+
+/// Latest value received on each OSC address, shared with `osc_in` modules
+///
+/// Each address also counts its messages so readers can tell a repeated
+/// value (a button pressed twice) from no message at all.
+#[derive(Debug, Clone, Default)]
+pub struct OscValues {
+    values: Arc<Mutex<HashMap<String, (f32, u64)>>>,
+}
+
+impl OscValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a value for an address
+    ///
+    /// # Panics
+    /// Panics if the store mutex is poisoned
+    pub fn set(&self, address: &str, value: f32) {
+        // Allocate only for a new address, keeping the lock short
+        let mut values = self.values.lock().unwrap();
+        match values.get_mut(address) {
+            Some(entry) => *entry = (value, entry.1 + 1),
+            None => {
+                values.insert(address.to_string(), (value, 1));
+            }
+        }
+    }
+
+    /// The latest value for an address and how many messages it has had
+    ///
+    /// None while a message is being recorded, rather than waiting, as
+    /// readers are on the audio thread and can keep their last value.
+    #[must_use]
+    pub fn get(&self, address: &str) -> Option<(f32, u64)> {
+        self.values.try_lock().ok()?.get(address).copied()
+    }
+}
+
+/// A UDP socket receiving OSC packets on a background thread, closed when dropped
+pub struct OscServer {
+    local_addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscServer {
+    /// Listen on `addr` (such as `0.0.0.0:9000`) and pass every decoded message to `handler`
+    ///
+    /// Packets that are not valid OSC are ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the socket cannot be bound
+    pub fn start<A, F>(addr: A, mut handler: F) -> Result<Self>
+    where
+        A: ToSocketAddrs,
+        F: FnMut(OscMessage) + Send + 'static,
+    {
+        let socket = UdpSocket::bind(addr).map_err(|e| anyhow!("Failed to open OSC port: {e}"))?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let local_addr = socket.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let thread = std::thread::Builder::new()
+            .name("osc-server".to_string())
+            .spawn(move || {
+                let mut buffer = vec![0u8; MAX_PACKET];
+                while !thread_stop.load(Ordering::Relaxed) {
+                    // Timeouts just bring us back round to check the stop flag
+                    let Ok((len, _)) = socket.recv_from(&mut buffer) else {
+                        continue;
+                    };
+                    if let Ok(messages) = decode_packet(&buffer[..len]) {
+                        messages.into_iter().for_each(&mut handler);
+                    }
+                }
+            })
+            .map_err(|e| anyhow!("Failed to start OSC server thread: {e}"))?;
+
+        Ok(Self { local_addr, stop, thread: Some(thread) })
+    }
+
+    /// The address the server is bound to, with the real port when 0 was asked for
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for OscServer {
+    /// Stop the thread and wait for it, so the port is free again on return
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Sends OSC messages to one host and port from a background thread
+///
+/// `send` only queues the message, so it is safe to call while processing
+/// audio. The thread ends when the sender is dropped.
+pub struct OscSender {
+    target: SocketAddr,
+    queue: Sender<OscMessage>,
+}
+
+impl OscSender {
+    /// Resolve `target` (such as `127.0.0.1:9001`) and start the sending thread
+    ///
+    /// # Errors
+    /// Returns an error if the target cannot be resolved or no socket can be opened
+    pub fn connect<A: ToSocketAddrs>(target: A) -> Result<Self> {
+        let target = target
+            .to_socket_addrs()
+            .map_err(|e| anyhow!("Invalid OSC target: {e}"))?
+            .next()
+            .ok_or_else(|| anyhow!("OSC target did not resolve to an address"))?;
+        let local: SocketAddr =
+            if target.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        let socket =
+            UdpSocket::bind(local).map_err(|e| anyhow!("Failed to open OSC socket: {e}"))?;
+        let (queue, messages) = mpsc::channel::<OscMessage>();
+
+        std::thread::Builder::new()
+            .name("osc-sender".to_string())
+            .spawn(move || {
+                // Nobody listening is not an error worth stopping for
+                for message in messages {
+                    let _ = socket.send_to(&message.encode(), target);
+                }
+            })
+            .map_err(|e| anyhow!("Failed to start OSC sender thread: {e}"))?;
+
+        Ok(Self { target, queue })
+    }
+
+    /// Queue a message for sending
+    pub fn send(&self, message: OscMessage) {
+        let _ = self.queue.send(message);
+    }
+
+    /// Where messages are sent
+    #[must_use]
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+}
@@ -0,0 +1,292 @@
+//! Tests for the OSC codec, the OSC control server, osc_in and osc_out
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_engine::GraphEngine;
+    use crate::graph_modules::GraphOscIn;
+    use crate::observability::OscWatch;
+    use crate::osc::{decode_packet, OscArg, OscMessage, OscValues};
+    use crate::test_framework::{process_module, TestRunner};
+    use std::net::UdpSocket;
+    use std::time::{Duration, Instant};
+
+    const BLOCK: usize = 64;
+
+    fn send(port: u16, message: &OscMessage) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.send_to(&message.encode(), ("127.0.0.1", port)).unwrap();
+    }
+
+    /// Wait for the server thread to apply a message
+    fn wait_for(mut done: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if done() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = OscMessage::new(
+            "/zim/vcf/cutoff",
+            vec![
+                OscArg::Float(1200.0),
+                OscArg::Int(-3),
+                OscArg::Str("abc".to_string()),
+                OscArg::Bool(true),
+                OscArg::Double(0.25),
+            ],
+        );
+        let bytes = message.encode();
+        assert_eq!(bytes.len() % 4, 0);
+        // "/zim/vcf/cutoff" is 15 bytes, so its terminator exactly fills the word
+        assert_eq!(&bytes[12..20], b"off\0,fis");
+
+        let decoded = decode_packet(&bytes).unwrap();
+        assert_eq!(decoded, vec![message]);
+        assert_eq!(decoded[0].value(), Some(1200.0));
+        assert_eq!(OscMessage::new("/bang", Vec::new()).value(), None);
+
+        // 64-bit integers keep their size instead of wrapping
+        let mut bytes = b"/big\0\0\0\0,h\0\0".to_vec();
+        bytes.extend((-5_000_000_000i64).to_be_bytes());
+        let decoded = decode_packet(&bytes).unwrap();
+        assert_eq!(decoded[0].args, vec![OscArg::Double(-5_000_000_000.0)]);
+    }
+
+    #[test]
+    fn test_bundles_are_flattened() {
+        let first = OscMessage::new("/1/fader1", vec![OscArg::Float(0.5)]).encode();
+        let second = OscMessage::new("/1/toggle1", vec![OscArg::Bool(false)]).encode();
+
+        let mut bundle = b"#bundle\0".to_vec();
+        bundle.extend([0, 0, 0, 0, 0, 0, 0, 1]); // "immediately"
+        for element in [&first, &second] {
+            bundle.extend((element.len() as u32).to_be_bytes());
+            bundle.extend(element);
+        }
+
+        let messages = decode_packet(&bundle).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].address, "/1/toggle1");
+        assert_eq!(messages[1].value(), Some(0.0));
+
+        assert!(decode_packet(b"nope\0\0\0\0").is_err());
+        assert!(decode_packet(&first[..first.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn test_osc_in_smooths_and_triggers() {
+        let values = OscValues::new();
+        let mut osc_in = GraphOscIn::new(vec!["/a".to_string(), "/b".to_string()], 0.01);
+        osc_in.set_values(values.clone());
+
+        let outputs = process_module(&mut osc_in, &[], BLOCK);
+        assert_eq!(outputs.get("out").unwrap()[BLOCK - 1], 0.0);
+        assert_eq!(outputs.get("trigger").unwrap()[0], 0.0);
+
+        values.set("/a", 1.0);
+        values.set("/b", 5.0);
+        let outputs = process_module(&mut osc_in, &[], BLOCK);
+        let out = outputs.get("out").unwrap();
+        assert_eq!(outputs.get("raw").unwrap()[0], 1.0);
+        assert!(out[0] > 0.0 && out[BLOCK - 1] < 1.0, "smoothing: {out:?}");
+        assert!(out[BLOCK - 1] > out[0]);
+        assert_eq!(outputs.get("out1").unwrap()[BLOCK - 1], out[BLOCK - 1]);
+        assert_eq!(outputs.get("trigger").unwrap()[0], 1.0);
+        assert_eq!(outputs.get("raw2").unwrap()[0], 5.0);
+        assert_eq!(outputs.get("trigger2").unwrap()[0], 1.0);
+
+        // No new messages: no trigger. The same value again: a new trigger
+        let outputs = process_module(&mut osc_in, &[], BLOCK);
+        assert_eq!(outputs.get("trigger").unwrap()[0], 0.0);
+        values.set("/a", 1.0);
+        let outputs = process_module(&mut osc_in, &[], BLOCK);
+        assert_eq!(outputs.get("trigger").unwrap()[0], 1.0);
+        assert_eq!(outputs.get("trigger2").unwrap()[0], 0.0);
+
+        // With smoothing off the output jumps
+        osc_in.set_param("smooth", 0.0).unwrap();
+        values.set("/a", -2.0);
+        let outputs = process_module(&mut osc_in, &[], BLOCK);
+        assert_eq!(outputs.get("out").unwrap()[0], -2.0);
+    }
+
+    #[test]
+    fn test_server_sets_params_and_gates() {
+        let mut engine = GraphEngine::new();
+        engine
+            .load_patch("lfo: lfo 1.0\ngate: manual\nfader: osc_in /1/fader1 0")
+            .unwrap();
+        let port = engine.start_osc(0).unwrap().port();
+        assert_eq!(engine.osc_addr().map(|addr| addr.port()), Some(port));
+
+        send(port, &OscMessage::new("/zim/lfo/freq", vec![OscArg::Float(3.5)]));
+        assert!(wait_for(|| {
+            let mut graph = engine.observer_manager_mut();
+            graph.get_module_mut("lfo").unwrap().get_param("freq") == Some(3.5)
+        }));
+
+        send(port, &OscMessage::new("/zim/gate", vec![OscArg::Int(1)]));
+        assert!(wait_for(|| {
+            let mut graph = engine.observer_manager_mut();
+            graph.get_module_mut("gate").unwrap().get_param("gate") == Some(1.0)
+        }));
+
+        send(port, &OscMessage::new("/1/fader1", vec![OscArg::Float(0.75)]));
+        assert!(wait_for(|| {
+            engine.process_for_test(BLOCK);
+            let graph = engine.observer_manager_mut();
+            graph.get_output("fader", "out").map(|out| out[0]) == Some(0.75)
+        }));
+
+        // Unknown modules are reported, not fatal
+        send(port, &OscMessage::new("/zim/nope/freq", vec![OscArg::Float(1.0)]));
+        send(port, &OscMessage::new("/zim/gate", vec![OscArg::Bool(false)]));
+        assert!(wait_for(|| {
+            let mut graph = engine.observer_manager_mut();
+            graph.get_module_mut("gate").unwrap().get_param("gate") == Some(0.0)
+        }));
+
+        engine.stop_osc();
+        assert!(engine.osc_addr().is_none());
+    }
+
+    #[test]
+    fn test_osc_listens_locally_unless_given_a_host() {
+        let mut engine = GraphEngine::new();
+        assert!(engine.start_osc(0).unwrap().ip().is_loopback());
+        assert!(engine.start_osc_on("0.0.0.0:0").unwrap().ip().is_unspecified());
+        assert!(engine.start_osc_on("no-such-host.invalid:9000").is_err());
+    }
+
+    #[test]
+    fn test_osc_restarts_on_the_same_port() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch("lfo: lfo 1.0").unwrap();
+        let port = engine.start_osc(0).unwrap().port();
+        assert_eq!(engine.start_osc(port).unwrap().port(), port);
+
+        // The new listener is the one that works
+        send(port, &OscMessage::new("/zim/lfo/freq", vec![OscArg::Float(2.5)]));
+        assert!(wait_for(|| {
+            let mut graph = engine.observer_manager_mut();
+            graph.get_module_mut("lfo").unwrap().get_param("freq") == Some(2.5)
+        }));
+    }
+
+    #[test]
+    fn test_osc_in_needs_addresses() {
+        let mut engine = GraphEngine::new();
+        assert!(engine.process_line("f: osc_in").is_err());
+        assert!(engine.process_line("f: osc_in fader1").is_err());
+        assert!(engine.process_line("f: osc_in /1/fader1 /1/fader2 0.1").is_ok());
+        let info = engine.inspect_module("f").unwrap();
+        assert!(info.outputs.iter().any(|port| port.name == "out2"));
+    }
+
+    /// Everything received until the sender goes quiet, as (address, value)
+    fn receive_all(socket: &UdpSocket) -> Vec<(String, f32)> {
+        socket.set_read_timeout(Some(Duration::from_millis(300))).unwrap();
+        let mut buffer = [0u8; 1024];
+        let mut received = Vec::new();
+        while let Ok(len) = socket.recv(&mut buffer) {
+            for message in decode_packet(&buffer[..len]).unwrap() {
+                received.push((message.address.clone(), message.value().unwrap()));
+            }
+        }
+        received
+    }
+
+    #[test]
+    fn test_osc_watch_specs() {
+        let watch = OscWatch::parse("vco.freq=/1/label1").unwrap();
+        assert_eq!(watch.module, "vco");
+        assert_eq!(watch.port.as_deref(), Some("freq"));
+        assert_eq!(watch.address.as_deref(), Some("/1/label1"));
+        assert_eq!(OscWatch::parse("seq").unwrap().port, None);
+
+        assert!(OscWatch::parse("vco.").is_err());
+        assert!(OscWatch::parse("vco.freq=label").is_err());
+        assert!(OscWatch::parse("vco=/all").is_err());
+    }
+
+    #[test]
+    fn test_osc_out_sends_signals_and_gate_edges() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+
+        let mut engine = GraphEngine::new();
+        engine
+            .load_patch(&format!(
+                "lfo: lfo 5\ngate: manual\nviz: osc_out 127.0.0.1:{port} lfo.sine gate.gate=/button 100"
+            ))
+            .unwrap();
+
+        // One second of audio with the gate held through the middle
+        for block in 0..689 {
+            match block {
+                200 => assert_eq!(engine.activate_manual_gates(), 1),
+                400 => assert_eq!(engine.release_manual_gates(), 1),
+                _ => {}
+            }
+            engine.process_for_test(BLOCK);
+        }
+        let received = receive_all(&socket);
+
+        // 100 values a second, decimated from every sample
+        let sine: Vec<f32> =
+            received.iter().filter(|(a, _)| a == "/lfo/sine").map(|(_, v)| *v).collect();
+        assert!((95..=105).contains(&sine.len()), "{} sine messages", sine.len());
+        assert!(sine.iter().any(|v| *v > 0.5) && sine.iter().any(|v| *v < -0.5));
+
+        // The gate's starting state, then one message per edge
+        let button: Vec<f32> =
+            received.iter().filter(|(a, _)| a == "/button").map(|(_, v)| *v).collect();
+        assert_eq!(button, vec![0.0, 1.0, 0.0]);
+        assert!(received.iter().all(|(a, _)| a == "/lfo/sine" || a == "/button"));
+
+        // Clearing the patch stops the output
+        engine.clear_patch();
+        engine.process_for_test(BLOCK);
+        assert!(receive_all(&socket).is_empty());
+    }
+
+    #[test]
+    fn test_osc_out_needs_a_target_and_sources() {
+        let mut engine = GraphEngine::new();
+        assert!(engine.process_line("viz: osc_out").is_err());
+        assert!(engine.process_line("viz: osc_out 127.0.0.1:9001").is_err());
+        assert!(engine.process_line("viz: osc_out nowhere vco.freq").is_err());
+        assert!(engine.process_line("viz: osc_out 127.0.0.1:9001 vco.freq 20").is_ok());
+        assert!(engine.list_modules().is_empty());
+    }
+
+    #[test]
+    fn test_osc_faders_example() {
+        let mut runner = TestRunner::new();
+        let result = runner
+            .run_patch_file("examples/osc/faders.zim", Duration::from_secs(1))
+            .expect("example should run");
+
+        // Without a controller the faders rest at zero and the drone still plays
+        result.assert_signal_varied("vcf", "lp").unwrap();
+    }
+
+    #[test]
+    fn test_osc_visuals_example() {
+        let mut runner = TestRunner::new();
+        let result = runner
+            .run_patch_file("examples/osc/visuals.zim", Duration::from_secs(1))
+            .expect("example should run");
+
+        // Sending to a port nobody listens on leaves the patch unaffected
+        assert!(result.gate_fire_count("clk", "clock") > 0);
+        result.assert_signal_varied("amp", "out").unwrap();
+    }
+}
@@ -0,0 +1,267 @@
+//! Safety stage between the graph and the audio device
+//!
+//! Every block headed for the speakers passes through here: a DC blocker that
+//! is always on, a linked-stereo brickwall limiter that can be bypassed, and a
+//! guard that mutes the output as soon as a NaN or infinity shows up. Once
+//! muted, the stage stays silent until `unmute` is called, so a patch stuck
+//! producing garbage cannot keep reaching the device.
+
+/// Pole of the DC blocker (about 3.5Hz at 44.1kHz)
+const DC_POLE: f32 = 0.9995;
+
+/// Limiter release time constant in seconds
+const LIMITER_RELEASE: f32 = 0.1;
+
+/// Default limiter ceiling, just under full scale
+pub const DEFAULT_CEILING: f32 = 0.98;
+
+/// One-pole, one-zero DC blocking filter
+#[derive(Default)]
+struct DcBlocker {
+    x1: f32,
+    y1: f32,
+}
+
+impl DcBlocker {
+    fn process(&mut self, input: f32) -> f32 {
+        let output = input - self.x1 + DC_POLE * self.y1;
+        self.x1 = input;
+        self.y1 = output;
+        output
+    }
+}
+
+/// DC blocker, limiter and non-finite guard for the master output
+pub struct OutputStage {
+    dc_left: DcBlocker,
+    dc_right: DcBlocker,
+    limiter_enabled: bool,
+    ceiling: f32,
+    gain: f32,
+    release_coeff: f32,
+    muted: bool,
+    fault: Option<String>,
+}
+
+impl OutputStage {
+    #[must_use]
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            dc_left: DcBlocker::default(),
+            dc_right: DcBlocker::default(),
+            limiter_enabled: true,
+            ceiling: DEFAULT_CEILING,
+            gain: 1.0,
+            release_coeff: (-1.0 / (LIMITER_RELEASE * sample_rate)).exp(),
+            muted: false,
+            fault: None,
+        }
+    }
+
+    /// Process a stereo block in place
+    ///
+    /// `fault_source` names whatever first produced a non-finite value, if
+    /// known. Returns a message the first time a block is muted because of
+    /// one, so the caller can report it.
+    pub fn process(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        fault_source: Option<&str>,
+    ) -> Option<String> {
+        let mut new_fault = None;
+        if !self.muted && left.iter().chain(right.iter()).any(|v| !v.is_finite()) {
+            let message = match fault_source {
+                Some(source) => format!("non-finite value from {source}"),
+                None => "non-finite value on the output".to_string(),
+            };
+            self.muted = true;
+            self.fault = Some(message.clone());
+            new_fault = Some(message);
+        }
+
+        if self.muted {
+            left.fill(0.0);
+            right.fill(0.0);
+            self.reset();
+            return new_fault;
+        }
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            *l = self.dc_left.process(*l);
+            *r = self.dc_right.process(*r);
+
+            if self.limiter_enabled {
+                // Instant attack, smooth release, linked across both channels
+                let peak = l.abs().max(r.abs());
+                let target = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+                if target < self.gain {
+                    self.gain = target;
+                } else {
+                    self.gain = target + (self.gain - target) * self.release_coeff;
+                }
+                *l = (*l * self.gain).clamp(-self.ceiling, self.ceiling);
+                *r = (*r * self.gain).clamp(-self.ceiling, self.ceiling);
+            }
+        }
+
+        None
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.release_coeff = (-1.0 / (LIMITER_RELEASE * sample_rate)).exp();
+    }
+
+    /// Silence the output immediately and clear all filter state
+    pub fn panic(&mut self) {
+        self.muted = true;
+        self.reset();
+    }
+
+    /// Resume output after a panic or a non-finite fault
+    pub fn unmute(&mut self) {
+        self.muted = false;
+        self.fault = None;
+        self.reset();
+    }
+
+    #[must_use]
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Why the output was muted, if it was muted by a fault
+    #[must_use]
+    pub fn fault(&self) -> Option<&str> {
+        self.fault.as_deref()
+    }
+
+    pub fn set_limiter_enabled(&mut self, enabled: bool) {
+        self.limiter_enabled = enabled;
+        self.gain = 1.0;
+    }
+
+    #[must_use]
+    pub fn limiter_enabled(&self) -> bool {
+        self.limiter_enabled
+    }
+
+    fn reset(&mut self) {
+        self.dc_left = DcBlocker::default();
+        self.dc_right = DcBlocker::default();
+        self.gain = 1.0;
+    }
+}
+
+impl Default for OutputStage {
+    fn default() -> Self {
+        Self::new(44100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dc_is_removed() {
+        let mut stage = OutputStage::default();
+        let mut left = vec![0.5; 44100];
+        let mut right = vec![-0.5; 44100];
+        stage.process(&mut left, &mut right, None);
+
+        assert!(left[44099].abs() < 0.01);
+        assert!(right[44099].abs() < 0.01);
+    }
+
+    #[test]
+    fn test_limiter_holds_ceiling() {
+        let mut stage = OutputStage::default();
+        let mut left: Vec<f32> = (0..4410).map(|i| 4.0 * (i as f32 * 0.05).sin()).collect();
+        let mut right = left.clone();
+        stage.process(&mut left, &mut right, None);
+        assert!(left.iter().all(|v| v.abs() <= DEFAULT_CEILING));
+
+        // Bypassed, loud signals pass through the DC blocker untouched in level
+        stage.set_limiter_enabled(false);
+        let mut left = vec![0.0, 3.0];
+        let mut right = vec![0.0, 0.0];
+        stage.process(&mut left, &mut right, None);
+        assert!(left[1] > 2.0);
+    }
+
+    #[test]
+    fn test_non_finite_mutes_and_reports() {
+        let mut stage = OutputStage::default();
+        let mut left = vec![0.1, f32::NAN, 0.1];
+        let mut right = vec![0.1, 0.1, f32::INFINITY];
+
+        let fault = stage.process(&mut left, &mut right, Some("module 'fb' port 'out'"));
+        assert_eq!(fault.as_deref(), Some("non-finite value from module 'fb' port 'out'"));
+        assert!(stage.is_muted());
+        assert!(left.iter().chain(right.iter()).all(|&v| v == 0.0));
+
+        // Stays muted and reports only once
+        let mut left = vec![0.3; 4];
+        let mut right = vec![0.3; 4];
+        assert!(stage.process(&mut left, &mut right, None).is_none());
+        assert!(left.iter().all(|&v| v == 0.0));
+
+        stage.unmute();
+        assert!(stage.fault().is_none());
+        let mut left = vec![0.3; 4];
+        let mut right = vec![0.3; 4];
+        stage.process(&mut left, &mut right, None);
+        assert!(left[0] > 0.0);
+    }
+
+    #[test]
+    fn test_panic_silences_immediately() {
+        let mut stage = OutputStage::default();
+        stage.panic();
+        let mut left = vec![0.5; 8];
+        let mut right = vec![0.5; 8];
+        stage.process(&mut left, &mut right, None);
+
+        assert!(left.iter().chain(right.iter()).all(|&v| v == 0.0));
+        assert!(stage.fault().is_none());
+    }
+
+    #[test]
+    fn test_executor_reports_first_non_finite_module() {
+        use crate::graph::{Connection, ConnectionExpr, GraphExecutor};
+        use crate::graph_modules::{GraphConstant, GraphMonoMixer};
+
+        let mut graph = GraphExecutor::new();
+        graph.add_module("mix".to_string(), Box::new(GraphMonoMixer::new(1)));
+        graph.add_module("bad".to_string(), Box::new(GraphConstant::new(f32::INFINITY)));
+        graph.add_connection(Connection {
+            to_module: "mix".to_string(),
+            to_port: "in1".to_string(),
+            expression: ConnectionExpr::Direct {
+                module: "bad".to_string(),
+                port: "out".to_string(),
+            },
+        });
+        graph.process(64);
+
+        // The mixer goes non-finite too, but the constant is reported
+        assert_eq!(graph.nonfinite_source(), Some("module 'bad' port 'out'"));
+
+        let mut left = graph.get_output("mix", "out").unwrap().clone();
+        let mut right = left.clone();
+        let mut stage = OutputStage::default();
+        let fault = stage.process(&mut left, &mut right, graph.nonfinite_source());
+        assert_eq!(fault.as_deref(), Some("non-finite value from module 'bad' port 'out'"));
+
+        graph.clear_nonfinite();
+        assert!(graph.nonfinite_source().is_none());
+
+        // Once the module recovers, an earlier fault is no longer reported
+        graph.process(64);
+        assert!(graph.nonfinite_source().is_some());
+        graph.set_module_param("bad", "value", 0.0).unwrap();
+        graph.process(64);
+        assert!(graph.nonfinite_source().is_none());
+    }
+}
@@ -10,7 +10,14 @@ use anyhow::{anyhow, Result};
 #[derive(Debug, Clone)]
 pub enum Command {
     /// Create a new module with the given name, type, and parameters.
-    CreateModule { name: String, module_type: ModuleType, params: Vec<f32> },
+    ///
+    /// Non-numeric arguments (table names, file paths) are kept in `args`.
+    CreateModule {
+        name: String,
+        module_type: ModuleType,
+        params: Vec<f32>,
+        args: Vec<String>,
+    },
     /// Connect the output of one module to the input of another.
     Connect { from: String, to: String },
     /// Set a parameter value on a module.
@@ -64,6 +71,7 @@ pub fn parse_line(line: &str) -> Result<Command> {
 
         // For oscillators, check if waveform is specified
         let mut params: Vec<f32> = Vec::new();
+        let mut args: Vec<String> = Vec::new();
         let mut waveform: Option<String> = None;
 
         for (i, part) in parts[1..].iter().enumerate() {
@@ -77,9 +85,11 @@ pub fn parse_line(line: &str) -> Result<Command> {
                     _ => {}
                 }
             }
-            // Try to parse as number
+            // Try to parse as number, otherwise keep it as a named argument
             if let Ok(num) = part.parse::<f32>() {
                 params.push(num);
+            } else {
+                args.push((*part).to_string());
             }
         }
 
@@ -95,7 +105,7 @@ pub fn parse_line(line: &str) -> Result<Command> {
             params.insert(0, wf_code);
         }
 
-        return Ok(Command::CreateModule { name, module_type, params });
+        return Ok(Command::CreateModule { name, module_type, params, args });
     }
 
     // Parameter setting: "module.param <- value"
@@ -142,10 +152,11 @@ mod tests {
     fn test_parse_module_creation() {
         let cmd = parse_line("vco: osc 440").unwrap();
         match cmd {
-            Command::CreateModule { name, module_type, params } => {
+            Command::CreateModule { name, module_type, params, args } => {
                 assert_eq!(name, "vco");
                 assert_eq!(module_type, ModuleType::Oscillator);
                 assert_eq!(params, vec![440.0]);
+                assert!(args.is_empty());
             }
             _ => panic!("Wrong command type"),
         }
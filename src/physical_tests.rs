@@ -0,0 +1,203 @@
+//! Tests for the physical modelling string and resonator
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{GraphResonator, GraphString, ResonatorShape};
+    use crate::test_framework::{process_module, TestRunner};
+    use std::f32::consts::TAU;
+    use std::time::Duration;
+
+    const SAMPLE_RATE: f32 = 44100.0;
+
+    /// A single rising edge at the first sample
+    fn trigger(len: usize) -> Vec<f32> {
+        let mut signal = vec![0.0; len];
+        signal[0] = 1.0;
+        signal
+    }
+
+    /// Amplitude of one frequency in a signal (Goertzel)
+    fn magnitude(signal: &[f32], freq: f32) -> f32 {
+        let coeff = 2.0 * (TAU * freq / SAMPLE_RATE).cos();
+        let (mut s1, mut s2) = (0.0f32, 0.0f32);
+        for &x in signal {
+            let s = x + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s;
+        }
+        let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+        2.0 * power.max(0.0).sqrt() / signal.len() as f32
+    }
+
+    fn rms(signal: &[f32]) -> f32 {
+        (signal.iter().map(|x| x * x).sum::<f32>() / signal.len() as f32).sqrt()
+    }
+
+    /// Lag with the strongest autocorrelation within a range
+    fn period(signal: &[f32], lags: std::ops::Range<usize>) -> usize {
+        lags.max_by(|&a, &b| {
+            let corr =
+                |lag: usize| -> f32 { signal.iter().zip(&signal[lag..]).map(|(x, y)| x * y).sum() };
+            corr(a).total_cmp(&corr(b))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_string_plucks_in_tune() {
+        let len = 22050;
+        let mut string = GraphString::new(220.0);
+        let outputs = process_module(&mut string, &[("trig", trigger(len))], len);
+        let out = outputs.get("out").unwrap();
+
+        // 44100 / 220 is about 200.5 samples
+        let lag = period(&out[2205..], 150..260);
+        assert!((200..=201).contains(&lag), "period {lag}");
+
+        // Pitch input overrides the parameter
+        let mut string = GraphString::new(220.0);
+        let outputs = process_module(
+            &mut string,
+            &[("trig", trigger(len)), ("pitch", vec![330.0; len])],
+            len,
+        );
+        let lag = period(&outputs.get("out").unwrap()[2205..], 100..180);
+        assert!((133..=134).contains(&lag), "period {lag}");
+    }
+
+    #[test]
+    fn test_string_damping_shortens_ring() {
+        let len = 44100;
+        let tail = |damping: f32| {
+            let mut string = GraphString::new(220.0);
+            string.set_param("damping", damping).unwrap();
+            let outputs = process_module(&mut string, &[("trig", trigger(len))], len);
+            rms(&outputs.get("out").unwrap()[len / 2..])
+        };
+
+        assert!(tail(0.0) > 0.05);
+        assert!(tail(0.8) < 0.001);
+        assert!(tail(0.0) > tail(0.3));
+    }
+
+    #[test]
+    fn test_string_pick_position_cancels_harmonics() {
+        let len = 22050;
+        let mut string = GraphString::new(220.0);
+        string.set_param("position", 0.5).unwrap();
+        string.set_param("brightness", 1.0).unwrap();
+        let outputs = process_module(&mut string, &[("trig", trigger(len))], len);
+        let out = &outputs.get("out").unwrap()[2205..];
+
+        // Plucked in the middle, the even harmonics have a node under the pick
+        assert!(magnitude(out, 440.0) < magnitude(out, 220.0) * 0.1);
+        assert!(magnitude(out, 880.0) < magnitude(out, 660.0) * 0.1);
+    }
+
+    #[test]
+    fn test_string_rings_from_exciter() {
+        let len = 22050;
+        let mut string = GraphString::new(220.0);
+        let outputs = process_module(&mut string, &[], len);
+        assert!(outputs.get("out").unwrap().iter().all(|&v| v == 0.0));
+
+        // A click on the exciter input rings the string without a trigger
+        let mut string = GraphString::new(220.0);
+        let mut click = vec![0.0; len];
+        click[0] = 1.0;
+        let outputs = process_module(&mut string, &[("exciter", click)], len);
+        let out = &outputs.get("out").unwrap()[2205..];
+        assert!(rms(out) > 0.001);
+        assert!(magnitude(out, 220.0) > magnitude(out, 300.0) * 10.0);
+    }
+
+    #[test]
+    fn test_resonator_shape_sets_partials() {
+        let len = 22050;
+        let mut bar = GraphResonator::new(200.0, ResonatorShape::Bar);
+        bar.set_param("brightness", 1.0).unwrap();
+        let outputs = process_module(&mut bar, &[("trig", trigger(len))], len);
+        let out = outputs.get("out").unwrap();
+
+        assert!(magnitude(out, 200.0) > 0.01);
+        assert!(magnitude(out, 551.2) > 0.01);
+        assert!(magnitude(out, 400.0) < magnitude(out, 200.0) * 0.05);
+
+        let mut harmonic = GraphResonator::new(200.0, ResonatorShape::Harmonic);
+        harmonic.set_param("brightness", 1.0).unwrap();
+        let outputs = process_module(&mut harmonic, &[("trig", trigger(len))], len);
+        let out = outputs.get("out").unwrap();
+        assert!(magnitude(out, 400.0) > 0.01);
+        assert!(magnitude(out, 551.2) < magnitude(out, 400.0) * 0.05);
+
+        assert_eq!(bar.get_param("shape"), Some(1.0));
+        assert_eq!(ResonatorShape::from_name("drum"), Some(ResonatorShape::Membrane));
+    }
+
+    #[test]
+    fn test_resonator_decay_and_brightness() {
+        let len = 44100;
+        let mut resonator = GraphResonator::new(300.0, ResonatorShape::Harmonic);
+        resonator.set_param("decay", 0.25).unwrap();
+        let outputs = process_module(&mut resonator, &[("trig", trigger(len))], len);
+        let out = outputs.get("out").unwrap();
+
+        // The fundamental is 60dB down after the decay time
+        let start = rms(&out[..441]);
+        let after = rms(&out[11025..11466]);
+        assert!(after < start * 0.002, "start {start} after {after}");
+
+        // Decay CV overrides the parameter
+        let outputs = process_module(
+            &mut GraphResonator::new(300.0, ResonatorShape::Harmonic),
+            &[("trig", trigger(len)), ("decay", vec![2.0; len])],
+            len,
+        );
+        assert!(rms(&outputs.get("out").unwrap()[11025..11466]) > start * 0.1);
+
+        // Brightness balances the upper modes against the fundamental
+        let upper_share = |brightness: f32| {
+            let mut resonator = GraphResonator::new(300.0, ResonatorShape::Harmonic);
+            resonator.set_param("brightness", brightness).unwrap();
+            let outputs = process_module(&mut resonator, &[("trig", trigger(len))], len);
+            let out = outputs.get("out").unwrap();
+            magnitude(out, 1500.0) / magnitude(out, 300.0)
+        };
+        assert!(upper_share(1.0) > upper_share(0.0) * 10.0);
+    }
+
+    #[test]
+    fn test_plucks_and_strikes_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clk: clock 240
+            str: string 110
+            str.trig <- clk.clock
+            str.position <- 0.1
+            burst_env: envelope 0.001 0.01
+            burst_env.gate <- clk.eighth
+            hiss: noise
+            burst: vca
+            burst.audio <- hiss.white
+            burst.cv <- burst_env.out
+            res: resonator 330 membrane
+            res.in <- burst.out * 0.05
+            mix: mixer 2
+            mix.in1 <- str.out
+            mix.in2 <- res.out
+            out <- mix.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(1))
+            .expect("Physical modelling patch should load and run");
+
+        result.assert_signal_varied("str", "out").expect("String should ring");
+        result.assert_signal_varied("res", "out").expect("Resonator should ring");
+        result
+            .assert_signal_range("res", "out", -2.0, 2.0)
+            .expect("Resonator should stay bounded");
+    }
+}
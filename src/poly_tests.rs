@@ -0,0 +1,308 @@
+//! Tests for polyphonic cables, the voice allocator and the sum module
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{
+        Connection, ConnectionExpr, GraphExecutor, GraphModule, PortBuffers, PortDescriptor,
+    };
+    use crate::graph_modules::{
+        GraphConstant, GraphPoly, GraphStereoOutput, GraphSum, GraphVca, VoiceAllocator,
+        VoicePolicy,
+    };
+    use crate::test_framework::{process_module, TestRunner};
+    use anyhow::{anyhow, Result};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const BLOCK: usize = 64;
+
+    /// Poly source holding one constant per channel
+    struct Chord {
+        notes: Vec<f32>,
+    }
+
+    impl GraphModule for Chord {
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+
+        fn inputs(&self) -> Vec<PortDescriptor> {
+            Vec::new()
+        }
+
+        fn outputs(&self) -> Vec<PortDescriptor> {
+            vec![PortDescriptor {
+                name: "out".to_string(),
+                default_value: 0.0,
+                description: "One channel per note".to_string(),
+            }]
+        }
+
+        fn is_polyphonic(&self) -> bool {
+            true
+        }
+
+        fn output_channels(&self) -> usize {
+            self.notes.len()
+        }
+
+        fn process(&mut self, _inputs: &PortBuffers, outputs: &mut PortBuffers, n: usize) {
+            outputs.set_channels("out", self.notes.len(), n);
+            for (channel, note) in self.notes.iter().enumerate() {
+                outputs.channel_mut("out", channel).unwrap().fill(*note);
+            }
+        }
+
+        fn set_param(&mut self, name: &str, _value: f32) -> Result<()> {
+            Err(anyhow!("Unknown parameter: {name}"))
+        }
+
+        fn get_param(&self, _name: &str) -> Option<f32> {
+            None
+        }
+    }
+
+    fn connect(graph: &mut GraphExecutor, to: &str, port: &str, expression: ConnectionExpr) {
+        graph.add_connection(Connection {
+            to_module: to.to_string(),
+            to_port: port.to_string(),
+            expression,
+        });
+    }
+
+    fn direct(module: &str, port: &str) -> ConnectionExpr {
+        ConnectionExpr::Direct {
+            module: module.to_string(),
+            port: port.to_string(),
+        }
+    }
+
+    fn chord_graph(notes: &[f32]) -> GraphExecutor {
+        let mut graph = GraphExecutor::new();
+        graph.add_module("chord".to_string(), Box::new(Chord { notes: notes.to_vec() }));
+        graph.add_module("vca".to_string(), Box::new(GraphVca::new(1.0)));
+        graph.set_module_factory("vca", Arc::new(|| Ok(Box::new(GraphVca::new(1.0)))));
+        graph
+    }
+
+    #[test]
+    fn test_allocator_round_robin() {
+        let mut voices = VoiceAllocator::new(3, VoicePolicy::RoundRobin);
+        assert_eq!(voices.note_on(60, 1.0, 1.0), Some(0));
+        assert_eq!(voices.note_on(64, 2.0, 1.0), Some(1));
+
+        // A released voice is skipped until the rotation comes back to it
+        voices.note_off(60);
+        assert_eq!(voices.note_on(67, 3.0, 1.0), Some(2));
+        assert_eq!(voices.note_on(71, 4.0, 1.0), Some(0));
+
+        // All busy: the next voice in turn is stolen and its gate drops
+        assert_eq!(voices.note_on(72, 5.0, 0.5), Some(1));
+        assert_eq!(voices.gate(1), 0.0);
+        assert_eq!(voices.gate(1), 1.0);
+        assert_eq!(voices.voices()[1].key, 72);
+        assert_eq!(voices.voices()[1].velocity, 0.5);
+
+        // Released voices keep their pitch for the tail
+        voices.note_off(72);
+        assert_eq!(voices.gate(1), 0.0);
+        assert_eq!(voices.voices()[1].pitch, 5.0);
+    }
+
+    #[test]
+    fn test_allocator_note_restarted_as_it_is_released() {
+        let mut voices = VoiceAllocator::new(1, VoicePolicy::RoundRobin);
+        voices.note_on(60, 1.0, 1.0);
+        assert_eq!(voices.gate(0), 1.0);
+
+        // Off and on again within one sample: the gate still drops once
+        voices.note_off(60);
+        assert_eq!(voices.note_on(60, 1.0, 0.8), Some(0));
+        assert_eq!(voices.gate(0), 0.0);
+        assert_eq!(voices.gate(0), 1.0);
+
+        // After a release that was heard, the next note opens the gate at once
+        voices.note_off(60);
+        assert_eq!(voices.gate(0), 0.0);
+        voices.note_on(62, 2.0, 1.0);
+        assert_eq!(voices.gate(0), 1.0);
+    }
+
+    #[test]
+    fn test_allocator_steal_policies() {
+        let mut voices = VoiceAllocator::new(2, VoicePolicy::StealOldest);
+        voices.note_on(1, 1.0, 1.0);
+        voices.note_on(2, 2.0, 1.0);
+        voices.note_on(1, 1.0, 1.0); // re-struck, so note 2 is now the oldest
+        assert_eq!(voices.note_on(3, 3.0, 1.0), Some(1));
+
+        let mut voices = VoiceAllocator::new(2, VoicePolicy::NoSteal);
+        voices.note_on(1, 1.0, 1.0);
+        voices.note_on(2, 2.0, 1.0);
+        assert_eq!(voices.note_on(3, 3.0, 1.0), None);
+        assert!(voices.voices().iter().all(|v| v.key != 3));
+
+        assert_eq!(VoicePolicy::from_name("rr"), Some(VoicePolicy::RoundRobin));
+        assert_eq!(VoicePolicy::from_param(VoicePolicy::NoSteal.as_param()), VoicePolicy::NoSteal);
+        assert_eq!(VoicePolicy::from_name("loudest"), None);
+    }
+
+    #[test]
+    fn test_poly_spreads_mono_gates_across_voices() {
+        let mut poly = GraphPoly::new(3, VoicePolicy::RoundRobin);
+        let mut gate = vec![0.0; 400];
+        let mut pitch = vec![0.0; 400];
+        for note in 0..4 {
+            let start = note * 100;
+            gate[start + 10..start + 60].fill(1.0);
+            pitch[start..start + 100].fill(100.0 * (note + 1) as f32);
+        }
+
+        let outputs = process_module(
+            &mut poly,
+            &[("gate", gate), ("pitch", pitch), ("velocity", vec![0.7; 400])],
+            400,
+        );
+        assert_eq!(outputs.channels("gate"), 3);
+
+        let voice = |port: &str, v: usize| outputs.channel(port, v).unwrap().to_vec();
+        assert_eq!(voice("gate", 0)[20], 1.0);
+        assert_eq!(voice("gate", 1)[120], 1.0);
+        assert_eq!(voice("gate", 2)[220], 1.0);
+        assert_eq!(voice("gate", 0)[80], 0.0);
+        assert_eq!(voice("velocity", 1)[120], 0.7);
+
+        // Pitch holds through the release, the fourth note wraps to voice 0
+        assert_eq!(voice("pitch", 1)[199], 200.0);
+        assert_eq!(voice("pitch", 0)[320], 400.0);
+        assert_eq!(voice("gate", 0)[320], 1.0);
+    }
+
+    #[test]
+    fn test_mono_module_runs_per_channel() {
+        let mut graph = chord_graph(&[0.25, 0.5, 1.0]);
+        graph.set_module_param("vca", "gain", 2.0).unwrap();
+        connect(&mut graph, "vca", "audio", direct("chord", "out"));
+        connect(&mut graph, "vca", "cv", direct("chord", "out"));
+        graph.build_voices();
+        graph.process(BLOCK);
+
+        // Each voice squares its own note, with the gain set before it existed
+        let out = graph.get_output("vca", "out").unwrap();
+        assert_eq!(out.len(), 3 * BLOCK);
+        for (channel, note) in [0.25f32, 0.5, 1.0].iter().enumerate() {
+            let sample = out[channel * BLOCK + 10];
+            assert!((sample - note * note * 2.0).abs() < 1e-6, "channel {channel}: {sample}");
+        }
+
+        // Parameters set later reach every voice
+        graph.set_module_param("vca", "gain", 1.0).unwrap();
+        graph.process(BLOCK);
+        let out = graph.get_output("vca", "out").unwrap();
+        assert!((out[2 * BLOCK + 10] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sum_expression_spreads_mono_terms() {
+        let mut graph = chord_graph(&[0.1, 0.2]);
+        graph.add_module("sum".to_string(), Box::new(GraphSum::new(1.0)));
+        graph.add_module("half".to_string(), Box::new(GraphConstant::new(0.5)));
+        // Mono terms and offsets reach every channel
+        connect(
+            &mut graph,
+            "vca",
+            "audio",
+            ConnectionExpr::Offset {
+                expr: Box::new(direct("chord", "out")),
+                offset: 1.0,
+            },
+        );
+        connect(
+            &mut graph,
+            "vca",
+            "cv",
+            ConnectionExpr::Sum {
+                exprs: vec![direct("chord", "out"), direct("half", "out")],
+            },
+        );
+        connect(&mut graph, "sum", "in", direct("vca", "out"));
+        graph.build_voices();
+        graph.process(BLOCK);
+
+        let sum = graph.get_output("sum", "out").unwrap();
+        assert_eq!(sum.len(), BLOCK);
+        let expected = 1.1 * 0.6 + 1.2 * 0.7;
+        assert!((sum[5] - expected).abs() < 1e-6, "sum {}", sum[5]);
+    }
+
+    #[test]
+    fn test_voices_are_built_outside_processing() {
+        let built = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&built);
+        let mut graph = chord_graph(&[0.1, 0.2, 0.3, 0.4]);
+        graph.set_module_factory(
+            "vca",
+            Arc::new(move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+                Ok(Box::new(GraphVca::new(1.0)))
+            }),
+        );
+        graph.add_module("lfo".to_string(), Box::new(GraphConstant::new(1.0)));
+        connect(&mut graph, "vca", "audio", direct("chord", "out"));
+        connect(&mut graph, "vca", "cv", direct("lfo", "out"));
+
+        // Processing never calls the factory; without voices only channel 0 plays
+        graph.process(BLOCK);
+        assert_eq!(built.load(Ordering::Relaxed), 0);
+        assert_eq!(graph.get_output("vca", "out").unwrap().len(), BLOCK);
+
+        let missing = graph.missing_voices();
+        assert_eq!(missing.len(), 1);
+        assert_eq!((missing[0].0.as_str(), missing[0].1), ("vca", 3));
+        graph.build_voices();
+        assert_eq!(built.load(Ordering::Relaxed), 3);
+        assert!(graph.missing_voices().is_empty());
+
+        graph.process(BLOCK);
+        let out = graph.get_output("vca", "out").unwrap();
+        assert_eq!(out.len(), 4 * BLOCK);
+        assert!((out[3 * BLOCK] - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mono_without_factory_uses_first_channel() {
+        let mut graph = GraphExecutor::new();
+        graph.add_module("chord".to_string(), Box::new(Chord { notes: vec![0.3, 0.9] }));
+        graph.add_module("vca".to_string(), Box::new(GraphVca::new(1.0)));
+        connect(&mut graph, "vca", "audio", direct("chord", "out"));
+        connect(&mut graph, "vca", "cv", direct("chord", "out"));
+        graph.process(BLOCK);
+
+        let out = graph.get_output("vca", "out").unwrap();
+        assert_eq!(out.len(), BLOCK);
+        assert!((out[0] - 0.09).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stereo_output_sums_channels() {
+        let mut graph = chord_graph(&[0.1, 0.2, 0.3]);
+        graph.add_module("_output".to_string(), Box::new(GraphStereoOutput::new()));
+        connect(&mut graph, "_output", "mono", direct("chord", "out"));
+        graph.process(BLOCK);
+
+        let left = graph.get_output("_output", "left").unwrap();
+        assert!((left[0] - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_poly_chords_example() {
+        let mut runner = TestRunner::new();
+        let result = runner
+            .run_patch_file("examples/poly/poly_chords.zim", Duration::from_secs(2))
+            .expect("example should run");
+
+        result.assert_signal_varied("voices", "out").unwrap();
+        assert!(result.gate_fire_count("alloc", "gate") > 0);
+    }
+}
@@ -0,0 +1,173 @@
+//! Tests for the random and chaos modulation sources
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{ChaosKind, GraphChaos, GraphRandom};
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    const SAMPLE_RATE: usize = 44100;
+
+    fn pulses(len: usize, positions: &[usize]) -> Vec<f32> {
+        let mut signal = vec![0.0; len];
+        for &position in positions {
+            signal[position] = 1.0;
+        }
+        signal
+    }
+
+    fn triggers(signal: &[f32]) -> Vec<usize> {
+        (0..signal.len()).filter(|&i| signal[i] > 0.5).collect()
+    }
+
+    #[test]
+    fn test_random_steps_at_rate() {
+        let mut random = GraphRandom::new(10.0, 7);
+        let outputs = process_module(&mut random, &[], SAMPLE_RATE);
+        let stepped = outputs.get("stepped").unwrap();
+        let steps = triggers(outputs.get("trigger").unwrap());
+
+        // The tenth step lands on the last sample, give or take rounding
+        assert!((9..=10).contains(&steps.len()), "steps {}", steps.len());
+        assert!(stepped.iter().all(|v| (-1.0..=1.0).contains(v)));
+
+        // Held between steps, new value on each step
+        let (first, second) = (steps[0], steps[1]);
+        assert!(stepped[first..second].iter().all(|&v| v == stepped[first]));
+        assert_ne!(stepped[first - 1], stepped[first]);
+
+        // Rate CV adds to the parameter
+        let mut random = GraphRandom::new(10.0, 7);
+        let outputs =
+            process_module(&mut random, &[("rate", vec![10.0; SAMPLE_RATE])], SAMPLE_RATE);
+        let steps = triggers(outputs.get("trigger").unwrap()).len();
+        assert!((19..=20).contains(&steps), "steps {steps}");
+    }
+
+    #[test]
+    fn test_random_is_reproducible_from_seed() {
+        let run = |seed: u32| {
+            let mut random = GraphRandom::new(20.0, seed);
+            process_module(&mut random, &[], 4410).get("stepped").unwrap().clone()
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+
+        // Reseeding restarts the sequence
+        let mut random = GraphRandom::new(20.0, 42);
+        process_module(&mut random, &[], 4410);
+        random.set_param("seed", 42.0).unwrap();
+        let again = process_module(&mut random, &[], 4410);
+        assert_eq!(again.get("stepped").unwrap(), &run(42));
+    }
+
+    #[test]
+    fn test_random_smooth_and_slewed_outputs() {
+        let mut random = GraphRandom::new(5.0, 99);
+        random.set_param("slew", 0.01).unwrap();
+        let outputs = process_module(&mut random, &[], SAMPLE_RATE);
+        let stepped = outputs.get("stepped").unwrap();
+        let smooth = outputs.get("smooth").unwrap();
+        let slewed = outputs.get("slewed").unwrap();
+        let steps = triggers(outputs.get("trigger").unwrap());
+
+        // Smooth never jumps and lands on each value as the next step begins
+        assert!(smooth.windows(2).all(|w| (w[1] - w[0]).abs() < 0.001));
+        for pair in steps.windows(2) {
+            assert!((smooth[pair[1] - 1] - stepped[pair[0]]).abs() < 0.001);
+        }
+
+        // Slewed catches up with the stepped value well within a step
+        for &step in &steps[..steps.len() - 1] {
+            assert!((slewed[step + 4410] - stepped[step]).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_random_trigger_input() {
+        let len = 4410;
+        let mut random = GraphRandom::new(0.0, 5);
+        let outputs =
+            process_module(&mut random, &[("trig", pulses(len, &[100, 1100, 3100]))], len);
+        let stepped = outputs.get("stepped").unwrap();
+
+        assert_eq!(triggers(outputs.get("trigger").unwrap()), vec![100, 1100, 3100]);
+        assert_ne!(stepped[99], stepped[100]);
+        assert_eq!(stepped[100], stepped[1099]);
+
+        // Smooth glides over the previous trigger interval
+        let smooth = outputs.get("smooth").unwrap();
+        assert!((smooth[1100 + 999] - stepped[1100]).abs() < 0.001);
+        assert!((smooth[1100 + 500] - stepped[1100]).abs() > 0.0);
+    }
+
+    #[test]
+    fn test_chaos_stays_on_attractor() {
+        let len = SAMPLE_RATE * 10;
+        let mut chaos = GraphChaos::new(ChaosKind::Lorenz, 5.0, 1);
+        let outputs = process_module(&mut chaos, &[], len);
+
+        for axis in ["x", "y", "z"] {
+            let signal = outputs.get(axis).unwrap();
+            assert!(signal.iter().all(|v| v.is_finite() && v.abs() < 1.5), "{axis} bounded");
+        }
+
+        // Lorenz x swaps between the two lobes
+        let x = outputs.get("x").unwrap();
+        let crossings = x.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        assert!(crossings > 5, "lobe changes {crossings}");
+
+        let mut rossler = GraphChaos::new(ChaosKind::Rossler, 5.0, 1);
+        let outputs = process_module(&mut rossler, &[], len);
+        assert!(outputs.get("x").unwrap().iter().all(|v| v.abs() < 1.5));
+        assert_eq!(ChaosKind::from_name("roessler"), Some(ChaosKind::Rossler));
+    }
+
+    #[test]
+    fn test_chaos_seed_rate_and_parameters() {
+        let run = |seed: u32, cv: &[(&str, Vec<f32>)]| {
+            let mut chaos = GraphChaos::new(ChaosKind::Lorenz, 2.0, seed);
+            process_module(&mut chaos, cv, SAMPLE_RATE).get("x").unwrap().clone()
+        };
+        assert_eq!(run(3, &[]), run(3, &[]));
+        assert_ne!(run(3, &[]), run(4, &[]));
+
+        // Rate 0 freezes the system
+        let mut chaos = GraphChaos::new(ChaosKind::Lorenz, 0.0, 3);
+        let outputs = process_module(&mut chaos, &[], 100);
+        let x = outputs.get("x").unwrap();
+        assert!(x.iter().all(|&v| v == x[0]));
+
+        // Rho below one has a single stable point at the origin
+        let settled = run(3, &[("b", vec![-27.5; SAMPLE_RATE]), ("rate", vec![8.0; SAMPLE_RATE])]);
+        assert!(settled[SAMPLE_RATE - 1].abs() < 0.01);
+    }
+
+    #[test]
+    fn test_random_and_chaos_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            rnd: random 4 42
+            att: chaos 0.3 7 lorenz
+            vco: osc sine 220
+            vco.freq <- rnd.smooth * 100 + 330
+            vcf: filter 1000 0.4
+            vcf.audio <- vco.sine
+            vcf.cutoff <- att.x * 600 + 1200
+            out <- vcf.lp
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Random and chaos patch should load and run");
+
+        result.assert_gate_fired("rnd", "trigger").expect("Random should step");
+        result.assert_signal_varied("rnd", "smooth").expect("Smooth random should move");
+        result.assert_signal_varied("att", "x").expect("Attractor should move");
+        result
+            .assert_signal_range("att", "z", -1.5, 1.5)
+            .expect("Attractor should stay bounded");
+    }
+}
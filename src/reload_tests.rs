@@ -0,0 +1,239 @@
+//! Tests for reloading a patch while it plays
+
+#[cfg(test)]
+mod tests {
+    use crate::graph_engine::GraphEngine;
+    use crate::observability::{GateEvent, ParameterEvent, SignalEvent, SignalObserver};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const BLOCK: usize = 64;
+
+    const PATCH: &str = "
+lfo: lfo 1
+clk: clock 120
+seq: seq8
+seq.clock <- clk.clock
+seq.step1 <- 100
+seq.step2 <- 200
+seq.step3 <- 300
+vco: osc sine 220
+vco.freq <- seq.cv
+out <- vco.sine
+";
+
+    fn run(engine: &GraphEngine, blocks: usize) {
+        for _ in 0..blocks {
+            engine.process_for_test(BLOCK);
+        }
+    }
+
+    fn output(engine: &GraphEngine, module: &str, port: &str) -> Vec<f32> {
+        engine.observer_manager_mut().get_output(module, port).unwrap().to_vec()
+    }
+
+    fn param(engine: &GraphEngine, module: &str, name: &str) -> Option<f32> {
+        engine.observer_manager_mut().get_module_mut(module).unwrap().get_param(name)
+    }
+
+    #[test]
+    fn test_reload_keeps_unchanged_modules_running() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch(PATCH).unwrap();
+        run(&engine, 200);
+        let ramp = *output(&engine, "lfo", "ramp").last().unwrap();
+        let step = output(&engine, "seq", "cv")[BLOCK - 1];
+        assert!(ramp > 0.2, "lfo should be well into its cycle: {ramp}");
+
+        // Swap the oscillator for a different one and slow the lfo
+        let edited = PATCH.replace("vco: osc sine 220", "vco: osc saw 220").replace(
+            "out <- vco.sine",
+            "lfo.freq <- 0.5\nvcf: filter 800 0.2\nvcf.audio <- vco.saw\nout <- vcf.lp",
+        );
+        let summary = engine.reload_patch(&edited).unwrap();
+        assert_eq!(summary.kept, vec!["clk", "lfo", "seq"]);
+        assert_eq!(summary.added, vec!["vcf", "vco"]);
+        assert!(summary.removed.is_empty());
+        assert!(!summary.crossfaded);
+
+        // The lfo carries on from where it was, at its new rate
+        run(&engine, 1);
+        let next = output(&engine, "lfo", "ramp")[0];
+        assert!((next - ramp).abs() < 0.001, "lfo restarted: {ramp} -> {next}");
+        assert_eq!(param(&engine, "lfo", "freq"), Some(0.5));
+        assert_eq!(output(&engine, "seq", "cv")[0], step);
+        assert!(output(&engine, "vcf", "lp").iter().any(|v| *v != 0.0));
+    }
+
+    #[test]
+    fn test_reload_replaces_changed_and_removes_missing() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch(PATCH).unwrap();
+        run(&engine, 100);
+
+        // A new module type means a fresh module; lfo and seq are gone
+        let edited =
+            "clk: lfo 3\nvco: osc sine 220\nvco.freq <- clk.sine * 100 + 300\nout <- vco.sine";
+        let summary = engine.reload_patch(edited).unwrap();
+        assert_eq!(summary.kept, vec!["vco"]);
+        assert_eq!(summary.added, vec!["clk"]);
+        assert_eq!(summary.removed, vec!["lfo", "seq"]);
+        assert_eq!(summary.to_string(), "kept 1, added 1, removed 2");
+
+        run(&engine, 1);
+        assert!(output(&engine, "clk", "ramp")[0] < 0.001);
+        let mut modules = engine.list_modules();
+        modules.sort();
+        assert_eq!(modules, vec!["_output", "clk", "vco"]);
+    }
+
+    #[test]
+    fn test_reload_retunes_changed_creation_values() {
+        let patch = "dc: constant 1\nvcf: filter 800\nvcf.audio <- dc.out\n\
+                     mix: mixer 2\nmix.in1 <- vcf.lp\nout <- mix.out";
+        let mut engine = GraphEngine::new();
+        engine.load_patch(patch).unwrap();
+        run(&engine, 50);
+        let settled = *output(&engine, "vcf", "lp").last().unwrap();
+        assert!(settled > 0.9, "filter should have settled: {settled}");
+
+        // A new cutoff is set in place; a new input count needs a new mixer
+        let edited = patch.replace("filter 800", "filter 900").replace("mixer 2", "mixer 3");
+        let summary = engine.reload_patch(&edited).unwrap();
+        assert_eq!(summary.kept, vec!["dc", "vcf"]);
+        assert_eq!(summary.added, vec!["mix"]);
+        assert_eq!(param(&engine, "vcf", "cutoff"), Some(900.0));
+
+        // The filter keeps its state instead of starting again from silence
+        run(&engine, 1);
+        let next = output(&engine, "vcf", "lp")[0];
+        assert!((next - settled).abs() < 0.01, "filter restarted: {settled} -> {next}");
+    }
+
+    #[test]
+    fn test_reload_does_not_rebuild_kept_modules() {
+        // The file is gone by the time of the reload, so a rebuild would fail
+        let path = std::env::temp_dir().join("zim_test_reload_kept.mid");
+        std::fs::copy("examples/midi/groove.mid", &path).unwrap();
+        let patch = format!("song: midifile {}\nout <- song.gate1", path.display());
+        let mut engine = GraphEngine::new();
+        engine.load_patch(&patch).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let summary = engine.reload_patch(&format!("{patch}\nlfo: lfo 2")).unwrap();
+        assert_eq!(summary.kept, vec!["song"]);
+        assert_eq!(summary.added, vec!["lfo"]);
+
+        // A changed argument still needs the file
+        let looped = patch.replace("\nout", " once\nout");
+        assert!(engine.reload_patch(&looped).is_err());
+    }
+
+    #[test]
+    fn test_reload_resets_params_the_patch_no_longer_sets() {
+        let patch = "vco: osc sine 220\nvcf: filter 800 0.2\nvcf.audio <- vco.sine\nout <- vcf.lp";
+        let mut engine = GraphEngine::new();
+        engine.load_patch(&format!("{patch}\nvcf.res <- 0.7")).unwrap();
+        engine.process_line("vco.freq <- 330").unwrap();
+        run(&engine, 10);
+
+        // Both modules are kept but set as the new patch builds them
+        let summary = engine.reload_patch(patch).unwrap();
+        assert_eq!(summary.kept, vec!["vcf", "vco"]);
+        assert_eq!(param(&engine, "vcf", "res"), Some(0.2));
+        assert_eq!(param(&engine, "vco", "freq"), Some(220.0));
+        let text = engine.patch_text().unwrap();
+        assert!(!text.contains("vcf.res"), "{text}");
+        assert!(!text.contains("vco.freq"), "{text}");
+    }
+
+    #[test]
+    fn test_reload_keeps_added_observers() {
+        struct CycleCounter(Arc<AtomicUsize>);
+
+        impl SignalObserver for CycleCounter {
+            fn observe_signal(&mut self, _event: &SignalEvent) {}
+            fn observe_gate(&mut self, _event: &GateEvent) {}
+            fn observe_parameter(&mut self, _event: &ParameterEvent) {}
+            fn begin_process_cycle(&mut self, _cycle: usize) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let cycles = Arc::new(AtomicUsize::new(0));
+        let mut engine = GraphEngine::new();
+        engine.load_patch(PATCH).unwrap();
+        engine.add_observer(Box::new(CycleCounter(Arc::clone(&cycles))));
+        run(&engine, 2);
+
+        engine.reload_patch(&PATCH.replace("lfo 1", "lfo 2")).unwrap();
+        run(&engine, 3);
+        assert_eq!(cycles.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_failed_reload_keeps_old_patch() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch(PATCH).unwrap();
+        run(&engine, 10);
+
+        let broken = format!("{PATCH}\nbad: no_such_module");
+        assert!(engine.reload_patch(&broken).is_err());
+        assert_eq!(engine.list_modules().len(), 5);
+
+        // A later good edit still sees the original modules as kept
+        let summary = engine.reload_patch(PATCH).unwrap();
+        assert_eq!(summary.kept.len(), 4);
+        assert!(summary.added.is_empty());
+    }
+
+    #[test]
+    fn test_full_rebuild_crossfades() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch("a: osc sine 440\nout <- a.sine").unwrap();
+        let (before, _) = engine.render_for_test(BLOCK);
+        let last = before[BLOCK - 1];
+
+        let summary = engine.reload_patch("b: osc saw 55\nout <- b.saw").unwrap();
+        assert!(summary.crossfaded);
+        assert_eq!(summary.removed, vec!["a"]);
+
+        // The first sample still follows the old sine rather than jumping to the saw
+        let (during, _) = engine.render_for_test(BLOCK);
+        assert!((during[0] - last).abs() < 0.1, "jump from {last} to {}", during[0]);
+
+        // After 50ms only the new patch is heard
+        assert!(!engine.release_crossfade(), "fade still playing");
+        for _ in 0..40 {
+            engine.render_for_test(BLOCK);
+        }
+        let (after, _) = engine.render_for_test(BLOCK);
+        let direct = output(&engine, "_output", "left");
+        assert_eq!(after, direct);
+
+        // The audio side leaves the spent graph for the control thread
+        assert!(engine.release_crossfade());
+        assert!(!engine.release_crossfade());
+    }
+
+    #[test]
+    fn test_output_routing_change_crossfades() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch("a: osc sine 440\nout <- a.sine").unwrap();
+        let (before, _) = engine.render_for_test(BLOCK);
+        let last = before[BLOCK - 1];
+        assert!(last.abs() > 0.1, "sine should be mid-swing: {last}");
+
+        // Dropping `out` altogether still fades the old patch away
+        let summary = engine.reload_patch("b: osc saw 55").unwrap();
+        assert!(summary.crossfaded);
+        let (during, _) = engine.render_for_test(BLOCK);
+        assert!((during[0] - last).abs() < 0.1, "jump from {last} to {}", during[0]);
+
+        // And a patch that adds `out` is heard without restarting anything
+        let summary = engine.reload_patch("b: osc saw 55\nout <- b.saw").unwrap();
+        assert_eq!(summary.kept, vec!["b"]);
+        let (after, _) = engine.render_for_test(BLOCK);
+        assert!(after.iter().any(|v| *v != 0.0));
+    }
+}
@@ -0,0 +1,363 @@
+//! Tests for clock and rhythm generator modules
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{Connection, ConnectionExpr, GraphExecutor, GraphModule};
+    use crate::graph_engine::GraphEngine;
+    use crate::graph_modules::{
+        BernoulliMode, GraphBernoulli, GraphClock, GraphEuclid, GraphSeq8, GraphTuring,
+    };
+    use crate::test_framework::{process_module, TestRunner};
+    use std::time::Duration;
+
+    /// Sample indices where a gate buffer goes from low to high
+    fn rising_edges(buffer: &[f32]) -> Vec<usize> {
+        let mut previous = 0.0;
+        let mut edges = Vec::new();
+        for (i, &value) in buffer.iter().enumerate() {
+            if value > 0.5 && previous <= 0.5 {
+                edges.push(i);
+            }
+            previous = value;
+        }
+        edges
+    }
+
+    fn assert_edges_near(actual: &[usize], expected: &[usize]) {
+        assert_eq!(actual.len(), expected.len(), "edges {actual:?} vs {expected:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(a.abs_diff(*e) <= 1, "edges {actual:?} vs {expected:?}");
+        }
+    }
+
+    /// Clock with `pulses` two-sample pulses, four samples apart
+    fn pulse_train(pulses: usize) -> Vec<f32> {
+        (0..pulses * 4).map(|i| if i % 4 < 2 { 1.0 } else { 0.0 }).collect()
+    }
+
+    /// Render a gate output as one character per clock pulse
+    fn pattern(buffer: &[f32]) -> String {
+        buffer.iter().step_by(4).map(|&v| if v > 0.5 { 'x' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn test_clock_divisions() {
+        let mut clock = GraphClock::new(120.0);
+        let outputs = process_module(&mut clock, &[], 88200);
+
+        // 120 BPM: one beat every 22050 samples
+        assert_edges_near(&rising_edges(outputs.get("clock").unwrap()), &[0, 22050, 44100, 66150]);
+        assert_edges_near(&rising_edges(outputs.get("bar").unwrap()), &[0]);
+        assert_edges_near(&rising_edges(outputs.get("half").unwrap()), &[0, 44100]);
+        assert_eq!(rising_edges(outputs.get("eighth").unwrap()).len(), 8);
+        assert_eq!(rising_edges(outputs.get("sixteenth").unwrap()).len(), 16);
+        assert_edges_near(
+            &rising_edges(outputs.get("quarter_t").unwrap()),
+            &[0, 14700, 29400, 44100, 58800, 73500],
+        );
+        assert_eq!(rising_edges(outputs.get("eighth_t").unwrap()).len(), 12);
+    }
+
+    #[test]
+    fn test_clock_swing_delays_offbeats() {
+        let mut clock = GraphClock::new(120.0);
+        clock.set_param("swing", 0.5).unwrap();
+        let outputs = process_module(&mut clock, &[], 22050);
+
+        // Eighth period is 11025 samples; the offbeat moves a quarter period later
+        assert_edges_near(&rising_edges(outputs.get("eighth").unwrap()), &[0, 13781]);
+        // Downbeats stay put
+        assert_edges_near(&rising_edges(outputs.get("clock").unwrap()), &[0]);
+    }
+
+    #[test]
+    fn test_clock_run_and_reset() {
+        let mut clock = GraphClock::new(120.0);
+        let mut run = vec![1.0; 44100];
+        run[15000..20000].fill(0.0);
+        let mut reset = vec![0.0; 44100];
+        reset[40000] = 1.0;
+
+        let outputs = process_module(&mut clock, &[("run", run), ("reset", reset)], 44100);
+        let out = outputs.get("clock").unwrap();
+
+        // Stopped while run is low
+        assert!(out[15000..20000].iter().all(|&v| v == 0.0));
+        // The second beat arrives 5000 samples late, then reset restarts the beat
+        assert_edges_near(&rising_edges(out), &[0, 27050, 40000]);
+    }
+
+    #[test]
+    fn test_clock_tap_tempo() {
+        let mut clock = GraphClock::new(120.0);
+        let mut tap = vec![0.0; 44100];
+        for start in [0, 11025, 22050, 33075] {
+            tap[start..start + 100].fill(1.0);
+        }
+        process_module(&mut clock, &[("tap", tap)], 44100);
+
+        // Taps every quarter second are 240 BPM
+        assert!((clock.get_param("bpm").unwrap() - 240.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_clock_drives_sequencer_in_same_buffer() {
+        let mut executor = GraphExecutor::new();
+        // Names chosen so alphabetical order would run the sequencer first
+        executor.add_module("z_clock".to_string(), Box::new(GraphClock::new(120.0)));
+        executor.add_module("a_seq".to_string(), Box::new(GraphSeq8::new()));
+        executor.add_connection(Connection {
+            to_module: "a_seq".to_string(),
+            to_port: "clock".to_string(),
+            expression: ConnectionExpr::Direct {
+                module: "z_clock".to_string(),
+                port: "clock".to_string(),
+            },
+        });
+
+        executor.process(512);
+
+        // The sequencer sees the first clock edge at sample 0, not a buffer later
+        assert_eq!(executor.get_output("a_seq", "gate").unwrap()[0], 1.0);
+    }
+
+    #[test]
+    fn test_modules_fed_by_a_feedback_loop_run_after_it() {
+        let mut engine = GraphEngine::new();
+        // a_tap sorts first alphabetically but listens to the loop
+        engine
+            .load_patch(
+                "z_src: constant 0.5
+m_loop: mixer 2
+n_loop: mixer 1
+a_tap: mixer 1
+m_loop.in1 <- z_src.out
+m_loop.in2 <- n_loop.out * 0.5
+n_loop.in1 <- m_loop.out
+a_tap.in1 <- m_loop.out",
+            )
+            .unwrap();
+        engine.process_for_test(64);
+
+        let graph = engine.observer_manager_mut();
+        let tap = graph.get_output("a_tap", "out").unwrap();
+        let looped = graph.get_output("m_loop", "out").unwrap();
+        assert!(looped[0] > 0.0);
+        assert_eq!(tap[0], looped[0], "a_tap read the loop a buffer late");
+    }
+
+    #[test]
+    fn test_clock_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clk: clock 120
+            div: clockdiv 2
+            div.clock <- clk.clock
+            seq: seq8
+            seq.clock <- clk.eighth
+            out <- seq.cv
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Clock patch should load and run");
+
+        assert_eq!(result.gate_fire_count("clk", "clock"), 4);
+        assert_eq!(result.gate_fire_count("seq", "gate"), 8);
+        result.assert_gate_fired("div", "gate").expect("Divided clock should fire");
+    }
+
+    #[test]
+    fn test_euclid_patterns() {
+        let mut tresillo = GraphEuclid::new(8.0, 3.0, 0.0);
+        let outputs = process_module(&mut tresillo, &[("clock", pulse_train(16))], 64);
+        assert_eq!(pattern(outputs.get("gate").unwrap()), "x..x..x.x..x..x.");
+        assert_eq!(pattern(outputs.get("accent").unwrap()), "x.......x.......");
+
+        let mut rotated = GraphEuclid::new(8.0, 3.0, 1.0);
+        let outputs = process_module(&mut rotated, &[("clock", pulse_train(8))], 32);
+        assert_eq!(pattern(outputs.get("gate").unwrap()), ".x..x..x");
+        // Accent follows the first hit after the top of the pattern
+        assert_eq!(pattern(outputs.get("accent").unwrap()), ".x......");
+    }
+
+    #[test]
+    fn test_euclid_cv_and_reset() {
+        let mut euclid = GraphEuclid::new(4.0, 1.0, 0.0);
+        let mut reset = vec![0.0; 32];
+        reset[17] = 1.0; // Between the fifth and sixth pulse
+        let outputs = process_module(
+            &mut euclid,
+            &[("clock", pulse_train(8)), ("fills", vec![1.0; 32]), ("reset", reset)],
+            32,
+        );
+
+        // Two fills over four steps, restarting after the reset
+        assert_eq!(pattern(outputs.get("gate").unwrap()), "x.x.xx.x");
+    }
+
+    #[test]
+    fn test_euclid_polyrhythm_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clk: clock 120
+            kick: euclid 16 5
+            kick.clock <- clk.sixteenth
+            hat: euclid 12 7 2
+            hat.clock <- clk.eighth_t
+            out <- kick.gate
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Euclid patch should load and run");
+
+        // Two seconds is one bar: 5 of 16 sixteenths and 7 of 12 triplets
+        assert_eq!(result.gate_fire_count("kick", "gate"), 5);
+        assert_eq!(result.gate_fire_count("hat", "gate"), 7);
+    }
+
+    /// Sample a CV output once per clock pulse
+    fn per_pulse(buffer: &[f32]) -> Vec<f32> {
+        buffer.iter().step_by(4).copied().collect()
+    }
+
+    #[test]
+    fn test_turing_locked_loop_repeats() {
+        let mut turing = GraphTuring::new(8, 42);
+        let outputs = process_module(&mut turing, &[("clock", pulse_train(32))], 128);
+
+        let gates = pattern(outputs.get("gate").unwrap());
+        assert_eq!(gates[0..8], gates[8..16]);
+        assert_eq!(gates[0..8], gates[24..32]);
+        let cv = per_pulse(outputs.get("cv").unwrap());
+        assert_eq!(cv[0..8], cv[8..16]);
+
+        // Each bit is the previous step's bit 0 shifted along
+        let pulse2 = pattern(outputs.get("pulse2").unwrap());
+        assert_eq!(pulse2[1..32], gates[0..31]);
+    }
+
+    #[test]
+    fn test_turing_full_probability_inverts_loop() {
+        let mut turing = GraphTuring::new(8, 42);
+        turing.set_param("prob", 1.0).unwrap();
+        let outputs = process_module(&mut turing, &[("clock", pulse_train(16))], 64);
+
+        let gates = pattern(outputs.get("gate").unwrap());
+        let inverted: String =
+            gates[0..8].chars().map(|c| if c == 'x' { '.' } else { 'x' }).collect();
+        assert_eq!(gates[8..16], inverted);
+    }
+
+    #[test]
+    fn test_turing_seed_recall() {
+        let run = |seed: f32| {
+            let mut turing = GraphTuring::default();
+            turing.set_param("prob", 0.3).unwrap();
+            turing.set_param("seed", seed).unwrap();
+            let outputs = process_module(&mut turing, &[("clock", pulse_train(64))], 256);
+            per_pulse(outputs.get("cv").unwrap())
+        };
+
+        assert_eq!(run(7.0), run(7.0));
+        assert_ne!(run(7.0), run(8.0));
+    }
+
+    #[test]
+    fn test_turing_melody_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clk: clock 120
+            tm: turing 8 2024
+            tm.clock <- clk.eighth
+            tm.prob <- 0.1
+            tm.scale <- 440
+            vco: osc sine
+            vco.freq <- tm.cv + 110
+            out <- vco.sine
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Turing patch should load and run");
+
+        result.assert_signal_varied("tm", "cv").expect("Register CV should change");
+        result
+            .assert_signal_range("tm", "cv", 0.0, 440.0)
+            .expect("CV should follow scale");
+    }
+
+    #[test]
+    fn test_bernoulli_probability_extremes() {
+        let mut always_a = GraphBernoulli::new(0.0, BernoulliMode::Trigger);
+        let outputs = process_module(&mut always_a, &[("trig", pulse_train(8))], 32);
+        assert_eq!(pattern(outputs.get("a").unwrap()), "xxxxxxxx");
+        assert_eq!(pattern(outputs.get("b").unwrap()), "........");
+
+        let mut always_b = GraphBernoulli::new(1.0, BernoulliMode::Trigger);
+        let outputs = process_module(&mut always_b, &[("trig", pulse_train(8))], 32);
+        assert_eq!(pattern(outputs.get("b").unwrap()), "xxxxxxxx");
+    }
+
+    #[test]
+    fn test_bernoulli_distribution_and_seed() {
+        let run = |seed: f32| {
+            let mut bernoulli = GraphBernoulli::new(0.25, BernoulliMode::Trigger);
+            bernoulli.set_param("seed", seed).unwrap();
+            let outputs = process_module(&mut bernoulli, &[("trig", pulse_train(1000))], 4000);
+            pattern(outputs.get("b").unwrap())
+        };
+
+        let routed_b = run(99.0).chars().filter(|&c| c == 'x').count();
+        assert!((200..300).contains(&routed_b), "{routed_b} of 1000 routed to B");
+        assert_eq!(run(99.0), run(99.0));
+    }
+
+    #[test]
+    fn test_bernoulli_latch_and_toggle() {
+        let mut latch = GraphBernoulli::new(0.5, BernoulliMode::Latch);
+        let outputs = process_module(&mut latch, &[("trig", pulse_train(16))], 64);
+        let a = outputs.get("a").unwrap();
+        let b = outputs.get("b").unwrap();
+        // Exactly one side is held high at every sample once triggered
+        assert!(a.iter().zip(b).all(|(a, b)| a + b == 1.0));
+
+        let mut toggle = GraphBernoulli::new(1.0, BernoulliMode::Toggle);
+        let outputs = process_module(&mut toggle, &[("trig", pulse_train(6))], 24);
+        assert_eq!(pattern(outputs.get("a").unwrap()), ".x.x.x");
+        assert_eq!(pattern(outputs.get("b").unwrap()), "x.x.x.");
+    }
+
+    #[test]
+    fn test_bernoulli_drum_patch() {
+        let mut runner = TestRunner::new();
+
+        let patch = r"
+            clk: clock 120
+            coin: bernoulli 0.3 latch
+            coin.trig <- clk.sixteenth
+            hat_env: envelope 0.001 0.05
+            hat_env.gate <- coin.a
+            out <- hat_env.out
+        ";
+
+        let result = runner
+            .run_patch(patch, Duration::from_secs(2))
+            .expect("Bernoulli patch should load and run");
+
+        result.assert_signal_varied("coin", "a").expect("Some triggers should go to A");
+        result.assert_signal_range("coin", "b", 0.0, 1.0).expect("Outputs are gates");
+    }
+
+    #[test]
+    fn test_bernoulli_unknown_mode_is_rejected() {
+        let mut runner = TestRunner::new();
+        assert!(runner
+            .run_patch("coin: bernoulli 0.5 sometimes", Duration::from_millis(10))
+            .is_err());
+    }
+}
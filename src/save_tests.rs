@@ -0,0 +1,152 @@
+//! Tests for writing the live graph back out as a patch
+
+#[cfg(test)]
+mod tests {
+    use crate::graph_engine::GraphEngine;
+
+    const BLOCK: usize = 64;
+
+    const PATCH: &str = "
+clk: clock 120
+seq: seq8
+seq.clock <- clk.clock
+seq.step1 <- 100
+seq.step2 <- 200
+vco: osc saw 110
+vco.freq <- seq.cv * 2 + 10
+lfo: lfo 0.5
+vcf: filter 800 0.3
+vcf.audio <- vco.saw
+vcf.cutoff <- lfo.sine + 1 * 400 + 600
+out.left <- vcf.lp
+out.right <- vco.saw * 0.25
+";
+
+    fn render(engine: &GraphEngine, blocks: usize) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for _ in 0..blocks {
+            let (left, right) = engine.render_for_test(BLOCK);
+            samples.extend(left);
+            samples.extend(right);
+        }
+        samples
+    }
+
+    fn param(engine: &GraphEngine, module: &str, name: &str) -> Option<f32> {
+        engine.observer_manager_mut().get_module_mut(module).unwrap().get_param(name)
+    }
+
+    #[test]
+    fn test_patch_text_is_canonical() {
+        let mut engine = GraphEngine::new();
+        engine
+            .load_patch(
+                "vco: osc sine 220\nlfo: lfo 2\nvco.freq <- lfo.sine * 50 + 220\nout <- vco.sine",
+            )
+            .unwrap();
+        engine.process_line("lfo.freq <- 0.25").unwrap();
+
+        assert_eq!(
+            engine.patch_text().unwrap(),
+            "lfo: lfo 2\nlfo.freq <- 0.25\nvco: osc sine 220\n\n\
+             vco.freq <- lfo.sine * 50 + 220\nout <- vco.sine\n"
+        );
+    }
+
+    #[test]
+    fn test_tweaked_patch_round_trips() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch(PATCH).unwrap();
+
+        // Tweak it the way a REPL session would
+        engine.process_line("vcf.res <- 0.7").unwrap();
+        engine.process_line("seq.step1 <- 150").unwrap();
+        engine.process_line("vcf.cutoff <- lfo.sine * 300 + 900").unwrap();
+        engine.process_line("lfo.freq <- 0.125").unwrap();
+        let saved = engine.patch_text().unwrap();
+
+        let mut loaded = GraphEngine::new();
+        loaded.load_patch(&saved).unwrap();
+        assert_eq!(loaded.patch_text().unwrap(), saved);
+
+        let mut modules = loaded.list_modules();
+        modules.sort();
+        let mut expected = engine.list_modules();
+        expected.sort();
+        assert_eq!(modules, expected);
+        assert_eq!(param(&loaded, "vcf", "res"), Some(0.7));
+        assert_eq!(param(&loaded, "seq", "step1"), Some(150.0));
+        assert_eq!(param(&loaded, "lfo", "freq"), Some(0.125));
+
+        // Both play the same audio from the start
+        let mut fresh = GraphEngine::new();
+        fresh.load_patch(PATCH).unwrap();
+        for line in saved.lines().filter(|line| line.contains("<-")) {
+            fresh.process_line(line).unwrap();
+        }
+        let played = render(&loaded, 50);
+        assert!(played.iter().any(|v| *v != 0.0));
+        assert_eq!(played, render(&fresh, 50));
+    }
+
+    #[test]
+    fn test_save_after_reload_matches_the_new_patch() {
+        let mut engine = GraphEngine::new();
+        engine.load_patch(PATCH).unwrap();
+        engine.process_line("vcf.res <- 0.7").unwrap();
+        engine.process_line("lfo.freq <- 0.125").unwrap();
+        render(&engine, 10);
+
+        // The REPL tweaks aren't in the file, so the reload drops them
+        let edited = PATCH
+            .replace("filter 800 0.3", "filter 900 0.3")
+            .replace("seq.step2 <- 200", "seq.step2 <- 250\nseq.step3 <- 300");
+        engine.reload_patch(&edited).unwrap();
+        let saved = engine.patch_text().unwrap();
+
+        let mut loaded = GraphEngine::new();
+        loaded.load_patch(&edited).unwrap();
+        assert_eq!(saved, loaded.patch_text().unwrap());
+        for (module, name) in [("vcf", "cutoff"), ("vcf", "res"), ("lfo", "freq"), ("seq", "step3")]
+        {
+            assert_eq!(param(&engine, module, name), param(&loaded, module, name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_save_leaves_out_momentary_actions() {
+        let mut engine = GraphEngine::new();
+        engine
+            .load_patch("clk: clock 120\ndiv: clockdiv 4\ndiv.clock <- clk.clock")
+            .unwrap();
+        for line in ["clk.tap <- 1", "clk.reset <- 1", "clk.swing <- 0.25", "div.reset <- 1"] {
+            engine.process_line(line).unwrap();
+        }
+
+        // Taps and resets happened once; loading the file mustn't do them again
+        assert_eq!(
+            engine.patch_text().unwrap(),
+            "clk: clock 120\nclk.swing <- 0.25\ndiv: clockdiv 4\n\ndiv.clock <- clk.clock\n"
+        );
+    }
+
+    #[test]
+    fn test_save_writes_a_loadable_file() {
+        let path = std::env::temp_dir().join("zim_test_save_patch.zim");
+        let mut engine = GraphEngine::new();
+        engine
+            .load_patch("gate: manual\nviz: osc_out 127.0.0.1:9001 gate.gate=/button 20")
+            .unwrap();
+        engine.save_patch(&path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(text, "gate: gate\n\nviz: osc_out 20 127.0.0.1:9001 gate.gate=/button\n");
+
+        let mut loaded = GraphEngine::new();
+        loaded.load_patch(&text).unwrap();
+        assert_eq!(loaded.patch_text().unwrap(), text);
+
+        assert!(engine.save_patch(std::env::temp_dir().join("no_such_dir/x.zim")).is_err());
+    }
+}
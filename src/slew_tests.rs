@@ -2,7 +2,9 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::test_framework::TestRunner;
+    use crate::graph::GraphModule;
+    use crate::graph_modules::{GraphSlewGen, SlewCurve};
+    use crate::test_framework::{process_module, TestRunner};
     use std::time::Duration;
 
     #[test]
@@ -261,4 +263,129 @@ mod tests {
             "Self-patching triangle should have reasonable amplitude range (got {range})"
         );
     }
+
+    /// Sample indices where a gate goes from low to high
+    fn rising_edges(signal: &[f32]) -> Vec<usize> {
+        (1..signal.len()).filter(|&i| signal[i] > 0.5 && signal[i - 1] <= 0.5).collect()
+    }
+
+    fn pulse_at(len: usize, positions: &[usize]) -> Vec<f32> {
+        let mut signal = vec![0.0; len];
+        for &position in positions {
+            signal[position] = 1.0;
+        }
+        signal
+    }
+
+    #[test]
+    fn test_slew_trigger_fires_full_cycle() {
+        let len = 4410;
+        let mut slew = GraphSlewGen::new(0.01, 0.02);
+        let outputs = process_module(&mut slew, &[("trig", pulse_at(len, &[0]))], len);
+        let out = outputs.get("out").unwrap();
+        let eor = outputs.get("eor").unwrap();
+        let eoc = outputs.get("eoc").unwrap();
+
+        // Linear rise to the peak in 10ms, then back down in 20ms
+        assert!((out[220] - 0.5).abs() < 0.01, "halfway up {}", out[220]);
+        assert_eq!(out[440], 1.0);
+        assert!((out[440 + 441] - 0.5).abs() < 0.01, "halfway down {}", out[881]);
+        assert_eq!(out[1400], 0.0);
+        assert_eq!(out[len - 1], 0.0);
+
+        // One end of rise and one end of cycle, no retriggering
+        assert_eq!(rising_edges(eor), vec![440]);
+        assert_eq!(rising_edges(eoc), vec![1322]);
+    }
+
+    #[test]
+    fn test_slew_retrigger_restarts_rise() {
+        let len = 4410;
+        let mut slew = GraphSlewGen::new(0.01, 0.02);
+        // Second trigger lands during the rise and is ignored, third during the fall
+        let outputs = process_module(&mut slew, &[("trig", pulse_at(len, &[0, 200, 882]))], len);
+        let out = outputs.get("out").unwrap();
+        let eor = outputs.get("eor").unwrap();
+
+        // Restarting from halfway takes half the rise time to reach the peak again
+        assert!(out[883] > out[882]);
+        assert_eq!(rising_edges(eor), vec![440, 1102]);
+    }
+
+    #[test]
+    fn test_slew_cycle_self_oscillates() {
+        let len = 44100;
+
+        // 10ms up and 15ms down makes a 40Hz oscillator
+        let mut slew = GraphSlewGen::new(0.01, 0.015).with_cycle(true);
+        let outputs = process_module(&mut slew, &[], len);
+        let cycles = rising_edges(outputs.get("eoc").unwrap()).len();
+        assert!((39..=40).contains(&cycles), "cycles {cycles}");
+
+        // Cycle input gates the oscillation
+        let mut slew = GraphSlewGen::new(0.01, 0.015);
+        let mut gate = vec![1.0; len];
+        gate[len / 2..].fill(0.0);
+        let outputs = process_module(&mut slew, &[("cycle", gate)], len);
+        let eoc = rising_edges(outputs.get("eoc").unwrap());
+        assert!((19..=21).contains(&eoc.len()), "cycles {}", eoc.len());
+        assert!(eoc.iter().all(|&edge| edge < len / 2 + 1103));
+
+        // Cycle parameter matches the patch syntax
+        let mut slew = GraphSlewGen::default();
+        slew.set_param("cycle", 1.0).unwrap();
+        assert_eq!(slew.get_param("cycle"), Some(1.0));
+    }
+
+    #[test]
+    fn test_slew_curve_morphs_segments() {
+        let len = 441;
+        let halfway = |curve: f32, curve_cv: f32| {
+            let mut slew = GraphSlewGen::new(0.01, 0.01);
+            slew.set_param("curve", curve).unwrap();
+            let outputs = process_module(
+                &mut slew,
+                &[("trig", pulse_at(len, &[0])), ("curve", vec![curve_cv; len])],
+                len,
+            );
+            outputs.get("out").unwrap()[220]
+        };
+
+        // Exponential is fast at the start, logarithmic slow
+        assert!(halfway(1.0, 0.0) > 0.8);
+        assert!((halfway(0.0, 0.0) - 0.5).abs() < 0.01);
+        assert!(halfway(-1.0, 0.0) < 0.2);
+        assert!(halfway(0.5, 0.0) > 0.5 && halfway(0.5, 0.0) < halfway(1.0, 0.0));
+
+        // CV adds to the parameter
+        assert_eq!(halfway(0.5, 0.5), halfway(1.0, 0.0));
+        assert_eq!(halfway(0.0, -1.0), halfway(-1.0, 0.0));
+
+        // Named curves and the old numeric code for logarithmic
+        let mut slew = GraphSlewGen::default().with_curve(SlewCurve::Exponential);
+        assert_eq!(slew.get_param("curve"), Some(1.0));
+        slew.set_param("curve", 2.0).unwrap();
+        assert_eq!(slew.get_param("curve"), Some(-1.0));
+    }
+
+    #[test]
+    fn test_krell_patch_runs_without_clocks() {
+        let mut runner = TestRunner::new();
+        let result = runner
+            .run_patch_file("examples/slew/krell_patch.zim", Duration::from_secs(4))
+            .expect("Krell patch should load and run");
+
+        let cycles = result.gate_fire_count("main_slew", "eoc");
+        assert!(cycles >= 3, "Cycling slew should keep clocking itself (got {cycles})");
+        assert!(
+            result.gate_fire_count("pluck_env", "eoc") >= 3,
+            "Plucks should follow the rises"
+        );
+        result
+            .assert_signal_varied("pitch_sh", "out")
+            .expect("Each cycle should pick a new pitch");
+        result
+            .assert_signal_range("mixer", "out", -1.0, 1.0)
+            .expect("Mix should stay in range");
+    }
 }
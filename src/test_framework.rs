@@ -2,6 +2,7 @@
 //!
 //! Provides utilities for testing module behavior and signal flow
 
+use crate::graph::{GraphModule, PortBuffers};
 use crate::graph_engine::GraphEngine;
 use crate::observability::{ObservationCollector, ObserverManager, SignalObserver};
 use std::sync::{Arc, Mutex};
@@ -181,6 +182,8 @@ impl TestRunner {
         let content = std::fs::read_to_string(path)
             .map_err(|e| format!("Failed to read patch file {path}: {e}"))?;
 
+        // Files the patch refers to are found next to it, as on the command line
+        self.engine = GraphEngine::new_with_patch_context(Some(path));
         self.run_patch(&content, duration)
     }
 }
@@ -191,6 +194,38 @@ impl Default for TestRunner {
     }
 }
 
+/// Run a single module for one buffer with the given input signals
+///
+/// Unlisted inputs keep their port defaults. Useful for sample-exact checks
+/// that the sampled observer data can't resolve.
+///
+/// # Panics
+/// Panics if a signal names a port the module doesn't have, or its length
+/// differs from `sample_count`
+#[allow(dead_code)] // Test framework API
+pub fn process_module(
+    module: &mut dyn GraphModule,
+    signals: &[(&str, Vec<f32>)],
+    sample_count: usize,
+) -> PortBuffers {
+    let mut inputs = PortBuffers::new();
+    let mut outputs = PortBuffers::new();
+    for port in module.inputs() {
+        inputs.get_or_default(&port.name, sample_count, port.default_value);
+    }
+    for port in module.outputs() {
+        outputs.get_or_default(&port.name, sample_count, 0.0);
+    }
+    for (port, values) in signals {
+        inputs
+            .get_mut(port)
+            .expect("module should have input port")
+            .copy_from_slice(values);
+    }
+    module.process(&inputs, &mut outputs, sample_count);
+    outputs
+}
+
 /// Wrapper for shared observation collection in tests
 #[allow(dead_code)] // Test framework helper
 struct SharedObservationCollector {
@@ -0,0 +1,426 @@
+//! Wavetable storage for the wavetable oscillator
+//!
+//! Tables are loaded from WAV files (single-cycle or multi-frame) or generated
+//! from one of the built-in sets. Every frame is resampled to a fixed table size
+//! and stored as a set of band-limited mipmaps, one per octave, so the
+//! oscillator can pick a level with no harmonics above Nyquist.
+
+#![allow(clippy::cast_precision_loss)]
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_sign_loss)]
+
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/// Number of samples in every stored table
+pub const TABLE_SIZE: usize = 2048;
+
+/// Number of mipmap levels (level 0 keeps 1024 harmonics, the last keeps 1)
+pub const MIP_LEVELS: usize = 11;
+
+/// Maximum number of frames kept from a multi-frame table
+pub const MAX_FRAMES: usize = 256;
+
+/// Frame size assumed for multi-frame WAV files (Serum-style tables)
+pub const DEFAULT_FRAME_SIZE: usize = 2048;
+
+/// Names of the built-in wavetable sets
+pub const BUILTIN_TABLES: [&str; 4] = ["sine", "basic", "pwm", "harmonics"];
+
+/// A morphable set of single-cycle frames with band-limited mipmaps
+pub struct Wavetable {
+    // frames[frame][level][sample]
+    frames: Vec<Vec<Vec<f32>>>,
+}
+
+impl Wavetable {
+    /// Build a wavetable from raw samples split into frames of `frame_size`
+    ///
+    /// # Errors
+    /// Returns an error if there are no samples or the frame size is zero
+    pub fn from_samples(samples: &[f32], frame_size: usize) -> Result<Self> {
+        if samples.is_empty() {
+            return Err(anyhow!("Wavetable has no samples"));
+        }
+        if frame_size == 0 {
+            return Err(anyhow!("Wavetable frame size must be greater than zero"));
+        }
+
+        let mut frames: Vec<Vec<Vec<f32>>> = samples
+            .chunks_exact(frame_size)
+            .take(MAX_FRAMES)
+            .map(|frame| build_mipmaps(&resample(frame, TABLE_SIZE)))
+            .collect();
+
+        if frames.is_empty() {
+            return Err(anyhow!(
+                "Wavetable has {} samples, fewer than one frame of {frame_size}",
+                samples.len()
+            ));
+        }
+
+        // Normalize the whole table so morphing doesn't change loudness abruptly
+        let peak = frames
+            .iter()
+            .flat_map(|levels| levels[0].iter())
+            .fold(0.0_f32, |peak, &s| peak.max(s.abs()));
+        if peak > 0.0 {
+            for sample in frames.iter_mut().flatten().flatten() {
+                *sample /= peak;
+            }
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// Generate one of the built-in wavetable sets
+    ///
+    /// # Errors
+    /// Returns an error if the name is not a built-in table
+    pub fn builtin(name: &str) -> Result<Self> {
+        let shapes: Vec<Vec<f32>> = match name {
+            "sine" => vec![shape(|p| (p * std::f32::consts::TAU).sin())],
+            "basic" => vec![
+                shape(|p| (p * std::f32::consts::TAU).sin()),
+                shape(|p| if p < 0.5 { p * 4.0 - 1.0 } else { 3.0 - p * 4.0 }),
+                shape(|p| p * 2.0 - 1.0),
+                shape(|p| if p < 0.5 { 1.0 } else { -1.0 }),
+            ],
+            "pwm" => (0..8)
+                .map(|i| {
+                    let width = 0.5 - i as f32 * 0.06;
+                    shape(|p| if p < width { 1.0 } else { -1.0 })
+                })
+                .collect(),
+            "harmonics" => (1..=8)
+                .map(|count| {
+                    shape(|p| {
+                        (1..=count)
+                            .map(|n| (p * std::f32::consts::TAU * n as f32).sin() / n as f32)
+                            .sum()
+                    })
+                })
+                .collect(),
+            _ => {
+                return Err(anyhow!(
+                    "Unknown built-in wavetable: {name} (available: {})",
+                    BUILTIN_TABLES.join(", ")
+                ))
+            }
+        };
+
+        Self::from_samples(&shapes.concat(), TABLE_SIZE)
+    }
+
+    /// Load a wavetable from a WAV file
+    ///
+    /// Files whose length is a multiple of `frame_size` are treated as
+    /// multi-frame tables; anything else is a single cycle.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not a supported WAV
+    pub fn from_wav_file<P: AsRef<Path>>(path: P, frame_size: Option<usize>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow!("Failed to read wavetable {}: {e}", path.display()))?;
+        let samples = parse_wav(&bytes)?;
+
+        let frame_size = match frame_size {
+            Some(size) => size,
+            None if samples.len() > DEFAULT_FRAME_SIZE
+                && samples.len() % DEFAULT_FRAME_SIZE == 0 =>
+            {
+                DEFAULT_FRAME_SIZE
+            }
+            None => samples.len(),
+        };
+
+        Self::from_samples(&samples, frame_size)
+    }
+
+    /// Number of frames available for morphing
+    #[must_use]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Pick the mipmap level whose highest harmonic stays below Nyquist
+    #[must_use]
+    pub fn mip_level(frequency: f32, sample_rate: f32) -> usize {
+        let frequency = frequency.abs();
+        if frequency <= 0.0 {
+            return 0;
+        }
+        let max_harmonics = (TABLE_SIZE / 2) as f32;
+        let allowed = sample_rate * 0.5 / frequency;
+        let level = (max_harmonics / allowed).log2().ceil();
+        if level <= 0.0 {
+            0
+        } else {
+            (level as usize).min(MIP_LEVELS - 1)
+        }
+    }
+
+    /// Read the table at a morph position (0..1), phase (0..1) and mip level
+    #[must_use]
+    pub fn sample(&self, position: f32, phase: f32, level: usize) -> f32 {
+        let level = level.min(MIP_LEVELS - 1);
+        let frame_pos = position.clamp(0.0, 1.0) * (self.frames.len() - 1) as f32;
+        let frame_a = frame_pos as usize;
+        let frame_b = (frame_a + 1).min(self.frames.len() - 1);
+        let frame_frac = frame_pos - frame_a as f32;
+
+        let a = read_table(&self.frames[frame_a][level], phase);
+        if frame_frac <= 0.0 || frame_a == frame_b {
+            return a;
+        }
+        let b = read_table(&self.frames[frame_b][level], phase);
+        a + (b - a) * frame_frac
+    }
+}
+
+/// Sample a naive waveform shape across one table cycle
+fn shape<F: Fn(f32) -> f32>(f: F) -> Vec<f32> {
+    (0..TABLE_SIZE).map(|i| f(i as f32 / TABLE_SIZE as f32)).collect()
+}
+
+/// Linear-interpolated table lookup with wrap-around
+fn read_table(table: &[f32], phase: f32) -> f32 {
+    let pos = phase.rem_euclid(1.0) * table.len() as f32;
+    let index = (pos as usize) % table.len();
+    let next = (index + 1) % table.len();
+    let frac = pos - pos.floor();
+    table[index] + (table[next] - table[index]) * frac
+}
+
+/// Resample one cycle to `size` samples with linear interpolation
+fn resample(frame: &[f32], size: usize) -> Vec<f32> {
+    if frame.len() == size {
+        return frame.to_vec();
+    }
+    (0..size).map(|i| read_table(frame, i as f32 / size as f32)).collect()
+}
+
+/// Build one band-limited table per octave from a single cycle
+fn build_mipmaps(cycle: &[f32]) -> Vec<Vec<f32>> {
+    let mut re = cycle.to_vec();
+    let mut im = vec![0.0; cycle.len()];
+    fft(&mut re, &mut im, false);
+
+    // Remove DC so morphing and feedback patches don't drift
+    re[0] = 0.0;
+    im[0] = 0.0;
+
+    (0..MIP_LEVELS)
+        .map(|level| {
+            let max_harmonic = (TABLE_SIZE / 2) >> level;
+            let mut level_re = re.clone();
+            let mut level_im = im.clone();
+            for bin in (max_harmonic + 1)..=(TABLE_SIZE - max_harmonic - 1) {
+                level_re[bin] = 0.0;
+                level_im[bin] = 0.0;
+            }
+            fft(&mut level_re, &mut level_im, true);
+            level_re
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT (length must be a power of two)
+fn fft(re: &mut [f32], im: &mut [f32], inverse: bool) {
+    let n = re.len();
+    debug_assert!(n.is_power_of_two() && im.len() == n);
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * std::f64::consts::TAU / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let (w_re, w_im) = (w_re as f32, w_im as f32);
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f32;
+        for (r, i) in re.iter_mut().zip(im.iter_mut()) {
+            *r *= scale;
+            *i *= scale;
+        }
+    }
+}
+
+/// Decode a RIFF/WAVE file to mono f32 samples (first channel only)
+///
+/// Supports 8/16/24/32-bit integer PCM and 32/64-bit float data.
+///
+/// # Errors
+/// Returns an error if the data is not a supported WAV file
+pub fn parse_wav(bytes: &[u8]) -> Result<Vec<f32>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(anyhow!("Not a RIFF/WAVE file"));
+    }
+
+    let mut format: Option<(u16, u16, u16)> = None; // (format tag, channels, bits)
+    let mut offset = 12;
+
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes([
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]) as usize;
+        let body_start = offset + 8;
+        let body_end = (body_start + size).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        match id {
+            b"fmt " => {
+                if body.len() < 16 {
+                    return Err(anyhow!("WAV fmt chunk is too short"));
+                }
+                let mut tag = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                // WAVE_FORMAT_EXTENSIBLE stores the real tag in the sub-format GUID
+                if tag == 0xFFFE && body.len() >= 26 {
+                    tag = u16::from_le_bytes([body[24], body[25]]);
+                }
+                format = Some((tag, channels.max(1), bits));
+            }
+            b"data" => {
+                let (tag, channels, bits) =
+                    format.ok_or_else(|| anyhow!("WAV data chunk before fmt chunk"))?;
+                return decode_samples(body, tag, channels as usize, bits);
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even number of bytes
+        offset = body_start + size + (size & 1);
+    }
+
+    Err(anyhow!("WAV file has no data chunk"))
+}
+
+fn decode_samples(data: &[u8], tag: u16, channels: usize, bits: u16) -> Result<Vec<f32>> {
+    let bytes_per_sample = usize::from(bits / 8);
+    if bytes_per_sample == 0 {
+        return Err(anyhow!("Unsupported WAV bit depth: {bits}"));
+    }
+    let frame_bytes = bytes_per_sample * channels;
+
+    let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+        (1, 8) => |b| (f32::from(b[0]) - 128.0) / 128.0,
+        (1, 16) => |b| f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0,
+        (1, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0,
+        (1, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+        (3, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (3, 64) => |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+        _ => return Err(anyhow!("Unsupported WAV format {tag} with {bits} bits")),
+    };
+
+    Ok(data
+        .chunks_exact(frame_bytes)
+        .map(|frame| decode(&frame[..bytes_per_sample]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav_16bit(samples: &[i16]) -> Vec<u8> {
+        let data_len = (samples.len() * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&44100u32.to_le_bytes());
+        bytes.extend_from_slice(&88200u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for s in samples {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_16bit_wav() {
+        let samples = parse_wav(&wav_16bit(&[0, 16384, -16384, 32767])).unwrap();
+        assert_eq!(samples.len(), 4);
+        assert!((samples[1] - 0.5).abs() < 1e-4);
+        assert!((samples[2] + 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_rejects_non_wav() {
+        assert!(parse_wav(b"not a wav file at all").is_err());
+    }
+
+    #[test]
+    fn test_single_cycle_is_resampled() {
+        let cycle: Vec<f32> =
+            (0..600).map(|i| (i as f32 / 600.0 * std::f32::consts::TAU).sin()).collect();
+        let table = Wavetable::from_samples(&cycle, cycle.len()).unwrap();
+        assert_eq!(table.frame_count(), 1);
+        assert!((table.sample(0.0, 0.25, 0) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_mipmaps_remove_high_harmonics() {
+        let table = Wavetable::builtin("basic").unwrap();
+        assert_eq!(table.frame_count(), 4);
+
+        // The top level of the saw frame keeps only the fundamental
+        let saw = 2.0 / 3.0;
+        let top = MIP_LEVELS - 1;
+        let peak = (0..256)
+            .map(|i| table.sample(saw, i as f32 / 256.0, top).abs())
+            .fold(0.0_f32, f32::max);
+        let quarter = table.sample(saw, 0.25, top);
+        assert!((quarter.abs() - peak).abs() < 0.01, "top mip level should be a pure sine");
+    }
+
+    #[test]
+    fn test_mip_level_selection() {
+        assert_eq!(Wavetable::mip_level(20.0, 44100.0), 0);
+        assert!(Wavetable::mip_level(5000.0, 44100.0) > 5);
+        assert_eq!(Wavetable::mip_level(20000.0, 44100.0), MIP_LEVELS - 1);
+    }
+}